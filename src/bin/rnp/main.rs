@@ -1,8 +1,8 @@
 use futures_intrusive::sync::ManualResetEvent;
-use rnp::{PingRunnerCore, RNP_ABOUT, RNP_AUTHOR, RNP_NAME, RNP_QUIET_LEVEL_NO_OUTPUT};
+use rnp::{PingRunnerCore, PingTracer, RnpPingRunnerConfig, RNP_ABOUT, RNP_AUTHOR, RNP_NAME, RNP_QUIET_LEVEL_NO_OUTPUT};
 use rnp_cli_options::RnpCliOptions;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use structopt::StructOpt;
 use tokio::runtime::Runtime;
 
 mod rnp_cli_options;
@@ -11,7 +11,12 @@ mod rnp_cli_options;
 fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
-    let mut opts = RnpCliOptions::from_args();
+    let mut opts = RnpCliOptions::from_args_with_config_file();
+    if opts.config_options.dump_config {
+        print!("{}", opts.to_config_string());
+        return;
+    }
+
     if opts.output_options.quiet_level < RNP_QUIET_LEVEL_NO_OUTPUT {
         println!("{} - {} - {}\n", RNP_NAME, RNP_AUTHOR, RNP_ABOUT);
     }
@@ -19,10 +24,14 @@ fn main() {
     opts.prepare_to_use();
     let runner_config = opts.to_ping_runner_config();
 
+    if opts.trace_options.is_trace_mode {
+        trace_targets(&runner_config, opts.trace_options.trace_max_hops);
+        return;
+    }
+
     let rt = Runtime::new().unwrap();
     let result = rt.block_on(async {
         let stop_event = Arc::new(ManualResetEvent::new(false));
-        let rnp_exit_failure_reason = runner_config.result_processor_config.exit_failure_reason.clone();
         let mut runner = PingRunnerCore::new(runner_config, stop_event.clone());
 
         ctrlc::set_handler(move || {
@@ -34,12 +43,9 @@ fn main() {
         runner.run_warmup_pings().await;
 
         runner.start_running_normal_pings();
-        runner.join().await;
-
-        if let Some(rnp_exit_failure_reason) = rnp_exit_failure_reason {
-            if rnp_exit_failure_reason.lock().unwrap().is_some() {
-                return Err("Ping failed!".to_string());
-            }
+        let summary = runner.join().await;
+        if summary.exit_failure_reason.is_some() {
+            return Err("Ping failed!".to_string());
         }
         return Ok(());
     });
@@ -49,3 +55,29 @@ fn main() {
         std::process::exit(1);
     }
 }
+
+#[cfg(not(tarpaulin_include))]
+fn trace_targets(runner_config: &RnpPingRunnerConfig, max_hop_count: u32) {
+    let tracer = PingTracer::new(&runner_config.worker_config.ping_client_config);
+
+    // All hops are traced with the first source port and the first source IP in the same IP family as the target, so we can
+    // tell which path the pings from them take.
+    let source_port = *runner_config.worker_scheduler_config.source_ports.ranges[0].start();
+    for target in &runner_config.worker_config.targets {
+        let source_ip = match runner_config.worker_config.source_ips.iter().find(|source_ip| source_ip.is_ipv4() == target.is_ipv4()) {
+            Some(source_ip) => *source_ip,
+            None => {
+                println!("Failed to trace {:?}: no source IP in the same IP family.", target);
+                std::process::exit(1);
+            }
+        };
+
+        let source = SocketAddr::new(source_ip, source_port);
+        println!("Tracing TCP {:?} from {:?} with max {} hops:", target, source, max_hop_count);
+        if let Err(e) = tracer.trace(&source, target, max_hop_count, |hop| println!("{}", hop)) {
+            println!("Failed to trace {:?}: {}", target, e);
+            std::process::exit(1);
+        }
+        println!();
+    }
+}
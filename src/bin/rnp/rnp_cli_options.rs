@@ -1,100 +1,274 @@
 use rand::Rng;
 use rnp::{
-    parse_ping_target, PingClientConfig, PingResultProcessorCommonConfig, PingResultProcessorConfig, PingWorkerConfig, PingWorkerSchedulerConfig,
-    PortRangeList, RnpPingRunnerConfig, RnpSupportedProtocol,
+    load_ping_targets_from_file, parse_dscp, parse_fwmark, parse_http_header, parse_ping_rate, PingClientConfig, PingFlowLabel,
+    PingPortPickingStrategy, PingResultProcessorCommonConfig, PingResultProcessorConfig, PingRunDuration, PingSourceIpList, PingTargetList,
+    PingWorkerConfig, PingWorkerSchedulerConfig, PortRangeList, RnpPingRunnerConfig, RnpSupportedProtocol,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
 
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use structopt::clap::ArgMatches;
 use structopt::StructOpt;
 
-#[derive(Debug, StructOpt, PartialEq)]
-#[structopt(name = rnp::RNP_NAME, author = rnp::RNP_AUTHOR, about = rnp::RNP_ABOUT)]
+const RNP_CLI_TRACE_MODE_NAME: &str = "trace";
+
+// All options are flattened when saving to config file, and the config keys are the same as the argument names in clap
+// (field names in kebab-case), so we can tell whether an option is specified in command line by its config key.
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[structopt(
+    name = rnp::RNP_NAME,
+    author = rnp::RNP_AUTHOR,
+    about = rnp::RNP_ABOUT,
+    after_help = "MODES:\n    rnp trace <targets> [options]    Trace the path to the targets with TCP SYNs instead of pinging them. The first source port is used for all hops, so the trace follows the same ECMP path as the pings from that port. Takes the same options as ping. Only supported on Linux."
+)]
 pub struct RnpCliOptions {
     #[structopt(flatten)]
+    #[serde(flatten)]
     pub common_options: RnpCliCommonOptions,
 
     #[structopt(flatten)]
+    #[serde(flatten)]
     pub ping_common_options: RnpCliPingCommonOptions,
 
     #[structopt(flatten)]
+    #[serde(flatten)]
     pub output_options: RnpCliOutputOptions,
 
     #[structopt(flatten)]
+    #[serde(flatten)]
     pub quic_options: RnpCliQuicPingOptions,
+
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub http_options: RnpCliHttpPingOptions,
+
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub trace_options: RnpCliTraceOptions,
+
+    // Must be the last one, because it collects all config values that are not taken by the options above.
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub config_options: RnpCliConfigOptions,
 }
 
-#[derive(Debug, StructOpt, PartialEq)]
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RnpCliCommonOptions {
-    #[structopt(short = "m", long = "mode", default_value = "TCP", help = "Specify protocol to use.")]
+    #[structopt(name = "mode", short = "m", long = "mode", default_value = "TCP", help = "Specify protocol to use.")]
+    #[serde(rename = "mode")]
     pub protocol: RnpSupportedProtocol,
 
-    #[structopt(parse(try_from_str = parse_ping_target), help = "Target endpoint. For IPv6, please use [] to wrap the address, such as [::1]:80.")]
-    pub target: SocketAddr,
+    #[structopt(
+        required_unless_one = &["targets-file", "config-file", "dump-config"],
+        help = "Target endpoints separated by comma. For IPv6, please use [] to wrap the address, such as [::1]:80.\nCIDR and port ranges are also supported, such as 10.0.0.0/28:443 or 10.0.0.1:8080-8090."
+    )]
+    pub targets: Option<PingTargetList>,
+
+    #[structopt(
+        long = "targets-file",
+        parse(from_os_str),
+        help = "Load target endpoints from a file. Each line can contain the same targets as the command line, and anything after # is ignored."
+    )]
+    pub targets_file: Option<PathBuf>,
 }
 
-#[derive(Debug, StructOpt, PartialEq)]
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RnpCliPingCommonOptions {
-    #[structopt(short = "s", long = "src-ip", default_value = "0.0.0.0", help = "Source IP address.")]
-    pub source_ip: IpAddr,
+    #[structopt(
+        name = "src-ip",
+        short = "s",
+        long = "src-ip",
+        default_value = "0.0.0.0",
+        help = "Source IP addresses separated by comma. CIDR is also supported, such as 10.0.1.0/29.\nWith multiple source IPs, every target is pinged from each of them in rotation."
+    )]
+    #[serde(rename = "src-ip")]
+    pub source_ip: PingSourceIpList,
 
     #[structopt(
+        name = "src-ports",
         long = "src-ports",
         alias = "sp",
         help = "Source port ranges to rotate in ping. Format: port,start-end. Example: 1024,10000-11000. [alias: --sp]"
     )]
+    #[serde(rename = "src-ports")]
     pub source_ports: Option<PortRangeList>,
 
-    #[structopt(short = "n", long = "count", default_value = "4", help = "Ping count.")]
+    #[structopt(
+        name = "port-picking",
+        long = "port-picking",
+        default_value = "sequential",
+        help = "Strategy of picking source ports from the port ranges: sequential, random (with replacement), shuffle (a new random order in each cycle) or stride:<step>."
+    )]
+    #[serde(rename = "port-picking")]
+    pub port_picking_strategy: PingPortPickingStrategy,
+
+    #[structopt(
+        name = "port-cooldown",
+        long = "port-cooldown",
+        default_value = "0",
+        help = "Minimum time in milliseconds before reusing the same source port, e.g. 60000 to avoid reusing ports in TIME_WAIT on Linux. Set to 0 to disable."
+    )]
+    #[serde(rename = "port-cooldown")]
+    pub port_cooldown_in_ms: u64,
+
+    #[structopt(
+        name = "cross-product",
+        long = "cross-product",
+        help = "Ping every target from every source port, by moving each source port on to the next target in every round of the source ports, instead of only spreading the targets over the source ports.\nUseful with a target port range, e.g. 10.0.0.1:8000-8100, to cover every pair of source and destination ports."
+    )]
+    #[serde(rename = "cross-product")]
+    pub use_port_cross_product: bool,
+
+    #[structopt(name = "count", short = "n", long = "count", default_value = "4", help = "Ping count.")]
+    #[serde(rename = "count")]
     pub ping_count: u32,
 
     #[structopt(short = "t", help = "Ping until stopped.")]
     pub ping_until_stopped: bool,
 
-    #[structopt(long = "warmup", default_value = "0", help = "Warm up ping count.")]
+    #[structopt(
+        long = "duration",
+        help = "Stop the pings after the specified duration, e.g. 30s, 10m or 1h30m. Warmup pings are not counted. Use with -t to ping until the duration ends, otherwise it stops at whichever of the ping count and the duration comes first."
+    )]
+    pub duration: Option<PingRunDuration>,
+
+    #[structopt(name = "warmup", long = "warmup", default_value = "0", help = "Warm up ping count.")]
+    #[serde(rename = "warmup")]
     pub warmup_count: u32,
 
-    #[structopt(short = "w", long = "timeout", default_value = "2000", help = "Wait time for each ping in milliseconds.")]
+    #[structopt(name = "timeout", short = "w", long = "timeout", default_value = "2000", help = "Wait time for each ping in milliseconds.")]
+    #[serde(rename = "timeout")]
     pub wait_timeout_in_ms: u32,
 
-    #[structopt(short = "i", long = "interval", default_value = "1000", help = "Sleep between each ping in milliseconds.")]
+    #[structopt(name = "interval", short = "i", long = "interval", default_value = "1000", help = "Sleep between each ping in milliseconds.")]
+    #[serde(rename = "interval")]
     pub ping_interval_in_ms: u32,
 
-    #[structopt(long = "ttl", help = "Time to live.")]
+    #[structopt(name = "ttl", long = "ttl", help = "Time to live.")]
+    #[serde(rename = "ttl")]
     pub time_to_live: Option<u32>,
 
+    #[structopt(
+        long = "flow-label",
+        help = "IPv6 flow label of the pings: a fixed label, such as 12345 or 0x3039, or \"rotate\" to use a different label in each ping, for scanning the ECMP paths hashed on flow labels.\nOnly works with IPv6 targets in TCP, TLS, HTTP and UDP mode on Linux."
+    )]
+    pub flow_label: Option<PingFlowLabel>,
+
+    #[structopt(
+        long = "tos",
+        conflicts_with = "dscp",
+        help = "Type of service byte of the pings, i.e. the TOS in IPv4 header or the traffic class in IPv6 header, e.g. 184 for DSCP EF. Setting it on IPv6 pings is only supported on Linux. Not supported in QUIC mode."
+    )]
+    pub tos: Option<u8>,
+
+    #[structopt(
+        long = "dscp",
+        parse(try_from_str = parse_dscp),
+        help = "DSCP of the pings, as a number from 0 to 63 or a class name, e.g. ef, af41 or cs1. The ECN bits are left as 0. Same as --tos with the value shifted by 2 bits. Not supported in QUIC mode."
+    )]
+    pub dscp: Option<u8>,
+
+    #[structopt(
+        long = "interface",
+        help = "Network interface to send the pings from, e.g. eth1 or a VRF device, regardless of the routing of the source IP. Only supported on Linux."
+    )]
+    pub interface: Option<String>,
+
+    #[structopt(
+        long = "fwmark",
+        parse(try_from_str = parse_fwmark),
+        help = "Firewall mark of the pings, such as 100 or 0x64, for picking the egress path with policy routing. Only supported on Linux and requires CAP_NET_ADMIN."
+    )]
+    pub fwmark: Option<u32>,
+
     #[structopt(
         short = "d",
         long = "check-disconnect",
-        help = "Check if connection can be correctly disconnected. Only available in TCP mode now.\nWhen enabled, we will use normal disconnect (w/ FIN) and check the connection disconnect."
+        help = "Check if connection can be correctly disconnected. Only available in TCP and TLS mode now.\nWhen enabled, we will use normal disconnect (w/ FIN) and check the connection disconnect."
     )]
     pub check_disconnect: bool,
 
     #[structopt(
+        name = "wait-before-disconnect",
         long = "wait-before-disconnect",
         default_value = "0",
         help = "Wait before disconnect in milliseconds. Only works when check-disconnect is enabled."
     )]
+    #[serde(rename = "wait-before-disconnect")]
     pub wait_before_disconnect_in_ms: u64,
 
     #[structopt(
+        name = "disconnect-timeout",
         long = "disconnect-timeout",
         alias = "dt",
         default_value = "2000",
         help = "After initiate disconnect, wait for specified milliseconds to check if disconnect can be successfully completed. [alias: --dt]"
     )]
+    #[serde(rename = "disconnect-timeout")]
     pub disconnect_timeout_in_ms: u64,
 
-    #[structopt(short = "p", long = "parallel", default_value = "1", help = "Count of pings running in parallel.")]
+    #[structopt(name = "parallel", short = "p", long = "parallel", default_value = "1", help = "Count of pings running in parallel.")]
+    #[serde(rename = "parallel")]
     pub parallel_ping_count: u32,
 
+    #[structopt(
+        name = "reprobe",
+        long = "reprobe",
+        default_value = "0",
+        help = "Re-probe the target with the same source port for specified times when a ping failed, to tell whether the failure is persistent or transient."
+    )]
+    #[serde(rename = "reprobe")]
+    pub reprobe_count: u32,
+
+    #[structopt(
+        name = "reprobe-interval",
+        long = "reprobe-interval",
+        default_value = "5000",
+        help = "Sleep between each re-probe of the same source port in milliseconds."
+    )]
+    #[serde(rename = "reprobe-interval")]
+    pub reprobe_interval_in_ms: u64,
+
+    #[structopt(
+        name = "rate",
+        long = "rate",
+        parse(try_from_str = parse_ping_rate),
+        help = "Send pings at a constant rate across all parallel pings, e.g. 100/s, instead of waiting for the interval after each ping. Parallel ping count still limits the pings in flight."
+    )]
+    #[serde(rename = "rate")]
+    pub ping_rate: Option<f64>,
+
+    #[structopt(
+        name = "rate-jitter",
+        long = "rate-jitter",
+        default_value = "0",
+        help = "Randomize the time between pings by up to the specified ratio in both directions, e.g. 0.2 for +/-20%. Only works with --rate."
+    )]
+    #[serde(rename = "rate-jitter")]
+    pub ping_rate_jitter: f64,
+
+    #[structopt(
+        name = "poisson",
+        long = "poisson",
+        help = "Send pings as a Poisson process, i.e. with exponentially distributed time between pings. Only works with --rate."
+    )]
+    #[serde(rename = "poisson")]
+    pub use_poisson_arrival: bool,
+
     #[structopt(long, help = "Exit as soon as a ping failed and return a non-zero error code.")]
     pub exit_on_fail: bool,
 }
 
-#[derive(Debug, StructOpt, PartialEq)]
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RnpCliOutputOptions {
     #[structopt(
         short = "q",
@@ -103,13 +277,26 @@ pub struct RnpCliOutputOptions {
     )]
     pub quiet_level: i32,
 
-    #[structopt(long = "log-csv", alias = "oc", parse(from_os_str), help = "Log ping results a csv file. [alias: --oc]")]
+    #[structopt(name = "log-csv", long = "log-csv", alias = "oc", parse(from_os_str), help = "Log ping results a csv file. [alias: --oc]")]
+    #[serde(rename = "log-csv")]
     pub csv_log_path: Option<PathBuf>,
 
-    #[structopt(long = "log-json", alias = "oj", parse(from_os_str), help = "Log ping results to a json file. [alias: --oj]")]
+    #[structopt(name = "log-json", long = "log-json", alias = "oj", parse(from_os_str), help = "Log ping results to a json file. [alias: --oj]")]
+    #[serde(rename = "log-json")]
     pub json_log_path: Option<PathBuf>,
 
-    #[structopt(short = "o", long = "log-text", parse(from_os_str), help = "Log ping results to a text file.")]
+    #[structopt(
+        name = "log-json-summary",
+        long = "log-json-summary",
+        alias = "ojs",
+        parse(from_os_str),
+        help = "Log ping statistics summary, such as latency percentiles and jitter, to a json file after ping is done. [alias: --ojs]"
+    )]
+    #[serde(rename = "log-json-summary")]
+    pub json_summary_log_path: Option<PathBuf>,
+
+    #[structopt(name = "log-text", short = "o", long = "log-text", parse(from_os_str), help = "Log ping results to a text file.")]
+    #[serde(rename = "log-text")]
     pub text_log_path: Option<PathBuf>,
 
     #[structopt(short = "r", long, help = "Show ping result scatter map after ping is done.")]
@@ -118,6 +305,12 @@ pub struct RnpCliOutputOptions {
     #[structopt(short = "l", long, help = "Show latency (round trip time) scatter map after ping is done.")]
     pub show_latency_scatter: bool,
 
+    #[structopt(
+        long,
+        help = "Show the source ports with loss or latency significantly worse than the others after ping is done, in the format that --src-ports accepts."
+    )]
+    pub show_bad_paths: bool,
+
     #[structopt(
         short = "b",
         long = "latency-buckets",
@@ -125,11 +318,18 @@ pub struct RnpCliOutputOptions {
         help = "If set, bucket ping latency (round trip time) after ping is done. Set to 0.0 to use the default one: [0.1,0.5,1.0,10.0,50.0,100.0,300.0,500.0]"
     )]
     pub latency_buckets: Option<Vec<f64>>,
+
+    #[structopt(
+        long = "metrics-address",
+        help = "If set, serve ping metrics in Prometheus format at http://<address>/metrics, so the ping can be scraped when running as a long-lived canary. Example: 127.0.0.1:9898."
+    )]
+    pub metrics_address: Option<SocketAddr>,
 }
 
-#[derive(Debug, StructOpt, PartialEq)]
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RnpCliQuicPingOptions {
-    #[structopt(long, help = "Specify the server name in the QUIC pings. Example: localhost.")]
+    #[structopt(long, help = "Specify the server name in the QUIC or TLS pings. Example: localhost.")]
     pub server_name: Option<String>,
 
     #[structopt(
@@ -139,11 +339,12 @@ pub struct RnpCliQuicPingOptions {
     pub log_tls_key: bool,
 
     #[structopt(
+        name = "alpn",
         long = "alpn",
-        default_value = "h3-29",
-        help = "ALPN protocol used in QUIC. Specify \"none\" to disable ALPN.\nIt is usually h3-<ver> for http/3 or hq-<ver> for specific version of QUIC.\nFor latest IDs, please check here: https://www.iana.org/assignments/tls-extensiontype-values/tls-extensiontype-values.xhtml#alpn-protocol-ids"
+        help = "ALPN protocol used in QUIC or TLS. Specify \"none\" to disable ALPN. Default to h3-29 for QUIC and none for TLS.\nIt is usually h3-<ver> for http/3 or hq-<ver> for specific version of QUIC, and http/1.1 or h2 for TLS.\nFor latest IDs, please check here: https://www.iana.org/assignments/tls-extensiontype-values/tls-extensiontype-values.xhtml#alpn-protocol-ids"
     )]
-    pub alpn_protocol: String,
+    #[serde(rename = "alpn")]
+    pub alpn_protocol: Option<String>,
 
     #[structopt(
         long,
@@ -152,9 +353,183 @@ pub struct RnpCliQuicPingOptions {
     pub use_timer_rtt: bool,
 }
 
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RnpCliHttpPingOptions {
+    #[structopt(long = "http-method", default_value = "GET", help = "Request method used in HTTP ping.")]
+    pub http_method: String,
+
+    #[structopt(long = "http-path", default_value = "/", help = "Request path used in HTTP ping. Example: /health.")]
+    pub http_path: String,
+
+    #[structopt(long = "http-host", help = "Host header used in HTTP ping. Default to server name if specified, otherwise the target endpoint.")]
+    pub http_host: Option<String>,
+
+    #[structopt(
+        name = "http-header",
+        long = "http-header",
+        number_of_values = 1,
+        parse(try_from_str = parse_http_header),
+        help = "Extra header sent in HTTP ping. Can be specified multiple times. Example: \"X-Probe: rnp\"."
+    )]
+    #[serde(rename = "http-header")]
+    pub http_headers: Vec<String>,
+
+    #[structopt(
+        name = "http-expected-status",
+        long = "http-expected-status",
+        use_delimiter = true,
+        help = "Expected status codes in HTTP ping. Other status codes will be reported as app handshake failure. Default to any 2xx or 3xx. Example: 200,204."
+    )]
+    #[serde(rename = "http-expected-status")]
+    pub http_expected_status_codes: Vec<u16>,
+}
+
+#[derive(Debug, StructOpt, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RnpCliTraceOptions {
+    // Set by running "rnp trace" instead of a flag, see RnpCliOptions::from_iter_with_config_file.
+    #[structopt(skip)]
+    #[serde(skip)]
+    pub is_trace_mode: bool,
+
+    #[structopt(long = "trace-max-hops", default_value = "30", help = "Max number of hops to trace in trace mode.")]
+    pub trace_max_hops: u32,
+}
+
+#[derive(Debug, Default, StructOpt, PartialEq, Serialize, Deserialize)]
+pub struct RnpCliConfigOptions {
+    #[structopt(
+        long = "config",
+        parse(from_os_str),
+        help = "Load options from a TOML config file. The keys are the long names of the options without \"--\", such as timeout = 1000 for --timeout, except targets, ping-until-stopped for -t and quiet-level for -q. Run with --dump-config to see all of them.\nOptions specified in command line override the ones in config file."
+    )]
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
+
+    #[structopt(
+        long = "profile",
+        requires = "config-file",
+        help = "Use the named profile in config file, which is defined in [profiles.<name>] and overrides the options at the top of the config file."
+    )]
+    #[serde(skip)]
+    pub profile: Option<String>,
+
+    #[structopt(
+        long = "unset",
+        requires = "config-file",
+        number_of_values = 1,
+        use_delimiter = true,
+        help = "Ignore the options in config file and use their defaults, such as --unset ping-until-stopped to turn off a flag set in config file. Can be specified multiple times."
+    )]
+    #[serde(skip)]
+    pub unset_config_keys: Vec<String>,
+
+    #[structopt(long = "dump-config", help = "Print the effective options merged from config file and command line in TOML, then exit.")]
+    #[serde(skip)]
+    pub dump_config: bool,
+
+    #[structopt(skip)]
+    #[serde(flatten, skip_serializing)]
+    pub unknown_config_values: BTreeMap<String, toml::Value>,
+}
+
 impl RnpCliOptions {
+    /// Parse the options from command line, and merge them with the options from config file if specified.
+    pub fn from_args_with_config_file() -> RnpCliOptions {
+        return RnpCliOptions::from_iter_with_config_file(std::env::args_os());
+    }
+
+    /// Trace mode is run as "rnp trace <targets> [options]" and takes the same options as ping. Instead of a clap subcommand
+    /// that duplicates all the options, the mode name is taken out before parsing the rest of the arguments.
+    pub fn from_iter_with_config_file<I, T>(args: I) -> RnpCliOptions
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let mut args: Vec<OsString> = args.into_iter().map(|arg| arg.into()).collect();
+        let is_trace_mode = args.len() > 1 && args[1] == RNP_CLI_TRACE_MODE_NAME;
+        if is_trace_mode {
+            args.remove(1);
+        }
+
+        let mut options = RnpCliOptions::from_clap_with_config_file(&RnpCliOptions::clap().get_matches_from(args));
+        options.trace_options.is_trace_mode = is_trace_mode;
+        return options;
+    }
+
+    pub fn from_clap_with_config_file(matches: &ArgMatches) -> RnpCliOptions {
+        let options = RnpCliOptions::from_clap(matches);
+
+        let config_values = match options.config_options.load_config_values() {
+            Ok(Some(config_values)) => config_values,
+            Ok(None) => return options,
+            Err(e) => panic!("{}", e),
+        };
+
+        return match options.merge_config_values(config_values, matches) {
+            Ok(merged_options) => merged_options,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    fn merge_config_values(self, config_values: toml::value::Table, matches: &ArgMatches) -> Result<RnpCliOptions, String> {
+        let mut merged_values = match toml::Value::try_from(&self) {
+            Ok(toml::Value::Table(values)) => values,
+            Ok(_) => unreachable!(),
+            Err(e) => return Err(format!("Failed to convert options to config: {}", e)),
+        };
+
+        for (key, value) in config_values {
+            // Options specified in command line always override the ones in config file.
+            if matches.occurrences_of(&key) > 0 || self.config_options.unset_config_keys.contains(&key) {
+                continue;
+            }
+
+            merged_values.insert(key, value);
+        }
+
+        let mut merged_options: RnpCliOptions =
+            toml::Value::Table(merged_values).try_into().map_err(|e| format!("Invalid options in config file: {}", e))?;
+
+        let unknown_config_keys: Vec<&String> = merged_options.config_options.unknown_config_values.keys().collect();
+        if !unknown_config_keys.is_empty() {
+            return Err(format!("Unknown options in config file: {:?}", unknown_config_keys));
+        }
+
+        // Config options are not saved in config file, hence we restore them from command line.
+        merged_options.config_options = self.config_options;
+        return Ok(merged_options);
+    }
+
+    pub fn to_config_string(&self) -> String {
+        return toml::to_string(self).expect("Failed to convert options to config!");
+    }
+
     pub fn prepare_to_use(&mut self) {
-        self.ping_common_options.prepare_to_use(&self.common_options.target);
+        self.common_options.prepare_to_use();
+        self.ping_common_options.prepare_to_use(&self.common_options.targets.as_ref().unwrap().targets[0]);
+
+        if self.ping_common_options.flow_label.is_some() && self.common_options.protocol == RnpSupportedProtocol::QUIC {
+            panic!("Flow label is not supported in QUIC mode!");
+        }
+
+        // quinn sets the ECN bits as TOS on every datagram it sends, which overrides the TOS we set on the socket.
+        let has_type_of_service = self.ping_common_options.tos.is_some() || self.ping_common_options.dscp.is_some();
+        if has_type_of_service && self.common_options.protocol == RnpSupportedProtocol::QUIC {
+            panic!("TOS and DSCP are not supported in QUIC mode!");
+        }
+
+        // The path is always traced with TCP SYNs, which follow the same path as the pings of the TCP based protocols.
+        if self.trace_options.is_trace_mode {
+            match &self.common_options.protocol {
+                RnpSupportedProtocol::TCP => (),
+                RnpSupportedProtocol::TLS | RnpSupportedProtocol::HTTP => {
+                    tracing::warn!("Trace mode traces with TCP SYNs, which take the same path as the {} pings.", self.common_options.protocol)
+                }
+                protocol => panic!("Trace mode is not supported in {} mode! Only TCP, TLS and HTTP are supported.", protocol),
+            }
+        }
 
         if let Some(latency_buckets) = &mut self.output_options.latency_buckets {
             tracing::debug!("Latency bucket set to 0. Use default one.");
@@ -168,30 +543,44 @@ impl RnpCliOptions {
         let mut config = RnpPingRunnerConfig {
             worker_config: PingWorkerConfig {
                 protocol: self.common_options.protocol.clone(),
-                target: self.common_options.target,
-                source_ip: self.ping_common_options.source_ip,
+                targets: self.common_options.targets.as_ref().unwrap().targets.clone(),
+                source_ips: self.ping_common_options.source_ip.ips.clone(),
                 ping_interval: Duration::from_millis(self.ping_common_options.ping_interval_in_ms.into()),
                 ping_client_config: PingClientConfig {
                     wait_timeout: Duration::from_millis(self.ping_common_options.wait_timeout_in_ms.into()),
                     time_to_live: self.ping_common_options.time_to_live,
+                    flow_label: self.ping_common_options.flow_label,
+                    type_of_service: self.ping_common_options.tos.or(self.ping_common_options.dscp.map(|dscp| dscp << 2)),
+                    interface: self.ping_common_options.interface.clone(),
+                    fwmark: self.ping_common_options.fwmark,
                     check_disconnect: self.ping_common_options.check_disconnect,
                     wait_before_disconnect: Duration::from_millis(self.ping_common_options.wait_before_disconnect_in_ms),
                     disconnect_timeout: Duration::from_millis(self.ping_common_options.disconnect_timeout_in_ms),
                     server_name: self.quic_options.server_name.as_ref().and_then(|s| Some(s.to_string())),
                     log_tls_key: self.quic_options.log_tls_key,
-                    alpn_protocol: if self.quic_options.alpn_protocol.to_uppercase() != String::from("NONE") {
-                        Some(self.quic_options.alpn_protocol.clone())
-                    } else {
-                        None
-                    },
+                    alpn_protocol: self.quic_options.get_alpn_protocol(&self.common_options.protocol),
                     use_timer_rtt: self.quic_options.use_timer_rtt,
+                    http_method: self.http_options.http_method.clone(),
+                    http_path: self.http_options.http_path.clone(),
+                    http_host: self.http_options.http_host.clone(),
+                    http_headers: self.http_options.http_headers.clone(),
+                    http_expected_status_codes: self.http_options.http_expected_status_codes.clone(),
                 },
             },
             worker_scheduler_config: PingWorkerSchedulerConfig {
                 source_ports: self.ping_common_options.source_ports.as_ref().unwrap().clone(),
+                port_picking_strategy: self.ping_common_options.port_picking_strategy,
+                port_cooldown: Duration::from_millis(self.ping_common_options.port_cooldown_in_ms),
+                use_port_cross_product: self.ping_common_options.use_port_cross_product,
                 ping_count: None,
+                duration: self.ping_common_options.duration.map(|duration| duration.duration),
                 warmup_count: self.ping_common_options.warmup_count,
                 parallel_ping_count: self.ping_common_options.parallel_ping_count,
+                reprobe_count: self.ping_common_options.reprobe_count,
+                reprobe_interval: Duration::from_millis(self.ping_common_options.reprobe_interval_in_ms),
+                ping_rate: self.ping_common_options.ping_rate,
+                ping_rate_jitter: self.ping_common_options.ping_rate_jitter,
+                use_poisson_arrival: self.ping_common_options.use_poisson_arrival,
             },
             result_processor_config: PingResultProcessorConfig {
                 common_config: PingResultProcessorCommonConfig { quiet_level: self.output_options.quiet_level },
@@ -199,10 +588,13 @@ impl RnpCliOptions {
                 exit_failure_reason: if self.ping_common_options.exit_on_fail { Some(Arc::new(Mutex::new(None))) } else { None },
                 csv_log_path: self.output_options.csv_log_path.clone(),
                 json_log_path: self.output_options.json_log_path.clone(),
+                json_summary_log_path: self.output_options.json_summary_log_path.clone(),
                 text_log_path: self.output_options.text_log_path.clone(),
                 show_result_scatter: self.output_options.show_result_scatter,
                 show_latency_scatter: self.output_options.show_latency_scatter,
+                show_bad_paths: self.output_options.show_bad_paths,
                 latency_buckets: self.output_options.latency_buckets.as_ref().and_then(|buckets| Some(buckets.clone())),
+                metrics_address: self.output_options.metrics_address,
             },
             external_ping_client_factory: None,
             extra_ping_result_processors: vec![],
@@ -216,16 +608,99 @@ impl RnpCliOptions {
     }
 }
 
+impl RnpCliCommonOptions {
+    pub fn prepare_to_use(&mut self) {
+        let mut targets = self.targets.take().map_or(vec![], |target_list| target_list.targets);
+
+        if let Some(targets_file) = &self.targets_file {
+            match load_ping_targets_from_file(targets_file) {
+                Ok(file_targets) => targets.extend(file_targets),
+                Err(e) => panic!("{}", e),
+            }
+        }
+
+        // Pinging the same target multiple times in one run only mixes up the per target statistics, hence removing the duplicates.
+        let mut unique_targets: Vec<SocketAddr> = Vec::new();
+        for target in targets {
+            if !unique_targets.contains(&target) {
+                unique_targets.push(target);
+            }
+        }
+
+        if unique_targets.len() == 0 {
+            panic!("No target is specified!");
+        }
+
+        if unique_targets.iter().any(|target| target.is_ipv4() != unique_targets[0].is_ipv4()) {
+            panic!("Targets are not all IPv4 or IPv6!");
+        }
+
+        self.targets = Some(PingTargetList { targets: unique_targets });
+    }
+}
+
+impl RnpCliConfigOptions {
+    /// Load the config values from config file, with the ones in the specified profile overriding the top level ones.
+    pub fn load_config_values(&self) -> Result<Option<toml::value::Table>, String> {
+        let config_file = match &self.config_file {
+            Some(config_file) => config_file,
+            None => return Ok(None),
+        };
+
+        let config_content =
+            fs::read_to_string(config_file).map_err(|e| format!("Failed to read config file \"{}\": {}", config_file.display(), e))?;
+        let mut config_values: toml::value::Table =
+            toml::from_str(&config_content).map_err(|e| format!("Failed to parse config file \"{}\": {}", config_file.display(), e))?;
+
+        let profiles = config_values.remove("profiles");
+        if let Some(profile) = &self.profile {
+            match profiles.as_ref().and_then(|profiles| profiles.get(profile)) {
+                Some(toml::Value::Table(profile_values)) => config_values.extend(profile_values.clone()),
+                _ => return Err(format!("Profile \"{}\" is not found in config file \"{}\".", profile, config_file.display())),
+            }
+        }
+
+        return Ok(Some(config_values));
+    }
+}
+
+impl RnpCliQuicPingOptions {
+    pub fn get_alpn_protocol(&self, protocol: &RnpSupportedProtocol) -> Option<String> {
+        return match &self.alpn_protocol {
+            Some(alpn_protocol) if alpn_protocol.to_uppercase() == String::from("NONE") => None,
+            Some(alpn_protocol) => Some(alpn_protocol.clone()),
+
+            // Most of the HTTPS servers don't support h3 ALPN over TLS, so we only use the default one for QUIC.
+            None if *protocol == RnpSupportedProtocol::QUIC => Some(String::from("h3-29")),
+            None => None,
+        };
+    }
+}
+
 impl RnpCliPingCommonOptions {
     pub fn prepare_to_use(&mut self, target: &SocketAddr) {
-        if target.is_ipv4() != self.source_ip.is_ipv4() {
-            match &self.source_ip {
-                IpAddr::V4(source_ip_v4) if *source_ip_v4 == Ipv4Addr::UNSPECIFIED => self.source_ip = IpAddr::V6(Ipv6Addr::UNSPECIFIED),
-                IpAddr::V6(source_ip_v6) if *source_ip_v6 == Ipv6Addr::UNSPECIFIED => self.source_ip = IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-                _ => panic!("Source IP and Target IP are not both IPv4 or IPv6!"),
+        for source_ip in &mut self.source_ip.ips {
+            if target.is_ipv4() != source_ip.is_ipv4() {
+                match source_ip {
+                    IpAddr::V4(source_ip_v4) if *source_ip_v4 == Ipv4Addr::UNSPECIFIED => *source_ip = IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                    IpAddr::V6(source_ip_v6) if *source_ip_v6 == Ipv6Addr::UNSPECIFIED => *source_ip = IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                    _ => (),
+                }
             }
         }
 
+        // Targets are all in the same IP family, so the source IPs in the other family can never be used.
+        let (source_ips, mismatched_source_ips): (Vec<IpAddr>, Vec<IpAddr>) =
+            self.source_ip.ips.iter().partition(|source_ip| source_ip.is_ipv4() == target.is_ipv4());
+        if source_ips.len() == 0 {
+            panic!("Source IPs {:?} are not in the same IP family as the targets!", mismatched_source_ips);
+        }
+
+        if mismatched_source_ips.len() > 0 {
+            tracing::warn!("Source IPs {:?} are not in the same IP family as the targets, hence skipped.", mismatched_source_ips);
+            self.source_ip.ips = source_ips;
+        }
+
         if self.source_ports.is_none() {
             let range_start = rand::thread_rng().gen_range(10000..30000);
             let range_end = range_start + 2000;
@@ -251,6 +726,11 @@ impl RnpCliPingCommonOptions {
             tracing::warn!("Parallel ping count cannot be 0. Setting to 1 as minimum.");
             self.parallel_ping_count = 1;
         }
+
+        if !(0.0..=1.0).contains(&self.ping_rate_jitter) {
+            tracing::warn!("Ping rate jitter ({}) must be within [0, 1], setting it to the closest one.", self.ping_rate_jitter);
+            self.ping_rate_jitter = if self.ping_rate_jitter > 1.0 { 1.0 } else { 0.0 };
+        }
     }
 }
 
@@ -263,44 +743,97 @@ mod tests {
         RnpPingRunnerConfig, RnpSupportedProtocol, RNP_QUIET_LEVEL_NONE, RNP_QUIET_LEVEL_NO_OUTPUT, RNP_QUIET_LEVEL_NO_PING_RESULT,
     };
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU32, Ordering};
     use std::time::Duration;
     use structopt::StructOpt;
 
+    static NEXT_TEST_CONFIG_FILE_ID: AtomicU32 = AtomicU32::new(0);
+
+    // Config file with a unique path in temp folder, which is removed when dropped, even if the test panics.
+    struct TestConfigFile {
+        path: PathBuf,
+    }
+
+    impl TestConfigFile {
+        fn new(content: &str) -> TestConfigFile {
+            let file_name =
+                format!("rnp_cli_options_test_config_{}_{}.toml", std::process::id(), NEXT_TEST_CONFIG_FILE_ID.fetch_add(1, Ordering::SeqCst));
+            let path = std::env::temp_dir().join(file_name);
+            std::fs::write(&path, content).unwrap();
+            return TestConfigFile { path };
+        }
+
+        fn path(&self) -> &str {
+            return self.path.to_str().unwrap();
+        }
+    }
+
+    impl Drop for TestConfigFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
     #[test]
     fn parsing_default_options_should_work() {
         assert_eq!(
             RnpCliOptions {
-                common_options: RnpCliCommonOptions { target: "10.0.0.1:443".parse().unwrap(), protocol: RnpSupportedProtocol::TCP },
+                common_options: RnpCliCommonOptions {
+                    targets: Some(PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap()] }),
+                    targets_file: None,
+                    protocol: RnpSupportedProtocol::TCP,
+                },
                 ping_common_options: RnpCliPingCommonOptions {
                     source_ip: "0.0.0.0".parse().unwrap(),
                     source_ports: None,
+                    port_picking_strategy: PingPortPickingStrategy::Sequential,
+                    port_cooldown_in_ms: 0,
+                    use_port_cross_product: false,
                     ping_count: 4,
                     ping_until_stopped: false,
+                    duration: None,
                     warmup_count: 0,
                     wait_timeout_in_ms: 2000,
                     ping_interval_in_ms: 1000,
                     time_to_live: None,
+                    flow_label: None,
+                    tos: None,
+                    dscp: None,
+                    interface: None,
+                    fwmark: None,
                     check_disconnect: false,
                     wait_before_disconnect_in_ms: 0,
                     disconnect_timeout_in_ms: 2000,
                     parallel_ping_count: 1,
+                    reprobe_count: 0,
+                    reprobe_interval_in_ms: 5000,
+                    ping_rate: None,
+                    ping_rate_jitter: 0.0,
+                    use_poisson_arrival: false,
                     exit_on_fail: false,
                 },
-                quic_options: RnpCliQuicPingOptions {
-                    server_name: None,
-                    log_tls_key: false,
-                    alpn_protocol: String::from("h3-29"),
-                    use_timer_rtt: false,
+                quic_options: RnpCliQuicPingOptions { server_name: None, log_tls_key: false, alpn_protocol: None, use_timer_rtt: false },
+                trace_options: RnpCliTraceOptions { is_trace_mode: false, trace_max_hops: 30 },
+                http_options: RnpCliHttpPingOptions {
+                    http_method: String::from("GET"),
+                    http_path: String::from("/"),
+                    http_host: None,
+                    http_headers: vec![],
+                    http_expected_status_codes: vec![],
                 },
                 output_options: RnpCliOutputOptions {
                     quiet_level: RNP_QUIET_LEVEL_NONE,
                     csv_log_path: None,
                     json_log_path: None,
+                    json_summary_log_path: None,
                     text_log_path: None,
                     show_result_scatter: false,
                     show_latency_scatter: false,
+                    show_bad_paths: false,
                     latency_buckets: None,
+                    metrics_address: None,
                 },
+                config_options: RnpCliConfigOptions::default(),
             },
             RnpCliOptions::from_iter(&["tp.exe", "10.0.0.1:443"])
         );
@@ -310,37 +843,62 @@ mod tests {
     fn parsing_short_options_should_work() {
         assert_eq!(
             RnpCliOptions {
-                common_options: RnpCliCommonOptions { target: "10.0.0.1:443".parse().unwrap(), protocol: RnpSupportedProtocol::TCP },
+                common_options: RnpCliCommonOptions {
+                    targets: Some(PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap()] }),
+                    targets_file: None,
+                    protocol: RnpSupportedProtocol::TCP,
+                },
                 ping_common_options: RnpCliPingCommonOptions {
                     source_ip: "10.0.0.2".parse().unwrap(),
                     source_ports: Some(PortRangeList { ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)] }),
+                    port_picking_strategy: PingPortPickingStrategy::Shuffle,
+                    port_cooldown_in_ms: 0,
+                    use_port_cross_product: false,
                     ping_count: 10,
                     ping_until_stopped: true,
+                    duration: None,
                     warmup_count: 0,
                     wait_timeout_in_ms: 1000,
                     ping_interval_in_ms: 1500,
                     time_to_live: None,
+                    flow_label: None,
+                    tos: None,
+                    dscp: None,
+                    interface: None,
+                    fwmark: None,
                     check_disconnect: true,
                     wait_before_disconnect_in_ms: 0,
                     disconnect_timeout_in_ms: 1000,
                     parallel_ping_count: 10,
+                    reprobe_count: 0,
+                    reprobe_interval_in_ms: 5000,
+                    ping_rate: None,
+                    ping_rate_jitter: 0.0,
+                    use_poisson_arrival: false,
                     exit_on_fail: false,
                 },
-                quic_options: RnpCliQuicPingOptions {
-                    server_name: None,
-                    log_tls_key: false,
-                    alpn_protocol: String::from("h3-29"),
-                    use_timer_rtt: false,
+                quic_options: RnpCliQuicPingOptions { server_name: None, log_tls_key: false, alpn_protocol: None, use_timer_rtt: false },
+                trace_options: RnpCliTraceOptions { is_trace_mode: false, trace_max_hops: 30 },
+                http_options: RnpCliHttpPingOptions {
+                    http_method: String::from("GET"),
+                    http_path: String::from("/"),
+                    http_host: None,
+                    http_headers: vec![],
+                    http_expected_status_codes: vec![],
                 },
                 output_options: RnpCliOutputOptions {
                     quiet_level: RNP_QUIET_LEVEL_NO_PING_RESULT,
                     csv_log_path: Some(PathBuf::from("log.csv")),
                     json_log_path: Some(PathBuf::from("log.json")),
+                    json_summary_log_path: Some(PathBuf::from("summary.json")),
                     text_log_path: Some(PathBuf::from("log.txt")),
                     show_result_scatter: true,
                     show_latency_scatter: true,
+                    show_bad_paths: true,
                     latency_buckets: Some(vec![0.1, 0.5, 1.0, 10.0]),
+                    metrics_address: Some("127.0.0.1:9898".parse().unwrap()),
                 },
+                config_options: RnpCliConfigOptions::default(),
             },
             RnpCliOptions::from_iter(&[
                 "rnp.exe",
@@ -351,6 +909,8 @@ mod tests {
                 "10.0.0.2",
                 "--sp",
                 "1024-2048,3096,3097",
+                "--port-picking",
+                "shuffle",
                 "-n",
                 "10",
                 "-t",
@@ -368,12 +928,17 @@ mod tests {
                 "log.csv",
                 "--oj",
                 "log.json",
+                "--ojs",
+                "summary.json",
                 "-o",
                 "log.txt",
                 "-r",
                 "-l",
+                "--show-bad-paths",
                 "-b",
                 "0.1,0.5,1.0,10.0",
+                "--metrics-address",
+                "127.0.0.1:9898",
             ])
         );
     }
@@ -382,37 +947,67 @@ mod tests {
     fn parsing_long_options_should_work() {
         assert_eq!(
             RnpCliOptions {
-                common_options: RnpCliCommonOptions { target: "10.0.0.1:443".parse().unwrap(), protocol: RnpSupportedProtocol::QUIC },
+                common_options: RnpCliCommonOptions {
+                    targets: Some(PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap()] }),
+                    targets_file: None,
+                    protocol: RnpSupportedProtocol::QUIC,
+                },
                 ping_common_options: RnpCliPingCommonOptions {
-                    source_ip: "10.0.0.2".parse().unwrap(),
+                    source_ip: PingSourceIpList { ips: vec!["10.0.0.2".parse().unwrap(), "10.0.1.1".parse().unwrap(), "10.0.1.2".parse().unwrap()] },
                     source_ports: Some(PortRangeList { ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)] }),
+                    port_picking_strategy: PingPortPickingStrategy::Stride(7),
+                    port_cooldown_in_ms: 60000,
+                    use_port_cross_product: true,
                     ping_count: 10,
                     ping_until_stopped: false,
+                    duration: Some(PingRunDuration { duration: Duration::from_secs(600) }),
                     warmup_count: 3,
                     wait_timeout_in_ms: 1000,
                     ping_interval_in_ms: 1500,
                     time_to_live: Some(128),
+                    flow_label: Some(PingFlowLabel::Rotate),
+                    tos: None,
+                    dscp: Some(46),
+                    interface: Some(String::from("eth1")),
+                    fwmark: Some(256),
                     check_disconnect: true,
                     wait_before_disconnect_in_ms: 3000,
                     disconnect_timeout_in_ms: 4000,
                     parallel_ping_count: 10,
+                    reprobe_count: 3,
+                    reprobe_interval_in_ms: 2000,
+                    ping_rate: Some(100.0),
+                    ping_rate_jitter: 0.2,
+                    use_poisson_arrival: true,
                     exit_on_fail: true,
                 },
                 quic_options: RnpCliQuicPingOptions {
                     server_name: Some(String::from("localhost")),
                     log_tls_key: true,
-                    alpn_protocol: String::from("hq-29"),
+                    alpn_protocol: Some(String::from("hq-29")),
                     use_timer_rtt: true,
                 },
+                trace_options: RnpCliTraceOptions { is_trace_mode: false, trace_max_hops: 30 },
+                http_options: RnpCliHttpPingOptions {
+                    http_method: String::from("HEAD"),
+                    http_path: String::from("/health"),
+                    http_host: Some(String::from("www.example.com")),
+                    http_headers: vec![String::from("X-Probe: rnp"), String::from("X-Time: 10:00")],
+                    http_expected_status_codes: vec![200, 204],
+                },
                 output_options: RnpCliOutputOptions {
                     quiet_level: RNP_QUIET_LEVEL_NO_OUTPUT,
                     csv_log_path: Some(PathBuf::from("log.csv")),
                     json_log_path: Some(PathBuf::from("log.json")),
+                    json_summary_log_path: Some(PathBuf::from("summary.json")),
                     text_log_path: Some(PathBuf::from("log.txt")),
                     show_result_scatter: true,
                     show_latency_scatter: true,
+                    show_bad_paths: true,
                     latency_buckets: Some(vec![0.1, 0.5, 1.0, 10.0]),
+                    metrics_address: Some("127.0.0.1:9898".parse().unwrap()),
                 },
+                config_options: RnpCliConfigOptions::default(),
             },
             RnpCliOptions::from_iter(&[
                 "rnp.exe",
@@ -420,11 +1015,18 @@ mod tests {
                 "--mode",
                 "quic",
                 "--src-ip",
-                "10.0.0.2",
+                "10.0.0.2,10.0.1.0/30",
                 "--src-ports",
                 "1024-2048,3096,3097",
+                "--port-picking",
+                "stride:7",
+                "--port-cooldown",
+                "60000",
+                "--cross-product",
                 "--count",
                 "10",
+                "--duration",
+                "10m",
                 "--warmup",
                 "3",
                 "--timeout",
@@ -433,6 +1035,14 @@ mod tests {
                 "1500",
                 "--ttl",
                 "128",
+                "--flow-label",
+                "rotate",
+                "--dscp",
+                "ef",
+                "--interface",
+                "eth1",
+                "--fwmark",
+                "0x100",
                 "--check-disconnect",
                 "--wait-before-disconnect",
                 "3000",
@@ -440,6 +1050,15 @@ mod tests {
                 "4000",
                 "--parallel",
                 "10",
+                "--reprobe",
+                "3",
+                "--reprobe-interval",
+                "2000",
+                "--rate",
+                "100/s",
+                "--rate-jitter",
+                "0.2",
+                "--poisson",
                 "--exit-on-fail",
                 "--server-name",
                 "localhost",
@@ -447,17 +1066,34 @@ mod tests {
                 "--alpn",
                 "hq-29",
                 "--use-timer-rtt",
+                "--http-method",
+                "HEAD",
+                "--http-path",
+                "/health",
+                "--http-host",
+                "www.example.com",
+                "--http-header",
+                "X-Probe: rnp",
+                "--http-header",
+                "X-Time:10:00",
+                "--http-expected-status",
+                "200,204",
                 "-qqq",
                 "--log-csv",
                 "log.csv",
                 "--log-json",
                 "log.json",
+                "--log-json-summary",
+                "summary.json",
                 "--log-text",
                 "log.txt",
                 "--show-result-scatter",
                 "--show-latency-scatter",
+                "--show-bad-paths",
                 "--latency-buckets",
                 "0.1,0.5,1.0,10.0",
+                "--metrics-address",
+                "127.0.0.1:9898",
             ])
         );
     }
@@ -468,12 +1104,16 @@ mod tests {
             RnpPingRunnerConfig {
                 worker_config: PingWorkerConfig {
                     protocol: RnpSupportedProtocol::TCP,
-                    target: "10.0.0.1:443".parse().unwrap(),
-                    source_ip: "10.0.0.2".parse().unwrap(),
+                    targets: vec!["10.0.0.1:443".parse().unwrap()],
+                    source_ips: vec!["10.0.0.2".parse().unwrap()],
                     ping_interval: Duration::from_millis(1500),
                     ping_client_config: PingClientConfig {
                         wait_timeout: Duration::from_millis(1000),
                         time_to_live: Some(128),
+                        flow_label: None,
+                        type_of_service: Some(184),
+                        interface: None,
+                        fwmark: None,
                         check_disconnect: false,
                         wait_before_disconnect: Duration::from_millis(2000),
                         disconnect_timeout: Duration::from_millis(3000),
@@ -481,13 +1121,27 @@ mod tests {
                         log_tls_key: false,
                         alpn_protocol: None,
                         use_timer_rtt: false,
+                        http_method: String::from("GET"),
+                        http_path: String::from("/"),
+                        http_host: None,
+                        http_headers: vec![],
+                        http_expected_status_codes: vec![],
                     },
                 },
                 worker_scheduler_config: PingWorkerSchedulerConfig {
                     source_ports: PortRangeList { ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)] },
+                    port_picking_strategy: PingPortPickingStrategy::Sequential,
+                    port_cooldown: Duration::ZERO,
+                    use_port_cross_product: false,
                     ping_count: Some(4),
+                    duration: None,
                     warmup_count: 1,
                     parallel_ping_count: 1,
+                    reprobe_count: 0,
+                    reprobe_interval: Duration::from_millis(5000),
+                    ping_rate: None,
+                    ping_rate_jitter: 0.0,
+                    use_poisson_arrival: false,
                 },
                 result_processor_config: PingResultProcessorConfig {
                     common_config: PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NONE },
@@ -495,46 +1149,79 @@ mod tests {
                     exit_failure_reason: None,
                     csv_log_path: None,
                     json_log_path: None,
+                    json_summary_log_path: None,
                     text_log_path: None,
                     show_result_scatter: false,
                     show_latency_scatter: false,
+                    show_bad_paths: false,
                     latency_buckets: None,
+                    metrics_address: None,
                 },
                 external_ping_client_factory: None,
                 extra_ping_result_processors: vec![],
             },
             RnpCliOptions {
-                common_options: RnpCliCommonOptions { target: "10.0.0.1:443".parse().unwrap(), protocol: RnpSupportedProtocol::TCP },
+                common_options: RnpCliCommonOptions {
+                    targets: Some(PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap()] }),
+                    targets_file: None,
+                    protocol: RnpSupportedProtocol::TCP,
+                },
                 ping_common_options: RnpCliPingCommonOptions {
                     ping_count: 4,
                     ping_until_stopped: false,
+                    duration: None,
                     warmup_count: 1,
                     source_ip: "10.0.0.2".parse().unwrap(),
                     source_ports: Some(PortRangeList { ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)] }),
+                    port_picking_strategy: PingPortPickingStrategy::Sequential,
+                    port_cooldown_in_ms: 0,
+                    use_port_cross_product: false,
                     wait_timeout_in_ms: 1000,
                     ping_interval_in_ms: 1500,
                     time_to_live: Some(128),
+                    flow_label: None,
+                    tos: None,
+                    dscp: Some(46),
+                    interface: None,
+                    fwmark: None,
                     check_disconnect: false,
                     wait_before_disconnect_in_ms: 2000,
                     disconnect_timeout_in_ms: 3000,
                     parallel_ping_count: 1,
+                    reprobe_count: 0,
+                    reprobe_interval_in_ms: 5000,
+                    ping_rate: None,
+                    ping_rate_jitter: 0.0,
+                    use_poisson_arrival: false,
                     exit_on_fail: false,
                 },
                 quic_options: RnpCliQuicPingOptions {
                     server_name: None,
                     log_tls_key: false,
-                    alpn_protocol: String::from("none"),
+                    alpn_protocol: Some(String::from("none")),
                     use_timer_rtt: false,
                 },
+                trace_options: RnpCliTraceOptions { is_trace_mode: false, trace_max_hops: 30 },
+                http_options: RnpCliHttpPingOptions {
+                    http_method: String::from("GET"),
+                    http_path: String::from("/"),
+                    http_host: None,
+                    http_headers: vec![],
+                    http_expected_status_codes: vec![],
+                },
                 output_options: RnpCliOutputOptions {
                     quiet_level: RNP_QUIET_LEVEL_NONE,
                     csv_log_path: None,
                     json_log_path: None,
+                    json_summary_log_path: None,
                     text_log_path: None,
                     show_result_scatter: false,
                     show_latency_scatter: false,
+                    show_bad_paths: false,
                     latency_buckets: None,
+                    metrics_address: None,
                 },
+                config_options: RnpCliConfigOptions::default(),
             }
             .to_ping_runner_config()
         );
@@ -543,12 +1230,16 @@ mod tests {
             RnpPingRunnerConfig {
                 worker_config: PingWorkerConfig {
                     protocol: RnpSupportedProtocol::QUIC,
-                    target: "10.0.0.1:443".parse().unwrap(),
-                    source_ip: "10.0.0.2".parse().unwrap(),
+                    targets: vec!["10.0.0.1:443".parse().unwrap()],
+                    source_ips: vec!["10.0.0.2".parse().unwrap()],
                     ping_interval: Duration::from_millis(1500),
                     ping_client_config: PingClientConfig {
                         wait_timeout: Duration::from_millis(2000),
                         time_to_live: Some(128),
+                        flow_label: None,
+                        type_of_service: None,
+                        interface: None,
+                        fwmark: None,
                         check_disconnect: true,
                         wait_before_disconnect: Duration::from_millis(3000),
                         disconnect_timeout: Duration::from_millis(4000),
@@ -556,13 +1247,27 @@ mod tests {
                         log_tls_key: true,
                         alpn_protocol: Some(String::from("h3")),
                         use_timer_rtt: true,
+                        http_method: String::from("GET"),
+                        http_path: String::from("/"),
+                        http_host: None,
+                        http_headers: vec![],
+                        http_expected_status_codes: vec![],
                     },
                 },
                 worker_scheduler_config: PingWorkerSchedulerConfig {
                     source_ports: PortRangeList { ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)] },
+                    port_picking_strategy: PingPortPickingStrategy::Sequential,
+                    port_cooldown: Duration::ZERO,
+                    use_port_cross_product: false,
                     ping_count: None,
+                    duration: None,
                     warmup_count: 3,
                     parallel_ping_count: 1,
+                    reprobe_count: 0,
+                    reprobe_interval: Duration::from_millis(5000),
+                    ping_rate: None,
+                    ping_rate_jitter: 0.0,
+                    use_poisson_arrival: false,
                 },
                 result_processor_config: PingResultProcessorConfig {
                     common_config: PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NO_PING_RESULT },
@@ -570,51 +1275,99 @@ mod tests {
                     exit_failure_reason: Some(Arc::new(Mutex::new(None))),
                     csv_log_path: Some(PathBuf::from("log.csv")),
                     json_log_path: Some(PathBuf::from("log.json")),
+                    json_summary_log_path: Some(PathBuf::from("summary.json")),
                     text_log_path: Some(PathBuf::from("log.txt")),
                     show_result_scatter: true,
                     show_latency_scatter: true,
+                    show_bad_paths: true,
                     latency_buckets: Some(vec![0.1, 0.5, 1.0, 10.0]),
+                    metrics_address: Some("127.0.0.1:9898".parse().unwrap()),
                 },
                 external_ping_client_factory: None,
                 extra_ping_result_processors: vec![],
             },
             RnpCliOptions {
-                common_options: RnpCliCommonOptions { target: "10.0.0.1:443".parse().unwrap(), protocol: RnpSupportedProtocol::QUIC },
+                common_options: RnpCliCommonOptions {
+                    targets: Some(PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap()] }),
+                    targets_file: None,
+                    protocol: RnpSupportedProtocol::QUIC,
+                },
                 ping_common_options: RnpCliPingCommonOptions {
                     ping_count: 4,
                     ping_until_stopped: true,
+                    duration: None,
                     warmup_count: 3,
                     source_ip: "10.0.0.2".parse().unwrap(),
                     source_ports: Some(PortRangeList { ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)] }),
+                    port_picking_strategy: PingPortPickingStrategy::Sequential,
+                    port_cooldown_in_ms: 0,
+                    use_port_cross_product: false,
                     wait_timeout_in_ms: 2000,
                     ping_interval_in_ms: 1500,
                     time_to_live: Some(128),
+                    flow_label: None,
+                    tos: None,
+                    dscp: None,
+                    interface: None,
+                    fwmark: None,
                     check_disconnect: true,
                     wait_before_disconnect_in_ms: 3000,
                     disconnect_timeout_in_ms: 4000,
                     parallel_ping_count: 1,
+                    reprobe_count: 0,
+                    reprobe_interval_in_ms: 5000,
+                    ping_rate: None,
+                    ping_rate_jitter: 0.0,
+                    use_poisson_arrival: false,
                     exit_on_fail: true,
                 },
                 quic_options: RnpCliQuicPingOptions {
                     server_name: Some(String::from("localhost")),
                     log_tls_key: true,
-                    alpn_protocol: String::from("h3"),
+                    alpn_protocol: Some(String::from("h3")),
                     use_timer_rtt: true,
                 },
+                trace_options: RnpCliTraceOptions { is_trace_mode: false, trace_max_hops: 30 },
+                http_options: RnpCliHttpPingOptions {
+                    http_method: String::from("GET"),
+                    http_path: String::from("/"),
+                    http_host: None,
+                    http_headers: vec![],
+                    http_expected_status_codes: vec![],
+                },
                 output_options: RnpCliOutputOptions {
                     quiet_level: RNP_QUIET_LEVEL_NO_PING_RESULT,
                     csv_log_path: Some(PathBuf::from("log.csv")),
                     json_log_path: Some(PathBuf::from("log.json")),
+                    json_summary_log_path: Some(PathBuf::from("summary.json")),
                     text_log_path: Some(PathBuf::from("log.txt")),
                     show_result_scatter: true,
                     show_latency_scatter: true,
+                    show_bad_paths: true,
                     latency_buckets: Some(vec![0.1, 0.5, 1.0, 10.0]),
+                    metrics_address: Some("127.0.0.1:9898".parse().unwrap()),
                 },
+                config_options: RnpCliConfigOptions::default(),
             }
             .to_ping_runner_config()
         );
     }
 
+    #[test]
+    fn default_alpn_protocol_should_depend_on_protocol() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443", "-m", "quic"]);
+        opts.prepare_to_use();
+        assert_eq!(Some(String::from("h3-29")), opts.to_ping_runner_config().worker_config.ping_client_config.alpn_protocol);
+
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443", "-m", "tls"]);
+        opts.prepare_to_use();
+        assert_eq!(None, opts.to_ping_runner_config().worker_config.ping_client_config.alpn_protocol);
+
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443", "-m", "tls", "--alpn", "http/1.1"]);
+        opts.prepare_to_use();
+        assert_eq!(Some(String::from("http/1.1")), opts.to_ping_runner_config().worker_config.ping_client_config.alpn_protocol);
+    }
+
     #[test]
     fn empty_source_port_in_options_should_be_fixed() {
         let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443"]);
@@ -653,13 +1406,233 @@ mod tests {
         assert_eq!(3, opts.ping_common_options.parallel_ping_count);
     }
 
+    #[test]
+    fn multiple_targets_in_options_should_work() {
+        let targets_file_path = std::env::temp_dir().join("rnp_cli_options_test_targets.txt");
+        std::fs::write(&targets_file_path, "# Backends\n10.0.0.2:443\n10.0.0.4/31:443 # Another pool\n").unwrap();
+
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443,10.0.0.2:443", "--targets-file", targets_file_path.to_str().unwrap()]);
+        opts.prepare_to_use();
+        assert_eq!(
+            vec![
+                "10.0.0.1:443".parse::<SocketAddr>().unwrap(),
+                "10.0.0.2:443".parse().unwrap(),
+                "10.0.0.4:443".parse().unwrap(),
+                "10.0.0.5:443".parse().unwrap()
+            ],
+            opts.to_ping_runner_config().worker_config.targets
+        );
+
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "--targets-file", targets_file_path.to_str().unwrap()]);
+        opts.prepare_to_use();
+        assert_eq!(3, opts.to_ping_runner_config().worker_config.targets.len());
+
+        assert!(RnpCliOptions::from_iter_safe(&["rnp.exe"]).is_err());
+        assert!(RnpCliOptions::from_iter_safe(&["rnp.exe", "www.google.com:443,10.0.0.1:443"]).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn flow_label_in_quic_mode_should_fail() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "[2001:db8::1]:443", "-m", "quic", "--flow-label", "rotate"]);
+        opts.prepare_to_use();
+    }
+
+    #[test]
+    #[should_panic]
+    fn tos_in_quic_mode_should_fail() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443", "-m", "quic", "--tos", "184"]);
+        opts.prepare_to_use();
+    }
+
+    #[test]
+    #[should_panic]
+    fn dscp_in_quic_mode_should_fail() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443", "-m", "quic", "--dscp", "ef"]);
+        opts.prepare_to_use();
+    }
+
+    #[test]
+    fn trace_mode_should_be_parsed_from_first_argument() {
+        let opts = RnpCliOptions::from_iter_with_config_file(["rnp.exe", "trace", "10.0.0.1:443", "--trace-max-hops", "10"]);
+        assert!(opts.trace_options.is_trace_mode);
+        assert_eq!(10, opts.trace_options.trace_max_hops);
+        assert_eq!("10.0.0.1:443".parse::<SocketAddr>().unwrap(), opts.common_options.targets.as_ref().unwrap().targets[0]);
+
+        let opts = RnpCliOptions::from_iter_with_config_file(["rnp.exe", "10.0.0.1:443"]);
+        assert!(!opts.trace_options.is_trace_mode);
+    }
+
+    #[test]
+    fn trace_mode_should_work_with_tcp_based_protocols() {
+        for protocol in ["tcp", "tls", "http"] {
+            let mut opts = RnpCliOptions::from_iter_with_config_file(["rnp.exe", "trace", "10.0.0.1:443", "-m", protocol]);
+            opts.prepare_to_use();
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn trace_mode_should_fail_with_quic() {
+        let mut opts = RnpCliOptions::from_iter_with_config_file(["rnp.exe", "trace", "10.0.0.1:443", "-m", "quic"]);
+        opts.prepare_to_use();
+    }
+
+    #[test]
+    #[should_panic]
+    fn mixing_ipv4_and_ipv6_targets_in_options_should_fail() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443,[::1]:443"]);
+        opts.prepare_to_use();
+    }
+
+    #[test]
+    fn loading_options_from_config_file_should_work() {
+        let config_file = TestConfigFile::new(
+            r#"
+mode = "TLS"
+targets = "10.0.0.1:443"
+src-ports = "1024-2048"
+count = 10
+parallel = 4
+timeout = 1000
+ping-until-stopped = true
+show-bad-paths = true
+
+[profiles.canary-eu]
+targets = "10.0.1.0/30:443"
+count = 100
+alpn = "h2"
+"#,
+        );
+
+        let matches = RnpCliOptions::clap().get_matches_from(["rnp.exe", "--config", config_file.path()]);
+        let opts = RnpCliOptions::from_clap_with_config_file(&matches);
+        assert_eq!(RnpSupportedProtocol::TLS, opts.common_options.protocol);
+        assert_eq!(Some(PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap()] }), opts.common_options.targets);
+        assert_eq!(Some(PortRangeList { ranges: vec![(1024..=2048)] }), opts.ping_common_options.source_ports);
+        assert_eq!(10, opts.ping_common_options.ping_count);
+        assert_eq!(4, opts.ping_common_options.parallel_ping_count);
+        assert_eq!(1000, opts.ping_common_options.wait_timeout_in_ms);
+        assert!(opts.ping_common_options.ping_until_stopped);
+        assert!(opts.output_options.show_bad_paths);
+        assert_eq!(None, opts.quic_options.alpn_protocol);
+
+        // Profile overrides the top level options, and command line overrides both.
+        let matches =
+            RnpCliOptions::clap().get_matches_from(["rnp.exe", "--config", config_file.path(), "--profile", "canary-eu", "-n", "5", "-w", "500"]);
+        let opts = RnpCliOptions::from_clap_with_config_file(&matches);
+        assert_eq!(RnpSupportedProtocol::TLS, opts.common_options.protocol);
+        assert_eq!(
+            Some(PingTargetList { targets: vec!["10.0.1.1:443".parse().unwrap(), "10.0.1.2:443".parse().unwrap()] }),
+            opts.common_options.targets
+        );
+        assert_eq!(5, opts.ping_common_options.ping_count);
+        assert_eq!(4, opts.ping_common_options.parallel_ping_count);
+        assert_eq!(500, opts.ping_common_options.wait_timeout_in_ms);
+        assert_eq!(Some(String::from("h2")), opts.quic_options.alpn_protocol);
+        assert_eq!(Some(String::from("canary-eu")), opts.config_options.profile);
+    }
+
+    #[test]
+    fn unset_options_in_config_file_should_use_defaults() {
+        let config_file = TestConfigFile::new("targets = \"10.0.0.1:443\"\nping-until-stopped = true\nshow-bad-paths = true\ncount = 10\n");
+
+        let matches = RnpCliOptions::clap().get_matches_from([
+            "rnp.exe",
+            "--config",
+            config_file.path(),
+            "--unset",
+            "ping-until-stopped,count",
+            "--unset",
+            "not-in-config",
+        ]);
+        let opts = RnpCliOptions::from_clap_with_config_file(&matches);
+        assert!(!opts.ping_common_options.ping_until_stopped);
+        assert_eq!(4, opts.ping_common_options.ping_count);
+        assert!(opts.output_options.show_bad_paths);
+        assert_eq!(vec!["ping-until-stopped", "count", "not-in-config"], opts.config_options.unset_config_keys);
+    }
+
+    #[test]
+    fn dumped_config_should_be_loaded_as_the_same_options() {
+        let opts = RnpCliOptions::from_iter(&[
+            "rnp.exe",
+            "10.0.0.1:443,10.0.0.2:443",
+            "-m",
+            "http",
+            "--sp",
+            "1024,2000-2100",
+            "--ttl",
+            "64",
+            "--flow-label",
+            "0x3039",
+            "-qq",
+            "-b",
+            "0.1,0.5",
+            "--http-header",
+            "X-Probe: rnp",
+            "--http-expected-status",
+            "200,204",
+        ]);
+
+        let config_file = TestConfigFile::new(&opts.to_config_string());
+
+        let matches = RnpCliOptions::clap().get_matches_from(["rnp.exe", "--config", config_file.path()]);
+        let mut loaded_opts = RnpCliOptions::from_clap_with_config_file(&matches);
+        loaded_opts.config_options = RnpCliConfigOptions::default();
+        assert_eq!(opts, loaded_opts);
+    }
+
+    #[test]
+    fn ping_runner_config_should_be_serializable() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "10.0.0.1:443", "-m", "tls", "--ttl", "64", "-b", "0.1,0.5", "--log-csv", "log.csv"]);
+        opts.prepare_to_use();
+        let config = opts.to_ping_runner_config();
+
+        let config_string = serde_json::to_string(&config).unwrap();
+        let loaded_config: RnpPingRunnerConfig = serde_json::from_str(&config_string).unwrap();
+        assert_eq!(config, loaded_config);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unknown_options_in_config_file_should_fail() {
+        // Keys are the long names of the options instead of the field names.
+        let config_file = TestConfigFile::new("targets = \"10.0.0.1:443\"\nsource-ports = \"1024-2048\"\n");
+
+        let matches = RnpCliOptions::clap().get_matches_from(["rnp.exe", "--config", config_file.path()]);
+        RnpCliOptions::from_clap_with_config_file(&matches);
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_profile_in_config_file_should_fail() {
+        let config_file = TestConfigFile::new("targets = \"10.0.0.1:443\"\n");
+
+        let matches = RnpCliOptions::clap().get_matches_from(["rnp.exe", "--config", config_file.path(), "--profile", "canary-eu"]);
+        RnpCliOptions::from_clap_with_config_file(&matches);
+    }
+
     #[test]
     fn invalid_options_for_ipv6_should_be_fixed() {
         let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "[2607:f8b0:400a:80a::200e]:443"]);
         opts.prepare_to_use();
 
         // If source ip is not set (unspecified/any), we update the IP accordingly to match our target.
-        assert!(opts.ping_common_options.source_ip.is_ipv6());
-        assert_eq!(Ipv6Addr::UNSPECIFIED, opts.ping_common_options.source_ip);
+        assert_eq!(vec![IpAddr::V6(Ipv6Addr::UNSPECIFIED)], opts.ping_common_options.source_ip.ips);
+    }
+
+    #[test]
+    fn source_ips_in_other_ip_family_should_be_skipped() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "[2001:db8::1]:443", "-s", "10.0.0.2,2001:db8::2"]);
+        opts.prepare_to_use();
+        assert_eq!(vec!["2001:db8::2".parse::<IpAddr>().unwrap()], opts.ping_common_options.source_ip.ips);
+    }
+
+    #[test]
+    #[should_panic]
+    fn source_ips_all_in_other_ip_family_should_fail() {
+        let mut opts = RnpCliOptions::from_iter(&["rnp.exe", "[2001:db8::1]:443", "-s", "10.0.0.2,10.0.0.3"]);
+        opts.prepare_to_use();
     }
 }
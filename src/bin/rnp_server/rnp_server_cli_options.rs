@@ -1,5 +1,6 @@
 use rnp::{parse_ping_target, RnpStubServerConfig, RnpSupportedProtocol};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 use structopt::StructOpt;
 
@@ -45,6 +46,24 @@ pub struct RnpServerCliCommonOptions {
         help = "When half shutdown is detected, wait specified milliseconds before fully shutdown the connection. [alias: --dd]"
     )]
     pub wait_before_disconnect_in_ms: u64,
+
+    #[structopt(
+        long = "cert",
+        parse(from_os_str),
+        help = "Certificate chain file used by QUIC server, in PEM or DER (.der) format. Self-signed certificate is generated if not specified."
+    )]
+    pub server_certificate_path: Option<PathBuf>,
+
+    #[structopt(long = "key", parse(from_os_str), help = "Private key file used by QUIC server, in PEM or DER (.der) format.")]
+    pub server_private_key_path: Option<PathBuf>,
+
+    #[structopt(
+        long = "alpn",
+        use_delimiter = true,
+        default_value = "h3-29,hq-29",
+        help = "ALPN protocols accepted by QUIC server, separated by comma, in order of descending preference."
+    )]
+    pub alpn_protocols: Vec<String>,
 }
 
 impl RnpServerCliOptions {
@@ -60,6 +79,9 @@ impl RnpServerCliOptions {
             write_count_limit: self.common_options.write_count_limit,
             report_interval: Duration::from_millis(self.common_options.report_interval_in_ms),
             wait_before_disconnect: Duration::from_millis(self.common_options.wait_before_disconnect_in_ms),
+            server_certificate_path: self.common_options.server_certificate_path,
+            server_private_key_path: self.common_options.server_private_key_path,
+            alpn_protocols: self.common_options.alpn_protocols,
         };
     }
 }
@@ -84,6 +106,9 @@ mod tests {
                     write_count_limit: 1,
                     sleep_before_write_in_ms: 0,
                     wait_before_disconnect_in_ms: 0,
+                    server_certificate_path: None,
+                    server_private_key_path: None,
+                    alpn_protocols: vec!["h3-29".to_string(), "hq-29".to_string()],
                 },
             },
             RnpServerCliOptions::from_iter(&["rnp_server.exe", "10.0.0.1:443"])
@@ -103,6 +128,9 @@ mod tests {
                     write_count_limit: 10,
                     sleep_before_write_in_ms: 1000,
                     wait_before_disconnect_in_ms: 3000,
+                    server_certificate_path: None,
+                    server_private_key_path: None,
+                    alpn_protocols: vec!["h3-29".to_string(), "hq-29".to_string()],
                 },
             },
             RnpServerCliOptions::from_iter(&[
@@ -137,7 +165,10 @@ mod tests {
                     write_chunk_size: 2048,
                     write_count_limit: 20,
                     sleep_before_write_in_ms: 2000,
-                    wait_before_disconnect_in_ms: 3000
+                    wait_before_disconnect_in_ms: 3000,
+                    server_certificate_path: Some(PathBuf::from("server.crt")),
+                    server_private_key_path: Some(PathBuf::from("server.key")),
+                    alpn_protocols: vec!["h3".to_string(), "rnp".to_string()],
                 },
             },
             RnpServerCliOptions::from_iter(&[
@@ -156,6 +187,12 @@ mod tests {
                 "2000",
                 "--disconnect-delay",
                 "3000",
+                "--cert",
+                "server.crt",
+                "--key",
+                "server.key",
+                "--alpn",
+                "h3,rnp",
             ])
         );
     }
@@ -172,6 +209,9 @@ mod tests {
                 write_count_limit: 3000,
                 sleep_before_write: Duration::from_millis(4000),
                 wait_before_disconnect: Duration::from_millis(5000),
+                server_certificate_path: Some(PathBuf::from("server.crt")),
+                server_private_key_path: Some(PathBuf::from("server.key")),
+                alpn_protocols: vec!["hq-29".to_string()],
             },
             RnpServerCliOptions {
                 common_options: RnpServerCliCommonOptions {
@@ -183,6 +223,9 @@ mod tests {
                     write_count_limit: 3000,
                     sleep_before_write_in_ms: 4000,
                     wait_before_disconnect_in_ms: 5000,
+                    server_certificate_path: Some(PathBuf::from("server.crt")),
+                    server_private_key_path: Some(PathBuf::from("server.key")),
+                    alpn_protocols: vec!["hq-29".to_string()],
                 },
             }
             .to_stub_server_config()
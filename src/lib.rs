@@ -1,16 +1,27 @@
 pub use ping_clients::ping_client::*;
 use ping_clients::ping_client_factory;
 pub use ping_clients::ping_client_factory::PingClientFactory;
+pub use ping_error_category::PingErrorCategory;
+use ping_job_picker::{PingJob, PingJobPicker};
+pub use ping_latency_histogram::{PingLatencyHistogram, PingLatencySummary};
 use ping_port_picker::PingPortPicker;
+use ping_rate_limiter::PingRateLimiter;
 pub use ping_result::PingResult;
 use ping_result_processing_worker::PingResultProcessingWorker;
 pub use ping_result_processors::ping_result_processor::*;
+pub use ping_result_statistics::{PingReprobeOutcome, PingResultStatistics, PingRunStatistics};
+pub use ping_result_stream::PingResultStream;
+pub use ping_run_summary::{PingRunSummary, PingSourcePortOutcome};
 pub use ping_runners::ping_runner_core::PingRunnerCore;
 pub use ping_runners::*;
+pub use ping_tracer::{PingTraceHop, PingTraceHopStatus, PingTracer};
 pub use rnp_basic_types::*;
 pub use rnp_config::*;
 pub use rnp_dto::*;
-pub use rnp_utils::parse_ping_target;
+pub use rnp_utils::{
+    load_ping_targets_from_file, parse_dscp, parse_duration, parse_fwmark, parse_http_header, parse_ping_rate, parse_ping_target, parse_ping_targets,
+    parse_source_ips, RNP_MAX_PING_TARGET_COUNT, RNP_MAX_SOURCE_IP_COUNT, RNP_MIN_PING_RATE,
+};
 pub use stub_servers::stub_server_factory;
 
 mod ping_runners;
@@ -1,9 +1,17 @@
 pub mod ping_clients;
+pub mod ping_error_category;
+pub mod ping_job_picker;
+pub mod ping_latency_histogram;
 pub mod ping_port_picker;
+pub mod ping_rate_limiter;
 pub mod ping_result;
 pub mod ping_result_processing_worker;
 pub mod ping_result_processors;
+pub mod ping_result_statistics;
+pub mod ping_result_stream;
+pub mod ping_run_summary;
 pub mod ping_runner_core;
+pub mod ping_tracer;
 pub mod ping_worker;
 
 pub use ping_worker::PingWorker;
@@ -1,17 +1,35 @@
 pub mod ping_client;
 pub mod ping_client_factory;
-mod ping_client_tcp;
+mod ping_client_flow_label;
+mod ping_client_http;
+mod ping_client_socket_options;
+pub(crate) mod ping_client_tcp;
+mod ping_client_tcp_info;
+mod ping_client_udp;
 
 // quinn cannot be built for windows.arm64, because it doesn't support uint128 and cause compile
 // failure in boringssl and ring. So before it is ready, we will have to ignore it.
 #[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
 mod ping_client_quic;
 
+// TLS ping is built on top of rustls, which has the same problem as quinn on windows.arm64.
+#[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+mod ping_client_tls;
+
 #[cfg(test)]
 mod ping_client_test_common;
 
 #[cfg(test)]
 mod ping_client_tcp_tests;
 
+#[cfg(test)]
+mod ping_client_http_tests;
+
+#[cfg(test)]
+mod ping_client_udp_tests;
+
 #[cfg(test)]
 mod ping_client_quic_tests;
+
+#[cfg(test)]
+mod ping_client_tls_tests;
@@ -1,7 +1,23 @@
 use async_trait::async_trait;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::time::Duration;
 
+/// Protocol specific details of a ping, such as the TCP_INFO from kernel. They are keyed by PascalCase names, so new
+/// details can be added by the ping clients without changing the result processors.
+pub type PingResultExtraDetails = BTreeMap<String, String>;
+
+// Details from TCP_INFO, which is read right after the connection is established.
+pub const PING_RESULT_EXTRA_DETAIL_TCP_RTT: &str = "TcpRttInMs";
+pub const PING_RESULT_EXTRA_DETAIL_TCP_RTT_VAR: &str = "TcpRttVarInMs";
+pub const PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS: &str = "TcpSynRetransmits";
+pub const PING_RESULT_EXTRA_DETAIL_TCP_MSS: &str = "TcpMss";
+pub const PING_RESULT_EXTRA_DETAIL_TCP_CONGESTION_STATE: &str = "TcpCongestionState";
+
+// Marking of the ping packets, which is only added when it is set on the pings.
+pub const PING_RESULT_EXTRA_DETAIL_FLOW_LABEL: &str = "FlowLabel";
+pub const PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE: &str = "TypeOfService";
+
 #[derive(thiserror::Error, Debug)]
 pub enum PingClientWarning {
     #[error("{0}")]
@@ -26,6 +42,18 @@ pub struct PingClientPingResultDetails {
     pub round_trip_time: Duration,
     pub is_timeout: bool,
     pub warning: Option<PingClientWarning>,
+
+    // Time spent on the app level handshake after the connection is established, such as TLS handshake.
+    // Only set by the ping clients which perform app handshake separately from connecting.
+    pub handshake_time: Option<Duration>,
+
+    // Response details of the app level request, such as HTTP. Only set by the ping clients which send requests after
+    // the connection is established.
+    pub status_code: Option<u16>,
+    pub time_to_first_byte: Option<Duration>,
+    pub total_time: Option<Duration>,
+
+    pub extra_details: PingResultExtraDetails,
 }
 
 impl PingClientPingResultDetails {
@@ -35,7 +63,17 @@ impl PingClientPingResultDetails {
         is_timeout: bool,
         warning: Option<PingClientWarning>,
     ) -> PingClientPingResultDetails {
-        PingClientPingResultDetails { actual_local_addr, round_trip_time, is_timeout, warning }
+        PingClientPingResultDetails {
+            actual_local_addr,
+            round_trip_time,
+            is_timeout,
+            warning,
+            handshake_time: None,
+            status_code: None,
+            time_to_first_byte: None,
+            total_time: None,
+            extra_details: PingResultExtraDetails::new(),
+        }
     }
 }
 
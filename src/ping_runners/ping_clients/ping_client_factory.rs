@@ -1,9 +1,14 @@
+use crate::ping_clients::ping_client_http::PingClientHttp;
 use crate::ping_clients::ping_client_tcp::PingClientTcp;
+use crate::ping_clients::ping_client_udp::PingClientUdp;
 use crate::*;
 
 #[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
 use crate::ping_clients::ping_client_quic::PingClientQuic;
 
+#[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+use crate::ping_clients::ping_client_tls::PingClientTls;
+
 pub type PingClientFactory = fn(protocol: &RnpSupportedProtocol, config: &PingClientConfig) -> Option<Box<dyn PingClient + Send + Sync>>;
 
 pub fn new_ping_client(
@@ -24,7 +29,10 @@ pub fn new_ping_client(
 fn new_inbox_ping_client(protocol: &RnpSupportedProtocol, config: &PingClientConfig) -> Box<dyn PingClient + Send + Sync> {
     match protocol {
         RnpSupportedProtocol::TCP => return Box::new(PingClientTcp::new(config)),
+        RnpSupportedProtocol::UDP => return Box::new(PingClientUdp::new(config)),
         RnpSupportedProtocol::QUIC => return Box::new(PingClientQuic::new(config)),
+        RnpSupportedProtocol::TLS => return Box::new(PingClientTls::new(config)),
+        RnpSupportedProtocol::HTTP => return Box::new(PingClientHttp::new(config)),
         RnpSupportedProtocol::External(p) => panic!("Protocol {} is not supported!", p),
     }
 }
@@ -33,9 +41,14 @@ fn new_inbox_ping_client(protocol: &RnpSupportedProtocol, config: &PingClientCon
 fn new_inbox_ping_client(protocol: &RnpSupportedProtocol, config: &PingClientConfig) -> Box<dyn PingClient + Send + Sync> {
     match protocol {
         RnpSupportedProtocol::TCP => return Box::new(PingClientTcp::new(config)),
+        RnpSupportedProtocol::UDP => return Box::new(PingClientUdp::new(config)),
         RnpSupportedProtocol::QUIC => {
             panic!("Sorry, QUIC ping is not supported yet for Windows ARM64.")
         }
+        RnpSupportedProtocol::TLS => {
+            panic!("Sorry, TLS ping is not supported yet for Windows ARM64.")
+        }
+        RnpSupportedProtocol::HTTP => return Box::new(PingClientHttp::new(config)),
         RnpSupportedProtocol::External(p) => panic!(format!("Protocol {} is not supported!", p)),
     }
 }
@@ -51,6 +64,10 @@ mod tests {
         let config = PingClientConfig {
             wait_timeout: Duration::from_millis(100),
             time_to_live: Some(128),
+            flow_label: None,
+            type_of_service: None,
+            interface: None,
+            fwmark: None,
             check_disconnect: false,
             wait_before_disconnect: Duration::ZERO,
             disconnect_timeout: Duration::from_millis(2000),
@@ -58,9 +75,95 @@ mod tests {
             log_tls_key: false,
             alpn_protocol: None,
             use_timer_rtt: false,
+            http_method: String::from("GET"),
+            http_path: String::from("/"),
+            http_host: None,
+            http_headers: vec![],
+            http_expected_status_codes: vec![],
         };
 
         let ping_client = new_ping_client(&RnpSupportedProtocol::TCP, &config, None);
         assert_eq!("TCP", ping_client.protocol());
     }
+
+    #[test]
+    fn create_udp_ping_client_should_work() {
+        let config = PingClientConfig {
+            wait_timeout: Duration::from_millis(100),
+            time_to_live: Some(128),
+            flow_label: None,
+            type_of_service: None,
+            interface: None,
+            fwmark: None,
+            check_disconnect: false,
+            wait_before_disconnect: Duration::ZERO,
+            disconnect_timeout: Duration::from_millis(2000),
+            server_name: None,
+            log_tls_key: false,
+            alpn_protocol: None,
+            use_timer_rtt: false,
+            http_method: String::from("GET"),
+            http_path: String::from("/"),
+            http_host: None,
+            http_headers: vec![],
+            http_expected_status_codes: vec![],
+        };
+
+        let ping_client = new_ping_client(&RnpSupportedProtocol::UDP, &config, None);
+        assert_eq!("UDP", ping_client.protocol());
+    }
+
+    #[test]
+    fn create_tls_ping_client_should_work() {
+        let config = PingClientConfig {
+            wait_timeout: Duration::from_millis(100),
+            time_to_live: Some(128),
+            flow_label: None,
+            type_of_service: None,
+            interface: None,
+            fwmark: None,
+            check_disconnect: false,
+            wait_before_disconnect: Duration::ZERO,
+            disconnect_timeout: Duration::from_millis(2000),
+            server_name: Some("localhost".to_string()),
+            log_tls_key: false,
+            alpn_protocol: Some("http/1.1".to_string()),
+            use_timer_rtt: false,
+            http_method: String::from("GET"),
+            http_path: String::from("/"),
+            http_host: None,
+            http_headers: vec![],
+            http_expected_status_codes: vec![],
+        };
+
+        let ping_client = new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        assert_eq!("TLS", ping_client.protocol());
+    }
+
+    #[test]
+    fn create_http_ping_client_should_work() {
+        let config = PingClientConfig {
+            wait_timeout: Duration::from_millis(100),
+            time_to_live: Some(128),
+            flow_label: None,
+            type_of_service: None,
+            interface: None,
+            fwmark: None,
+            check_disconnect: false,
+            wait_before_disconnect: Duration::ZERO,
+            disconnect_timeout: Duration::from_millis(2000),
+            server_name: None,
+            log_tls_key: false,
+            alpn_protocol: None,
+            use_timer_rtt: false,
+            http_method: String::from("HEAD"),
+            http_path: String::from("/health"),
+            http_host: Some("localhost".to_string()),
+            http_headers: vec![String::from("X-Probe: rnp")],
+            http_expected_status_codes: vec![200],
+        };
+
+        let ping_client = new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        assert_eq!("HTTP", ping_client.protocol());
+    }
 }
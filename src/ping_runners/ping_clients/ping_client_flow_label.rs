@@ -0,0 +1,85 @@
+use socket2::{SockAddr, Socket};
+use std::io;
+use std::net::{SocketAddr, SocketAddrV6};
+
+// Flow info of IPv6 socket addresses contains both traffic class and flow label, and we only take the flow label.
+#[cfg(target_os = "linux")]
+const FLOW_INFO_FLOW_LABEL_MASK: u32 = 0xFFFFF;
+
+/// Set up the socket to send the IPv6 flow label, which is passed in as the flow info of the target address, and return
+/// the address to connect to. Targets without flow label are returned as they are.
+#[cfg(target_os = "linux")]
+pub(crate) fn prepare_flow_label(socket: &Socket, target: &SocketAddr) -> io::Result<SockAddr> {
+    let target_v6 = match target {
+        SocketAddr::V6(target_v6) if target_v6.flowinfo() & FLOW_INFO_FLOW_LABEL_MASK != 0 => target_v6,
+        _ => return Ok(SockAddr::from(*target)),
+    };
+
+    let flow_label = target_v6.flowinfo() & FLOW_INFO_FLOW_LABEL_MASK;
+    linux::request_flow_label(socket, target_v6, flow_label)?;
+    linux::enable_flow_info_send(socket)?;
+
+    // Kernel takes the flow info in network byte order, while the flow info in Rust is copied into the socket address as is.
+    return Ok(SockAddr::from(SocketAddrV6::new(*target_v6.ip(), target_v6.port(), flow_label.to_be(), target_v6.scope_id())));
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn prepare_flow_label(_socket: &Socket, target: &SocketAddr) -> io::Result<SockAddr> {
+    return Ok(SockAddr::from(remove_flow_label(target)));
+}
+
+/// Remove the flow label from the target address, for the ping clients that cannot send it.
+pub(crate) fn remove_flow_label(target: &SocketAddr) -> SocketAddr {
+    return match target {
+        SocketAddr::V6(target_v6) => SocketAddr::V6(SocketAddrV6::new(*target_v6.ip(), target_v6.port(), 0, target_v6.scope_id())),
+        SocketAddr::V4(_) => *target,
+    };
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::super::ping_client_socket_options::linux::set_socket_option;
+    use socket2::Socket;
+    use std::io;
+    use std::net::SocketAddrV6;
+
+    // Flow label manager actions and flags defined in linux/in6.h.
+    const IPV6_FL_A_GET: u8 = 0;
+    const IPV6_FL_S_ANY: u8 = 255;
+    const IPV6_FL_F_CREATE: u16 = 1;
+
+    // struct in6_flowlabel_req defined in linux/in6.h.
+    #[repr(C)]
+    struct In6FlowLabelReq {
+        flr_dst: libc::in6_addr,
+        flr_label: u32,
+        flr_action: u8,
+        flr_share: u8,
+        flr_flags: u16,
+        flr_expires: u16,
+        flr_linger: u16,
+        flr_pad: u32,
+    }
+
+    // Kernel only sends the flow labels that are owned by the socket, so we need to get one from the flow label manager
+    // first. The label is shared with any other socket, so the concurrent pings can use the same label.
+    pub(super) fn request_flow_label(socket: &Socket, target: &SocketAddrV6, flow_label: u32) -> io::Result<()> {
+        let request = In6FlowLabelReq {
+            flr_dst: libc::in6_addr { s6_addr: target.ip().octets() },
+            flr_label: flow_label.to_be(),
+            flr_action: IPV6_FL_A_GET,
+            flr_share: IPV6_FL_S_ANY,
+            flr_flags: IPV6_FL_F_CREATE,
+            flr_expires: 0,
+            flr_linger: 0,
+            flr_pad: 0,
+        };
+
+        return set_socket_option(socket, libc::IPPROTO_IPV6, libc::IPV6_FLOWLABEL_MGR, &request);
+    }
+
+    pub(super) fn enable_flow_info_send(socket: &Socket) -> io::Result<()> {
+        let enabled: libc::c_int = 1;
+        return set_socket_option(socket, libc::IPPROTO_IPV6, libc::IPV6_FLOWINFO_SEND, &enabled);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::ping_clients::ping_client_flow_label;
+use crate::ping_clients::ping_client_tcp::PingClientTcp;
+use crate::*;
+use async_trait::async_trait;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+// Health probe responses are expected to be small, so we stop parsing the response if the headers grows too large,
+// which usually means we are not talking to a HTTP server.
+const HTTP_MAX_RESPONSE_HEADER_SIZE: usize = 64 * 1024;
+
+struct HttpResponseSummary {
+    status_code: u16,
+    time_to_first_byte: Duration,
+    total_time: Duration,
+}
+
+pub struct PingClientHttp {
+    config: PingClientConfig,
+}
+
+impl PingClientHttp {
+    pub fn new(config: &PingClientConfig) -> PingClientHttp {
+        return PingClientHttp { config: config.clone() };
+    }
+
+    #[tracing::instrument(name = "Running HTTP ping in ping client", level = "debug", skip(self))]
+    async fn ping_target(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
+        let request = self.build_request(target);
+        let socket = PingClientTcp::prepare_socket_for_ping(&self.config, source).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+        let target_addr = ping_client_flow_label::prepare_flow_label(&socket, target).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+
+        let start_time = Instant::now();
+        let connect_result = socket.connect_timeout(&target_addr, self.config.wait_timeout);
+        let rtt = Instant::now().duration_since(start_time);
+        match connect_result {
+            // Timeout is an expected value instead of an actual failure, so here we should return Ok.
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(PingClientPingResultDetails::new(None, rtt, true, None)),
+            Err(e) => return Err(PingClientError::PingFailed(Box::new(e))),
+            Ok(()) => (),
+        }
+
+        // If getting local address failed, we ignore it.
+        // The worse case we can get is to output a 0.0.0.0 as source IP, which is not critical to what we are trying to do.
+        let local_addr = socket.local_addr().map_or(None, |addr| addr.as_socket());
+
+        socket.set_nonblocking(true).map_err(|e| PingClientError::PingFailed(Box::new(e)))?;
+        let mut connection = TcpStream::from_std(socket.into()).map_err(|e| PingClientError::PingFailed(Box::new(e)))?;
+
+        let mut ping_result = PingClientPingResultDetails::new(local_addr, rtt, false, None);
+
+        // Same as TLS, once the TCP connection is established, the failures in HTTP layer are logged as warnings.
+        let response_result = tokio::time::timeout(self.config.wait_timeout, self.send_request(&mut connection, &request, target)).await;
+        let response = match response_result {
+            Err(_) => {
+                let e = io::Error::new(io::ErrorKind::TimedOut, "HTTP response timed out.");
+                ping_result.warning = Some(PingClientWarning::AppHandshakeFailed(Box::new(e)));
+                return Ok(ping_result);
+            }
+            Ok(Err(e)) => {
+                ping_result.warning = Some(PingClientWarning::AppHandshakeFailed(Box::new(e)));
+                return Ok(ping_result);
+            }
+            Ok(Ok(response)) => response,
+        };
+
+        ping_result.status_code = Some(response.status_code);
+        ping_result.time_to_first_byte = Some(response.time_to_first_byte);
+        ping_result.total_time = Some(response.total_time);
+
+        if !self.is_expected_status_code(response.status_code) {
+            let e = io::Error::new(io::ErrorKind::InvalidData, format!("Unexpected HTTP status code: {}", response.status_code));
+            ping_result.warning = Some(PingClientWarning::AppHandshakeFailed(Box::new(e)));
+        }
+
+        return Ok(ping_result);
+    }
+
+    fn build_request(&self, target: &SocketAddr) -> Vec<u8> {
+        let host = match (&self.config.http_host, &self.config.server_name) {
+            (Some(host), _) => host.clone(),
+            (None, Some(server_name)) => server_name.clone(),
+            (None, None) => target.to_string(),
+        };
+
+        // We always ask the server to close the connection after the response, so the response can be read till the end
+        // even when its length is not specified.
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nConnection: close\r\n",
+            self.config.http_method, self.config.http_path, host, RNP_NAME
+        );
+        for header in &self.config.http_headers {
+            request += header;
+            request += "\r\n";
+        }
+        request += "\r\n";
+
+        return request.into_bytes();
+    }
+
+    fn is_expected_status_code(&self, status_code: u16) -> bool {
+        if self.config.http_expected_status_codes.len() == 0 {
+            return (200..400).contains(&status_code);
+        }
+
+        return self.config.http_expected_status_codes.contains(&status_code);
+    }
+
+    #[tracing::instrument(name = "Sending HTTP request", level = "debug", skip(self, connection, request))]
+    async fn send_request(&self, connection: &mut TcpStream, request: &[u8], target: &SocketAddr) -> io::Result<HttpResponseSummary> {
+        let request_start_time = Instant::now();
+        connection.write_all(request).await?;
+
+        // Read until all response headers are received.
+        let mut read_buffer = vec![0 as u8; 4096];
+        let mut response_buffer: Vec<u8> = Vec::new();
+        let mut time_to_first_byte: Option<Duration> = None;
+        let header_size = loop {
+            let read_size = connection.read(&mut read_buffer[..]).await?;
+            if read_size == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection is closed before HTTP response headers are received."));
+            }
+
+            if time_to_first_byte.is_none() {
+                time_to_first_byte = Some(Instant::now().duration_since(request_start_time));
+            }
+
+            response_buffer.extend_from_slice(&read_buffer[..read_size]);
+            if let Some(header_end) = response_buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break header_end + 4;
+            }
+
+            if response_buffer.len() > HTTP_MAX_RESPONSE_HEADER_SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "HTTP response headers are too large."));
+            }
+        };
+
+        let headers = String::from_utf8_lossy(&response_buffer[..header_size]).to_string();
+        let status_code = PingClientHttp::parse_status_code(&headers)?;
+        tracing::debug!("HTTP response headers received; status_code={}, target={}", status_code, target);
+
+        // Read the response body as well, so the total time covers the whole response.
+        if self.response_has_body(status_code) {
+            let body_received = response_buffer.len() - header_size;
+            match PingClientHttp::parse_content_length(&headers)? {
+                Some(content_length) => {
+                    let mut remaining = content_length.saturating_sub(body_received);
+                    while remaining > 0 {
+                        let read_size = connection.read(&mut read_buffer[..]).await?;
+                        if read_size == 0 {
+                            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection is closed before HTTP response body is received."));
+                        }
+                        remaining = remaining.saturating_sub(read_size);
+                    }
+                }
+
+                // No content length means the body is either chunked or ends with the connection, and since we always ask
+                // the server to close the connection, reading until the end works for both cases.
+                None => {
+                    while connection.read(&mut read_buffer[..]).await? > 0 {
+                        continue;
+                    }
+                }
+            }
+        }
+
+        return Ok(HttpResponseSummary {
+            status_code,
+            time_to_first_byte: time_to_first_byte.unwrap(),
+            total_time: Instant::now().duration_since(request_start_time),
+        });
+    }
+
+    fn response_has_body(&self, status_code: u16) -> bool {
+        if self.config.http_method.to_uppercase() == "HEAD" {
+            return false;
+        }
+
+        return status_code >= 200 && status_code != 204 && status_code != 304;
+    }
+
+    fn parse_status_code(headers: &str) -> io::Result<u16> {
+        let status_line = headers.lines().next().unwrap_or("");
+        let mut status_line_parts = status_line.split_whitespace();
+
+        let status_code = match (status_line_parts.next(), status_line_parts.next()) {
+            (Some(version), Some(status_code)) if version.starts_with("HTTP/") => status_code.parse::<u16>().ok(),
+            _ => None,
+        };
+
+        return match status_code {
+            Some(status_code) if (100..1000).contains(&status_code) => Ok(status_code),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid HTTP status line: {}", status_line))),
+        };
+    }
+
+    fn parse_content_length(headers: &str) -> io::Result<Option<usize>> {
+        for header in headers.lines().skip(1) {
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("Content-Length") {
+                    let content_length = value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid HTTP content length: {}", value.trim())))?;
+                    return Ok(Some(content_length));
+                }
+            }
+        }
+
+        return Ok(None);
+    }
+}
+
+#[async_trait]
+impl PingClient for PingClientHttp {
+    fn protocol(&self) -> &'static str {
+        "HTTP"
+    }
+
+    async fn prepare_ping(&mut self, _: &SocketAddr) -> Result<(), PingClientError> {
+        Ok(())
+    }
+
+    async fn ping(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
+        return self.ping_target(source, target).await;
+    }
+}
@@ -0,0 +1,257 @@
+use crate::ping_clients::ping_client_test_common::*;
+use crate::stub_servers::stub_server_factory;
+use crate::{ping_clients::ping_client_factory, rnp_test_common, PingClientConfig, RnpStubServerConfig, RnpSupportedProtocol};
+use futures_intrusive::sync::ManualResetEvent;
+use pretty_assertions::assert_eq;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+
+#[test]
+fn ping_client_http_should_work_when_pinging_good_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11371".parse::<SocketAddr>().unwrap();
+    start_run_http_test_server(&rt, &server_address, "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        ping_client_should_work_when_pinging_good_host(&mut ping_client, &server_address).await;
+
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let ping_result = ping_client.ping(&source, &server_address).await.unwrap();
+        assert!(ping_result.warning.is_none());
+        assert_eq!(Some(200), ping_result.status_code);
+        assert!(ping_result.time_to_first_byte.is_some());
+        assert!(ping_result.total_time.unwrap() >= ping_result.time_to_first_byte.unwrap());
+    });
+}
+
+#[test]
+fn ping_client_http_should_send_configured_request() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11372".parse::<SocketAddr>().unwrap();
+    let received_request = start_run_http_test_server(&rt, &server_address, "HTTP/1.1 204 No Content\r\n\r\n");
+
+    rt.block_on(async move {
+        let mut config = create_ping_client_http_default_config();
+        config.http_method = String::from("HEAD");
+        config.http_path = String::from("/health?probe=1");
+        config.http_host = Some(String::from("www.example.com"));
+        config.http_headers = vec![String::from("X-Probe: rnp")];
+
+        let ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let ping_result = ping_client.ping(&source, &server_address).await.unwrap();
+        assert!(ping_result.warning.is_none());
+        assert_eq!(Some(204), ping_result.status_code);
+    });
+
+    assert_eq!(
+        "HEAD /health?probe=1 HTTP/1.1\r\nHost: www.example.com\r\nUser-Agent: Rnp\r\nConnection: close\r\nX-Probe: rnp\r\n\r\n",
+        received_request.lock().unwrap().as_str()
+    );
+}
+
+#[test]
+fn ping_client_http_should_warn_when_status_code_is_unexpected() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11373".parse::<SocketAddr>().unwrap();
+    start_run_http_test_server(&rt, &server_address, "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n");
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let expected_result = ExpectedTestCaseResult::Warning("Unexpected HTTP status code: 503");
+        ping_client_result_should_be_expected(&mut ping_client, &source, &server_address, Duration::from_millis(200), &expected_result).await;
+
+        let ping_result = ping_client.ping(&source, &server_address).await.unwrap();
+        assert_eq!(Some(503), ping_result.status_code);
+    });
+}
+
+#[test]
+fn ping_client_http_should_work_when_status_code_is_expected() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11374".parse::<SocketAddr>().unwrap();
+    start_run_http_test_server(&rt, &server_address, "HTTP/1.1 404 Not Found\r\n\r\nnot found");
+
+    rt.block_on(async move {
+        let mut config = create_ping_client_http_default_config();
+        config.http_expected_status_codes = vec![404];
+
+        let ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let ping_result = ping_client.ping(&source, &server_address).await.unwrap();
+        assert!(ping_result.warning.is_none());
+        assert_eq!(Some(404), ping_result.status_code);
+    });
+}
+
+#[test]
+fn ping_client_http_should_warn_when_server_closes_connection_on_accept() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11375".parse::<SocketAddr>().unwrap();
+    let mut server_config = create_tcp_stub_server_default_config(&server_address);
+    server_config.close_on_accept = true;
+    start_run_tcp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let expected_result = ExpectedTestCaseResult::Warning("Connection is closed before HTTP response headers are received.");
+        ping_client_result_should_be_expected(&mut ping_client, &source, &server_address, Duration::from_millis(200), &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_http_should_warn_when_server_does_not_respond() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11376".parse::<SocketAddr>().unwrap();
+    let server_config = create_tcp_stub_server_default_config(&server_address);
+    start_run_tcp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let expected_result = ExpectedTestCaseResult::Warning("HTTP response timed out.");
+        ping_client_result_should_be_expected(&mut ping_client, &source, &server_address, Duration::from_millis(200), &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_http_should_fail_when_pinging_non_existing_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+        ping_client_should_fail_when_pinging_non_existing_host(&mut ping_client, &ExpectedTestCaseResult::Timeout).await;
+    });
+}
+
+#[test]
+fn ping_client_http_should_fail_when_pinging_non_existing_port() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+
+        let expected_result = if cfg!(windows) { ExpectedTestCaseResult::Timeout } else { ExpectedTestCaseResult::Failed("connection refused") };
+        ping_client_should_fail_when_pinging_non_existing_port(&mut ping_client, &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_http_should_fail_when_binding_invalid_source_ip() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_http_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::HTTP, &config, None);
+
+        let expected_result = ExpectedTestCaseResult::Failed("The requested address is not valid in its context. (os error 10049)");
+        ping_client_should_fail_when_binding_invalid_source_ip(&mut ping_client, &expected_result).await;
+    });
+}
+
+// Stub server doesn't speak HTTP, so we use a minimal HTTP server here, which replies every request with the given
+// response and closes the connection. The last received request is returned for validation.
+fn start_run_http_test_server(rt: &Runtime, server_address: &SocketAddr, response: &'static str) -> Arc<Mutex<String>> {
+    let received_request = Arc::new(Mutex::new(String::new()));
+    let received_request_clone = received_request.clone();
+
+    let listener = rt.block_on(TcpListener::bind(server_address)).unwrap();
+    rt.spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let received_request = received_request_clone.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut read_buffer = vec![0 as u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut read_buffer).await {
+                        Ok(n) if n > 0 => request.extend_from_slice(&read_buffer[..n]),
+                        _ => return,
+                    }
+                }
+
+                *received_request.lock().unwrap() = String::from_utf8_lossy(&request).to_string();
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    return received_request;
+}
+
+fn create_tcp_stub_server_default_config(server_address: &SocketAddr) -> RnpStubServerConfig {
+    return RnpStubServerConfig {
+        protocol: RnpSupportedProtocol::TCP,
+        server_address: server_address.clone(),
+        close_on_accept: false,
+        sleep_before_write: Duration::ZERO,
+        write_chunk_size: 0,
+        write_count_limit: 0,
+        report_interval: Duration::from_secs(1),
+        wait_before_disconnect: Duration::ZERO,
+        server_certificate_path: None,
+        server_private_key_path: None,
+        alpn_protocols: vec![],
+    };
+}
+
+fn start_run_tcp_stub_server(rt: &Runtime, stub_server_config: RnpStubServerConfig) {
+    let ready_event = Arc::new(ManualResetEvent::new(false));
+    let ready_event_clone = ready_event.clone();
+    rt.spawn(async move {
+        let _ = stub_server_factory::run(&stub_server_config, Arc::new(ManualResetEvent::new(false)), ready_event_clone).await;
+    });
+    rt.block_on(ready_event.wait());
+}
+
+fn create_ping_client_http_default_config() -> PingClientConfig {
+    return PingClientConfig {
+        wait_timeout: Duration::from_millis(300),
+        time_to_live: None,
+        flow_label: None,
+        type_of_service: None,
+        interface: None,
+        fwmark: None,
+        check_disconnect: false,
+        wait_before_disconnect: Duration::ZERO,
+        disconnect_timeout: Duration::ZERO,
+        server_name: None,
+        log_tls_key: false,
+        alpn_protocol: None,
+        use_timer_rtt: false,
+        http_method: String::from("GET"),
+        http_path: String::from("/"),
+        http_host: None,
+        http_headers: vec![],
+        http_expected_status_codes: vec![],
+    };
+}
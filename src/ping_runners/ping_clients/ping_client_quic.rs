@@ -1,8 +1,11 @@
+use crate::ping_clients::{ping_client_flow_label, ping_client_socket_options};
 use crate::*;
 use async_trait::async_trait;
 use quinn::{ClientConfig, ClientConfigBuilder, ConnectionError, Endpoint, EndpointError};
 use rustls::ServerCertVerified;
-use std::net::SocketAddr;
+use socket2::SockRef;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -20,7 +23,11 @@ impl PingClientQuic {
         let endpoint = self.create_local_endpoint(source).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
         let server_name = self.config.server_name.as_ref().map_or("", |s| &s);
         let use_timer_rtt = self.config.use_timer_rtt;
-        let ping_result = PingClientQuic::connect_to_target(&endpoint, source, target, server_name, use_timer_rtt).await;
+
+        // quinn drops the packets coming from an address that is different from the one we connect to, including the flow
+        // info, which is 0 in the received packets, so flow label is not supported in QUIC ping.
+        let target = ping_client_flow_label::remove_flow_label(target);
+        let ping_result = PingClientQuic::connect_to_target(&endpoint, source, &target, server_name, use_timer_rtt).await;
         endpoint.wait_idle().await;
         return ping_result;
     }
@@ -31,15 +38,17 @@ impl PingClientQuic {
         let mut endpoint_builder = Endpoint::builder();
         endpoint_builder.default_client_config(client_config);
 
-        let socket = std::net::UdpSocket::bind(source).map_err(EndpointError::Socket)?;
-        if let Some(ttl) = self.config.time_to_live {
-            socket.set_ttl(ttl).map_err(EndpointError::Socket)?;
-        }
-
+        let socket = PingClientQuic::prepare_socket_for_ping(&self.config, source).map_err(EndpointError::Socket)?;
         let (endpoint, _) = endpoint_builder.with_socket(socket)?;
         return Ok(endpoint);
     }
 
+    pub(crate) fn prepare_socket_for_ping(config: &PingClientConfig, source: &SocketAddr) -> io::Result<UdpSocket> {
+        let socket = UdpSocket::bind(source)?;
+        ping_client_socket_options::apply_socket_options(&SockRef::from(&socket), config, source)?;
+        return Ok(socket);
+    }
+
     fn create_client_config(&self) -> ClientConfig {
         let mut client_config_builder = ClientConfigBuilder::default();
 
@@ -123,7 +132,7 @@ impl PingClient for PingClientQuic {
     }
 }
 
-struct SkipCertificationVerification;
+pub(crate) struct SkipCertificationVerification;
 
 impl rustls::ServerCertVerifier for SkipCertificationVerification {
     fn verify_server_cert(
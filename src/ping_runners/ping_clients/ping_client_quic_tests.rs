@@ -1,8 +1,87 @@
 use crate::ping_clients::ping_client_test_common::*;
-use crate::{ping_clients::ping_client_factory, rnp_test_common, PingClientConfig, RnpSupportedProtocol};
+use crate::stub_servers::stub_server_factory;
+use crate::{ping_clients::ping_client_factory, rnp_test_common, PingClientConfig, RnpStubServerConfig, RnpSupportedProtocol};
+use futures_intrusive::sync::ManualResetEvent;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Runtime;
 
+#[test]
+fn ping_client_quic_should_work_when_pinging_good_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11351".parse::<SocketAddr>().unwrap();
+    let server_config = create_quic_stub_server_default_config(&server_address);
+    start_run_quic_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_quic_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::QUIC, &config, None);
+        ping_client_should_work_when_pinging_good_host(&mut ping_client, &server_address).await;
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_client_quic_should_bind_socket_to_interface() {
+    use crate::ping_clients::ping_client_quic::PingClientQuic;
+    use socket2::SockRef;
+
+    let mut config = create_ping_client_quic_default_config();
+    config.interface = Some("lo".to_string());
+
+    let source = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+    let socket = PingClientQuic::prepare_socket_for_ping(&config, &source).unwrap();
+    assert_eq!(Some(b"lo".to_vec()), SockRef::from(&socket).device().unwrap());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_client_quic_should_set_fwmark_on_socket() {
+    use crate::ping_clients::ping_client_quic::PingClientQuic;
+    use socket2::SockRef;
+
+    let mut config = create_ping_client_quic_default_config();
+    config.fwmark = Some(0x1234);
+
+    // Setting SO_MARK requires CAP_NET_ADMIN, so skip the test when we are not allowed to do it.
+    let source = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+    let socket = match PingClientQuic::prepare_socket_for_ping(&config, &source) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => return,
+        result => result.unwrap(),
+    };
+    assert_eq!(0x1234, SockRef::from(&socket).mark().unwrap());
+}
+
+#[test]
+fn ping_client_quic_should_report_warning_when_alpn_mismatches() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11352".parse::<SocketAddr>().unwrap();
+    let mut server_config = create_quic_stub_server_default_config(&server_address);
+    server_config.alpn_protocols = vec!["h3".to_string()];
+    start_run_quic_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_quic_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::QUIC, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        ping_client_result_should_be_expected(
+            &mut ping_client,
+            &source,
+            &server_address,
+            Duration::from_millis(200),
+            &ExpectedTestCaseResult::Warning(
+                "aborted by peer: the cryptographic handshake failed: error 120: peer doesn't support any known protocol",
+            ),
+        )
+        .await;
+    });
+}
+
 #[test]
 fn ping_client_quic_should_fail_when_pinging_non_existing_host() {
     rnp_test_common::initialize();
@@ -42,10 +121,39 @@ fn ping_client_quic_should_fail_when_binding_invalid_source_ip() {
     });
 }
 
+fn create_quic_stub_server_default_config(server_address: &SocketAddr) -> RnpStubServerConfig {
+    return RnpStubServerConfig {
+        protocol: RnpSupportedProtocol::QUIC,
+        server_address: server_address.clone(),
+        close_on_accept: false,
+        sleep_before_write: Duration::ZERO,
+        write_chunk_size: 0,
+        write_count_limit: 0,
+        report_interval: Duration::from_secs(1),
+        wait_before_disconnect: Duration::ZERO,
+        server_certificate_path: None,
+        server_private_key_path: None,
+        alpn_protocols: vec!["hq-29".to_string()],
+    };
+}
+
+fn start_run_quic_stub_server(rt: &Runtime, stub_server_config: RnpStubServerConfig) {
+    let ready_event = Arc::new(ManualResetEvent::new(false));
+    let ready_event_clone = ready_event.clone();
+    rt.spawn(async move {
+        let _ = stub_server_factory::run(&stub_server_config, Arc::new(ManualResetEvent::new(false)), ready_event_clone).await;
+    });
+    rt.block_on(ready_event.wait());
+}
+
 fn create_ping_client_quic_default_config() -> PingClientConfig {
     return PingClientConfig {
         wait_timeout: Duration::from_millis(300),
         time_to_live: None,
+        flow_label: None,
+        type_of_service: None,
+        interface: None,
+        fwmark: None,
         check_disconnect: false,
         wait_before_disconnect: Duration::ZERO,
         disconnect_timeout: Duration::ZERO,
@@ -53,5 +161,10 @@ fn create_ping_client_quic_default_config() -> PingClientConfig {
         log_tls_key: false,
         alpn_protocol: Some("hq-29".to_string()),
         use_timer_rtt: false,
+        http_method: String::from("GET"),
+        http_path: String::from("/"),
+        http_host: None,
+        http_headers: vec![],
+        http_expected_status_codes: vec![],
     };
 }
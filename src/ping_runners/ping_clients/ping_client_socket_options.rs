@@ -0,0 +1,107 @@
+use crate::PingClientConfig;
+use socket2::Socket;
+use std::io;
+use std::net::SocketAddr;
+
+/// Apply the socket options that are shared by all ping clients, so every protocol sends the pings in the same way.
+pub(crate) fn apply_socket_options(socket: &Socket, config: &PingClientConfig, source: &SocketAddr) -> io::Result<()> {
+    if let Some(ttl) = config.time_to_live {
+        socket.set_ttl(ttl)?;
+    }
+
+    if let Some(tos) = config.type_of_service {
+        set_type_of_service(socket, source, tos)?;
+    }
+
+    // With policy routing and VRFs, the source IP alone doesn't decide which link the pings go through.
+    if let Some(interface) = &config.interface {
+        bind_to_interface(socket, interface)?;
+    }
+
+    if let Some(fwmark) = config.fwmark {
+        set_fwmark(socket, fwmark)?;
+    }
+
+    return Ok(());
+}
+
+// The TOS byte in IPv4 header and the traffic class in IPv6 header are the same thing, which carries DSCP and ECN.
+fn set_type_of_service(socket: &Socket, source: &SocketAddr, tos: u8) -> io::Result<()> {
+    if source.is_ipv4() {
+        return socket.set_tos(tos as u32);
+    }
+
+    return set_ipv6_traffic_class(socket, tos);
+}
+
+#[cfg(target_os = "linux")]
+fn set_ipv6_traffic_class(socket: &Socket, tos: u8) -> io::Result<()> {
+    return linux::set_socket_option(socket, libc::IPPROTO_IPV6, libc::IPV6_TCLASS, &(tos as libc::c_int));
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_ipv6_traffic_class(_socket: &Socket, _tos: u8) -> io::Result<()> {
+    return Err(io::Error::new(io::ErrorKind::Unsupported, "Setting traffic class of IPv6 pings is only supported on Linux."));
+}
+
+#[cfg(target_os = "linux")]
+fn bind_to_interface(socket: &Socket, interface: &str) -> io::Result<()> {
+    return socket.bind_device(Some(interface.as_bytes()));
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bind_to_interface(_socket: &Socket, _interface: &str) -> io::Result<()> {
+    return Err(io::Error::new(io::ErrorKind::Unsupported, "Binding pings to network interface is only supported on Linux."));
+}
+
+#[cfg(target_os = "linux")]
+fn set_fwmark(socket: &Socket, fwmark: u32) -> io::Result<()> {
+    return socket.set_mark(fwmark);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_fwmark(_socket: &Socket, _fwmark: u32) -> io::Result<()> {
+    return Err(io::Error::new(io::ErrorKind::Unsupported, "Setting firewall mark of pings is only supported on Linux."));
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) mod linux {
+    use socket2::Socket;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    pub(crate) fn set_socket_option<T>(socket: &Socket, level: libc::c_int, option_name: libc::c_int, option_value: &T) -> io::Result<()> {
+        // Safe, because the option value is owned by the caller and the kernel never reads more than the length we pass in.
+        let result = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                option_name,
+                option_value as *const T as *const libc::c_void,
+                std::mem::size_of::<T>() as libc::socklen_t,
+            )
+        };
+
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_socket_option<T: Default>(socket: &Socket, level: libc::c_int, option_name: libc::c_int) -> io::Result<T> {
+        let mut option_value = T::default();
+        let mut option_len = std::mem::size_of::<T>() as libc::socklen_t;
+
+        // Safe, because the kernel never writes more than the length we pass in.
+        let result =
+            unsafe { libc::getsockopt(socket.as_raw_fd(), level, option_name, &mut option_value as *mut T as *mut libc::c_void, &mut option_len) };
+
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(option_value);
+    }
+}
@@ -1,3 +1,4 @@
+use super::{ping_client_flow_label, ping_client_socket_options, ping_client_tcp_info};
 use crate::*;
 use async_trait::async_trait;
 use socket2::{Domain, SockAddr, Socket, Type};
@@ -19,10 +20,11 @@ impl PingClientTcp {
 
     #[tracing::instrument(name = "Running TCP ping in ping client", level = "debug", skip(self))]
     async fn ping_target(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
-        let socket = self.prepare_socket_for_ping(source).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+        let socket = PingClientTcp::prepare_socket_for_ping(&self.config, source).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+        let target_addr = ping_client_flow_label::prepare_flow_label(&socket, target).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
 
         let start_time = Instant::now();
-        let connect_result = socket.connect_timeout(&SockAddr::from(target.clone()), self.config.wait_timeout);
+        let connect_result = socket.connect_timeout(&target_addr, self.config.wait_timeout);
         let rtt = Instant::now().duration_since(start_time);
         match connect_result {
             // Timeout is an expected value instead of an actual failure, so here we should return Ok.
@@ -32,6 +34,15 @@ impl PingClientTcp {
         }
         let local_addr = socket.local_addr();
 
+        // TCP_INFO is only used for helping troubleshooting, so failing to get it won't fail the ping.
+        let extra_details = match ping_client_tcp_info::read_tcp_info_details(&socket) {
+            Ok(details) => details,
+            Err(e) => {
+                tracing::debug!("Failed to read TCP_INFO, skipped; target={}, error={}", target, e);
+                PingResultExtraDetails::new()
+            }
+        };
+
         // Check closing connection as well as opening connection
         let mut warning: Option<PingClientWarning> = None;
         if self.config.check_disconnect {
@@ -45,24 +56,26 @@ impl PingClientTcp {
 
         // If getting local address failed, we ignore it.
         // The worse case we can get is to output a 0.0.0.0 as source IP, which is not critical to what we are trying to do.
-        return match local_addr {
-            Ok(addr) => Ok(PingClientPingResultDetails::new(Some(addr.as_socket().unwrap()), rtt, false, warning)),
-            Err(_) => Ok(PingClientPingResultDetails::new(None, rtt, false, warning)),
+        let mut result = match local_addr {
+            Ok(addr) => PingClientPingResultDetails::new(Some(addr.as_socket().unwrap()), rtt, false, warning),
+            Err(_) => PingClientPingResultDetails::new(None, rtt, false, warning),
         };
+        result.extra_details = extra_details;
+
+        return Ok(result);
     }
 
-    #[tracing::instrument(name = "Creating socket for ping", level = "debug", skip(self))]
-    fn prepare_socket_for_ping(&self, source: &SocketAddr) -> io::Result<Socket> {
+    // The socket preparation is shared with other TCP based ping clients, such as TLS, so they can behave the same way as TCP ping.
+    #[tracing::instrument(name = "Creating socket for ping", level = "debug", skip(config))]
+    pub(crate) fn prepare_socket_for_ping(config: &PingClientConfig, source: &SocketAddr) -> io::Result<Socket> {
         let socket_domain = if source.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
         let socket = Socket::new(socket_domain, Type::STREAM, None)?;
 
-        socket.set_read_timeout(Some(self.config.wait_timeout))?;
-        if !self.config.check_disconnect {
+        socket.set_read_timeout(Some(config.wait_timeout))?;
+        if !config.check_disconnect {
             socket.set_linger(Some(Duration::from_secs(0)))?;
         }
-        if let Some(ttl) = self.config.time_to_live {
-            socket.set_ttl(ttl)?;
-        }
+        ping_client_socket_options::apply_socket_options(&socket, config, source)?;
 
         socket.bind(&SockAddr::from(source.clone()))?;
 
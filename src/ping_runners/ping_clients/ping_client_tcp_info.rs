@@ -0,0 +1,112 @@
+use crate::*;
+use socket2::Socket;
+use std::io;
+
+/// Read TCP_INFO of the connected socket from kernel and convert it into ping result details. Since no data is sent
+/// right after connect, all retransmits counted by kernel are SYN retransmits.
+#[cfg(target_os = "linux")]
+pub(crate) fn read_tcp_info_details(socket: &Socket) -> io::Result<PingResultExtraDetails> {
+    let tcp_info = linux::read_tcp_info(socket)?;
+
+    let mut details = PingResultExtraDetails::new();
+    details.insert(PING_RESULT_EXTRA_DETAIL_TCP_RTT.to_string(), format!("{:.3}", tcp_info.tcpi_rtt as f64 / 1000.0));
+    details.insert(PING_RESULT_EXTRA_DETAIL_TCP_RTT_VAR.to_string(), format!("{:.3}", tcp_info.tcpi_rttvar as f64 / 1000.0));
+    details.insert(PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS.to_string(), tcp_info.tcpi_total_retrans.to_string());
+    details.insert(PING_RESULT_EXTRA_DETAIL_TCP_MSS.to_string(), tcp_info.tcpi_snd_mss.to_string());
+    details.insert(PING_RESULT_EXTRA_DETAIL_TCP_CONGESTION_STATE.to_string(), linux::get_congestion_state_name(tcp_info.tcpi_ca_state));
+    return Ok(details);
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn read_tcp_info_details(_socket: &Socket) -> io::Result<PingResultExtraDetails> {
+    return Ok(PingResultExtraDetails::new());
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use socket2::Socket;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    // The leading part of struct tcp_info defined in linux/tcp.h, which is stable across kernel versions. Kernel only
+    // copies as many bytes as we ask for, so we don't need to define the fields we don't use at the end.
+    #[repr(C)]
+    #[derive(Debug, Default)]
+    #[allow(dead_code)]
+    pub(super) struct TcpInfo {
+        pub tcpi_state: u8,
+        pub tcpi_ca_state: u8,
+        pub tcpi_retransmits: u8,
+        pub tcpi_probes: u8,
+        pub tcpi_backoff: u8,
+        pub tcpi_options: u8,
+        pub tcpi_snd_rcv_wscale: u8,
+        pub tcpi_delivery_rate_app_limited_fastopen_client_fail: u8,
+
+        pub tcpi_rto: u32,
+        pub tcpi_ato: u32,
+        pub tcpi_snd_mss: u32,
+        pub tcpi_rcv_mss: u32,
+
+        pub tcpi_unacked: u32,
+        pub tcpi_sacked: u32,
+        pub tcpi_lost: u32,
+        pub tcpi_retrans: u32,
+        pub tcpi_fackets: u32,
+
+        pub tcpi_last_data_sent: u32,
+        pub tcpi_last_ack_sent: u32,
+        pub tcpi_last_data_recv: u32,
+        pub tcpi_last_ack_recv: u32,
+
+        pub tcpi_pmtu: u32,
+        pub tcpi_rcv_ssthresh: u32,
+        pub tcpi_rtt: u32,
+        pub tcpi_rttvar: u32,
+        pub tcpi_snd_ssthresh: u32,
+        pub tcpi_snd_cwnd: u32,
+        pub tcpi_advmss: u32,
+        pub tcpi_reordering: u32,
+
+        pub tcpi_rcv_rtt: u32,
+        pub tcpi_rcv_space: u32,
+
+        pub tcpi_total_retrans: u32,
+    }
+
+    pub(super) fn read_tcp_info(socket: &Socket) -> io::Result<TcpInfo> {
+        let mut tcp_info = TcpInfo::default();
+        let mut tcp_info_len = std::mem::size_of::<TcpInfo>() as libc::socklen_t;
+
+        // Safe, because the buffer is owned by us and the kernel never writes more than the length we pass in.
+        let result = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_INFO,
+                &mut tcp_info as *mut TcpInfo as *mut libc::c_void,
+                &mut tcp_info_len,
+            )
+        };
+
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(tcp_info);
+    }
+
+    // Congestion control states defined as enum tcp_ca_state in linux/tcp.h.
+    pub(super) fn get_congestion_state_name(ca_state: u8) -> String {
+        let name = match ca_state {
+            0 => "Open",
+            1 => "Disorder",
+            2 => "CWR",
+            3 => "Recovery",
+            4 => "Loss",
+            _ => return ca_state.to_string(),
+        };
+
+        return name.to_string();
+    }
+}
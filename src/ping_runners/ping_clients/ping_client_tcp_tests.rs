@@ -1,6 +1,10 @@
 use crate::ping_clients::ping_client_test_common::*;
 use crate::stub_servers::stub_server_factory;
 use crate::{ping_clients::ping_client_factory, rnp_test_common, PingClientConfig, RnpStubServerConfig, RnpSupportedProtocol};
+use crate::{
+    PING_RESULT_EXTRA_DETAIL_TCP_CONGESTION_STATE, PING_RESULT_EXTRA_DETAIL_TCP_MSS, PING_RESULT_EXTRA_DETAIL_TCP_RTT,
+    PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS,
+};
 use futures_intrusive::sync::ManualResetEvent;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -23,6 +27,75 @@ fn ping_client_tcp_should_work_when_pinging_good_host() {
     });
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_client_tcp_should_report_tcp_info_when_pinging_good_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11343".parse::<SocketAddr>().unwrap();
+    let server_config = create_tcp_stub_server_default_config(&server_address);
+    start_run_tcp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_tcp_default_config();
+        let ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TCP, &config, None);
+
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let result = ping_client.ping(&source, &server_address).await.unwrap();
+        let extra_details = &result.extra_details;
+        assert!(extra_details.contains_key(PING_RESULT_EXTRA_DETAIL_TCP_RTT));
+        assert!(extra_details.contains_key(PING_RESULT_EXTRA_DETAIL_TCP_MSS));
+        assert_eq!(Some(&String::from("0")), extra_details.get(PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS));
+        assert_eq!(Some(&String::from("Open")), extra_details.get(PING_RESULT_EXTRA_DETAIL_TCP_CONGESTION_STATE));
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_client_tcp_should_set_type_of_service_on_socket() {
+    use crate::ping_clients::ping_client_tcp::PingClientTcp;
+
+    let mut config = create_ping_client_tcp_default_config();
+    config.type_of_service = Some(184);
+
+    for source in ["127.0.0.1:0", "[::1]:0"] {
+        let source = source.parse::<SocketAddr>().unwrap();
+        let socket = PingClientTcp::prepare_socket_for_ping(&config, &source).unwrap();
+        assert_eq!(184, get_socket_type_of_service(&socket, &source));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_client_tcp_should_bind_socket_to_interface() {
+    use crate::ping_clients::ping_client_tcp::PingClientTcp;
+
+    let mut config = create_ping_client_tcp_default_config();
+    config.interface = Some("lo".to_string());
+
+    let source = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+    let socket = PingClientTcp::prepare_socket_for_ping(&config, &source).unwrap();
+    assert_eq!(Some(b"lo".to_vec()), socket.device().unwrap());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_client_tcp_should_set_fwmark_on_socket() {
+    use crate::ping_clients::ping_client_tcp::PingClientTcp;
+
+    let mut config = create_ping_client_tcp_default_config();
+    config.fwmark = Some(0x1234);
+
+    // Setting SO_MARK requires CAP_NET_ADMIN, so skip the test when we are not allowed to do it.
+    let source = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+    let socket = match PingClientTcp::prepare_socket_for_ping(&config, &source) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => return,
+        result => result.unwrap(),
+    };
+    assert_eq!(0x1234, socket.mark().unwrap());
+}
+
 #[test]
 fn ping_client_tcp_should_fail_when_binding_unavailable_source_port() {
     rnp_test_common::initialize();
@@ -155,6 +228,9 @@ fn create_tcp_stub_server_default_config(server_address: &SocketAddr) -> RnpStub
         write_count_limit: 0,
         report_interval: Duration::from_secs(1),
         wait_before_disconnect: Duration::ZERO,
+        server_certificate_path: None,
+        server_private_key_path: None,
+        alpn_protocols: vec![],
     };
 }
 
@@ -171,6 +247,10 @@ fn create_ping_client_tcp_default_config() -> PingClientConfig {
     return PingClientConfig {
         wait_timeout: Duration::from_millis(300),
         time_to_live: None,
+        flow_label: None,
+        type_of_service: None,
+        interface: None,
+        fwmark: None,
         check_disconnect: false,
         wait_before_disconnect: Duration::ZERO,
         disconnect_timeout: Duration::ZERO,
@@ -178,5 +258,10 @@ fn create_ping_client_tcp_default_config() -> PingClientConfig {
         log_tls_key: false,
         alpn_protocol: None,
         use_timer_rtt: false,
+        http_method: String::from("GET"),
+        http_path: String::from("/"),
+        http_host: None,
+        http_headers: vec![],
+        http_expected_status_codes: vec![],
     };
 }
@@ -103,3 +103,14 @@ pub async fn ping_client_result_should_be_expected(
         }
     }
 }
+
+#[cfg(target_os = "linux")]
+pub fn get_socket_type_of_service(socket: &socket2::Socket, source: &SocketAddr) -> u8 {
+    if source.is_ipv4() {
+        return socket.tos().unwrap() as u8;
+    }
+
+    let traffic_class: libc::c_int =
+        crate::ping_clients::ping_client_socket_options::linux::get_socket_option(socket, libc::IPPROTO_IPV6, libc::IPV6_TCLASS).unwrap();
+    return traffic_class as u8;
+}
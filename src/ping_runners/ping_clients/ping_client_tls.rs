@@ -0,0 +1,174 @@
+use crate::ping_clients::ping_client_flow_label;
+use crate::ping_clients::ping_client_quic::SkipCertificationVerification;
+use crate::ping_clients::ping_client_tcp::PingClientTcp;
+use crate::*;
+use async_trait::async_trait;
+use rustls::{ClientConfig, KeyLogFile, NoClientSessionStorage, Session};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+use webpki::DNSNameRef;
+
+// rustls always requires a DNS name for connecting, so when server name is not specified, we use this name as placeholder,
+// and disable SNI to avoid sending it out.
+const TLS_PLACEHOLDER_SERVER_NAME: &str = "localhost";
+
+pub struct PingClientTls {
+    config: PingClientConfig,
+    tls_connector: TlsConnector,
+}
+
+impl PingClientTls {
+    pub fn new(config: &PingClientConfig) -> PingClientTls {
+        let tls_config = PingClientTls::create_tls_config(config);
+        return PingClientTls { config: config.clone(), tls_connector: TlsConnector::from(Arc::new(tls_config)) };
+    }
+
+    fn create_tls_config(config: &PingClientConfig) -> ClientConfig {
+        let mut tls_config = ClientConfig::new();
+
+        // Setup ALPN protocol if specified.
+        if let Some(alpn_protocol) = &config.alpn_protocol {
+            tls_config.set_protocols(&[alpn_protocol.as_bytes().to_vec()]);
+        }
+
+        if config.log_tls_key {
+            tls_config.key_log = Arc::new(KeyLogFile::new());
+        }
+
+        if config.server_name.is_none() {
+            tls_config.enable_sni = false;
+        }
+
+        // Every ping should do a full handshake, so we disable all kinds of session resumption here.
+        tls_config.session_persistence = Arc::new(NoClientSessionStorage {});
+        tls_config.enable_tickets = false;
+
+        tls_config.dangerous().set_certificate_verifier(Arc::new(SkipCertificationVerification));
+        return tls_config;
+    }
+
+    #[tracing::instrument(name = "Running TLS ping in ping client", level = "debug", skip(self))]
+    async fn ping_target(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
+        let server_name = self.config.server_name.as_ref().map_or(TLS_PLACEHOLDER_SERVER_NAME, |s| s);
+        let server_name = DNSNameRef::try_from_ascii_str(server_name).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+        let socket = PingClientTcp::prepare_socket_for_ping(&self.config, source).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+        let target_addr = ping_client_flow_label::prepare_flow_label(&socket, target).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+
+        let start_time = Instant::now();
+        let connect_result = socket.connect_timeout(&target_addr, self.config.wait_timeout);
+        let rtt = Instant::now().duration_since(start_time);
+        match connect_result {
+            // Timeout is an expected value instead of an actual failure, so here we should return Ok.
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(PingClientPingResultDetails::new(None, rtt, true, None)),
+            Err(e) => return Err(PingClientError::PingFailed(Box::new(e))),
+            Ok(()) => (),
+        }
+
+        // If getting local address failed, we ignore it.
+        // The worse case we can get is to output a 0.0.0.0 as source IP, which is not critical to what we are trying to do.
+        let local_addr = socket.local_addr().map_or(None, |addr| addr.as_socket());
+
+        socket.set_nonblocking(true).map_err(|e| PingClientError::PingFailed(Box::new(e)))?;
+        let connection = TcpStream::from_std(socket.into()).map_err(|e| PingClientError::PingFailed(Box::new(e)))?;
+
+        let handshake_start_time = Instant::now();
+        let handshake_result = tokio::time::timeout(self.config.wait_timeout, self.tls_connector.connect(server_name, connection)).await;
+        let handshake_time = Instant::now().duration_since(handshake_start_time);
+
+        let mut ping_result = PingClientPingResultDetails::new(local_addr, rtt, false, None);
+        ping_result.handshake_time = Some(handshake_time);
+
+        // When TLS handshake fails, the TCP connection is already established, which means the underlying network is reachable,
+        // but TLS termination went wrong, so here, we log this failure as warning instead, the same as QUIC.
+        let tls_stream = match handshake_result {
+            Err(_) => {
+                let e = io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out.");
+                ping_result.warning = Some(PingClientWarning::AppHandshakeFailed(Box::new(e)));
+                return Ok(ping_result);
+            }
+            Ok(Err(e)) => {
+                ping_result.warning = Some(PingClientWarning::AppHandshakeFailed(Box::new(e)));
+                return Ok(ping_result);
+            }
+            Ok(Ok(tls_stream)) => tls_stream,
+        };
+
+        // Not all servers reject the handshake when none of the ALPN protocols is supported, some of them simply skip the
+        // negotiation, so we need to check the negotiated protocol by ourselves.
+        if let Some(alpn_protocol) = &self.config.alpn_protocol {
+            let (_, session) = tls_stream.get_ref();
+            if session.get_alpn_protocol() != Some(alpn_protocol.as_bytes()) {
+                let e = io::Error::new(io::ErrorKind::InvalidData, format!("ALPN protocol is not accepted by server: {}", alpn_protocol));
+                ping_result.warning = Some(PingClientWarning::AppHandshakeFailed(Box::new(e)));
+                return Ok(ping_result);
+            }
+        }
+
+        // Check closing connection as well as opening connection
+        if self.config.check_disconnect {
+            if let Err(e) = self.shutdown_connection(tls_stream, target).await {
+                ping_result.warning = Some(PingClientWarning::DisconnectFailed(Box::new(e)));
+            }
+        }
+
+        return Ok(ping_result);
+    }
+
+    #[tracing::instrument(name = "Shutdown TLS connection after ping", level = "debug", skip(self, tls_stream))]
+    async fn shutdown_connection(&self, mut tls_stream: TlsStream<TcpStream>, target: &SocketAddr) -> io::Result<()> {
+        if !self.config.wait_before_disconnect.is_zero() {
+            tracing::debug!("Waiting {:?} before disconnect; target={}", self.config.wait_before_disconnect, target);
+            tokio::time::sleep(self.config.wait_before_disconnect).await;
+        }
+
+        // Shutdown sends the close_notify alert and then shuts down the TCP connection write.
+        tracing::debug!("Shutdown TLS connection; target={}", target);
+        tls_stream.shutdown().await?;
+
+        // Try to read until recv returns nothing, which indicates shutdown is succeeded.
+        tracing::debug!("Wait until shutdown completes; timeout={:?}, target={}", self.config.disconnect_timeout, target);
+        let mut read_buffer = vec![0 as u8; 128];
+        if self.config.disconnect_timeout.is_zero() {
+            PingClientTls::wait_for_connection_shutdown(&mut tls_stream, &mut read_buffer).await?;
+        } else {
+            let wait_result =
+                tokio::time::timeout(self.config.disconnect_timeout, PingClientTls::wait_for_connection_shutdown(&mut tls_stream, &mut read_buffer))
+                    .await;
+            match wait_result {
+                Err(_) => return Err(io::Error::new(io::ErrorKind::TimedOut, "Disconnect timed out.")),
+                Ok(result) => result?,
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn wait_for_connection_shutdown(tls_stream: &mut TlsStream<TcpStream>, read_buffer: &mut Vec<u8>) -> io::Result<()> {
+        while tls_stream.read(&mut read_buffer[..]).await? > 0 {
+            continue;
+        }
+
+        return Ok(());
+    }
+}
+
+#[async_trait]
+impl PingClient for PingClientTls {
+    fn protocol(&self) -> &'static str {
+        "TLS"
+    }
+
+    async fn prepare_ping(&mut self, _: &SocketAddr) -> Result<(), PingClientError> {
+        Ok(())
+    }
+
+    async fn ping(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
+        return self.ping_target(source, target).await;
+    }
+}
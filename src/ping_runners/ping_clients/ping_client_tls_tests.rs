@@ -0,0 +1,224 @@
+use crate::ping_clients::ping_client_test_common::*;
+use crate::stub_servers::stub_server_factory;
+use crate::{ping_clients::ping_client_factory, rnp_test_common, PingClientConfig, RnpStubServerConfig, RnpSupportedProtocol};
+use futures_intrusive::sync::ManualResetEvent;
+use rustls::{Certificate, NoClientAuth, PrivateKey, ServerConfig};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+use tokio_rustls::TlsAcceptor;
+
+#[test]
+fn ping_client_tls_should_work_when_pinging_good_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11361".parse::<SocketAddr>().unwrap();
+    start_run_tls_test_server(&rt, &server_address, vec!["http/1.1"]);
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        ping_client_should_work_when_pinging_good_host(&mut ping_client, &server_address).await;
+
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let ping_result = ping_client.ping(&source, &server_address).await.unwrap();
+        assert!(ping_result.warning.is_none());
+        assert!(ping_result.handshake_time.is_some());
+    });
+}
+
+#[test]
+fn ping_client_tls_should_work_when_pinging_good_host_with_check_disconnect() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11362".parse::<SocketAddr>().unwrap();
+    start_run_tls_test_server(&rt, &server_address, vec!["http/1.1"]);
+
+    rt.block_on(async move {
+        let mut config = create_ping_client_tls_default_config();
+        config.check_disconnect = true;
+        config.disconnect_timeout = Duration::from_millis(1000);
+
+        let ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let ping_result = ping_client.ping(&source, &server_address).await.unwrap();
+        assert!(!ping_result.is_timeout);
+        assert!(ping_result.warning.is_none());
+    });
+}
+
+#[test]
+fn ping_client_tls_should_warn_when_alpn_mismatches() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11363".parse::<SocketAddr>().unwrap();
+    start_run_tls_test_server(&rt, &server_address, vec!["h2"]);
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let expected_result = ExpectedTestCaseResult::Warning("ALPN protocol is not accepted by server: http/1.1");
+        ping_client_result_should_be_expected(&mut ping_client, &source, &server_address, Duration::from_millis(200), &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_tls_should_warn_when_server_closes_connection_on_accept() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11364".parse::<SocketAddr>().unwrap();
+    let mut server_config = create_tcp_stub_server_default_config(&server_address);
+    server_config.close_on_accept = true;
+    start_run_tcp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let expected_result = ExpectedTestCaseResult::Warning("tls handshake eof");
+        ping_client_result_should_be_expected(&mut ping_client, &source, &server_address, Duration::from_millis(200), &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_tls_should_warn_when_server_does_not_speak_tls() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11365".parse::<SocketAddr>().unwrap();
+    let server_config = create_tcp_stub_server_default_config(&server_address);
+    start_run_tcp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        let expected_result = ExpectedTestCaseResult::Warning("TLS handshake timed out.");
+        ping_client_result_should_be_expected(&mut ping_client, &source, &server_address, Duration::from_millis(200), &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_tls_should_fail_when_pinging_non_existing_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+        ping_client_should_fail_when_pinging_non_existing_host(&mut ping_client, &ExpectedTestCaseResult::Timeout).await;
+    });
+}
+
+#[test]
+fn ping_client_tls_should_fail_when_pinging_non_existing_port() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+
+        let expected_result = if cfg!(windows) { ExpectedTestCaseResult::Timeout } else { ExpectedTestCaseResult::Failed("connection refused") };
+        ping_client_should_fail_when_pinging_non_existing_port(&mut ping_client, &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_tls_should_fail_when_binding_invalid_source_ip() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_tls_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::TLS, &config, None);
+
+        let expected_result = ExpectedTestCaseResult::Failed("The requested address is not valid in its context. (os error 10049)");
+        ping_client_should_fail_when_binding_invalid_source_ip(&mut ping_client, &expected_result).await;
+    });
+}
+
+// Stub server doesn't support TLS, so we use a minimal TLS server here, which completes the handshake and waits for the
+// client to close the connection.
+fn start_run_tls_test_server(rt: &Runtime, server_address: &SocketAddr, alpn_protocols: Vec<&str>) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let mut server_config = ServerConfig::new(NoClientAuth::new());
+    server_config.set_single_cert(vec![Certificate(cert.serialize_der().unwrap())], PrivateKey(cert.serialize_private_key_der())).unwrap();
+    server_config.set_protocols(&alpn_protocols.iter().map(|p| p.as_bytes().to_vec()).collect::<Vec<Vec<u8>>>());
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+    let listener = rt.block_on(TcpListener::bind(server_address)).unwrap();
+    rt.spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(mut tls_stream) = acceptor.accept(stream).await {
+                    let mut read_buffer = vec![0 as u8; 128];
+                    while let Ok(n) = tls_stream.read(&mut read_buffer).await {
+                        if n == 0 {
+                            break;
+                        }
+                    }
+                    let _ = tls_stream.shutdown().await;
+                }
+            });
+        }
+    });
+}
+
+fn create_tcp_stub_server_default_config(server_address: &SocketAddr) -> RnpStubServerConfig {
+    return RnpStubServerConfig {
+        protocol: RnpSupportedProtocol::TCP,
+        server_address: server_address.clone(),
+        close_on_accept: false,
+        sleep_before_write: Duration::ZERO,
+        write_chunk_size: 0,
+        write_count_limit: 0,
+        report_interval: Duration::from_secs(1),
+        wait_before_disconnect: Duration::ZERO,
+        server_certificate_path: None,
+        server_private_key_path: None,
+        alpn_protocols: vec![],
+    };
+}
+
+fn start_run_tcp_stub_server(rt: &Runtime, stub_server_config: RnpStubServerConfig) {
+    let ready_event = Arc::new(ManualResetEvent::new(false));
+    let ready_event_clone = ready_event.clone();
+    rt.spawn(async move {
+        let _ = stub_server_factory::run(&stub_server_config, Arc::new(ManualResetEvent::new(false)), ready_event_clone).await;
+    });
+    rt.block_on(ready_event.wait());
+}
+
+fn create_ping_client_tls_default_config() -> PingClientConfig {
+    return PingClientConfig {
+        wait_timeout: Duration::from_millis(300),
+        time_to_live: None,
+        flow_label: None,
+        type_of_service: None,
+        interface: None,
+        fwmark: None,
+        check_disconnect: false,
+        wait_before_disconnect: Duration::ZERO,
+        disconnect_timeout: Duration::ZERO,
+        server_name: Some("localhost".to_string()),
+        log_tls_key: false,
+        alpn_protocol: Some("http/1.1".to_string()),
+        use_timer_rtt: false,
+        http_method: String::from("GET"),
+        http_path: String::from("/"),
+        http_host: None,
+        http_headers: vec![],
+        http_expected_status_codes: vec![],
+    };
+}
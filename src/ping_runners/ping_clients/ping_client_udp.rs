@@ -0,0 +1,126 @@
+use crate::ping_clients::{ping_client_flow_label, ping_client_socket_options};
+use crate::*;
+use async_trait::async_trait;
+use socket2::{Domain, SockAddr, Socket, Type};
+use std::convert::TryInto;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+// Each ping packet carries a magic, the sequence number and the local timestamp when the packet is sent.
+// The sequence number is used to match the echo with the request, so late echoes from previous pings won't be
+// counted as the reply of the current one.
+const UDP_PING_PACKET_MAGIC: &[u8; 4] = b"RNP\0";
+const UDP_PING_PACKET_SIZE: usize = 20;
+
+pub struct PingClientUdp {
+    config: PingClientConfig,
+    next_sequence: AtomicU64,
+}
+
+impl PingClientUdp {
+    pub fn new(config: &PingClientConfig) -> PingClientUdp {
+        return PingClientUdp { config: config.clone(), next_sequence: AtomicU64::new(0) };
+    }
+
+    #[tracing::instrument(name = "Running UDP ping in ping client", level = "debug", skip(self))]
+    async fn ping_target(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
+        let socket = self.prepare_socket_for_ping(source, target).map_err(|e| PingClientError::PreparationFailed(Box::new(e)))?;
+
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        let request = PingClientUdp::build_ping_packet(sequence);
+
+        let start_time = Instant::now();
+        socket.send(&request).await.map_err(|e| PingClientError::PingFailed(Box::new(e)))?;
+
+        let wait_result = tokio::time::timeout(self.config.wait_timeout, PingClientUdp::wait_for_echo(&socket, sequence)).await;
+        let rtt = Instant::now().duration_since(start_time);
+        match wait_result {
+            // The ICMP port unreachable is saved as pending socket error, which doesn't wake up the pending read on
+            // every platform, so before treating it as timeout, we check the socket error once.
+            Err(_) => {
+                if let Ok(Some(e)) = socket.take_error() {
+                    return Err(PingClientError::PingFailed(Box::new(e)));
+                }
+
+                // Timeout is an expected value instead of an actual failure, so here we should return Ok.
+                return Ok(PingClientPingResultDetails::new(None, rtt, true, None));
+            }
+            Ok(Err(e)) => return Err(PingClientError::PingFailed(Box::new(e))),
+            Ok(Ok(())) => (),
+        }
+
+        // If getting local address failed, we ignore it.
+        // The worse case we can get is to output a 0.0.0.0 as source IP, which is not critical to what we are trying to do.
+        return match socket.local_addr() {
+            Ok(addr) => Ok(PingClientPingResultDetails::new(Some(addr), rtt, false, None)),
+            Err(_) => Ok(PingClientPingResultDetails::new(None, rtt, false, None)),
+        };
+    }
+
+    #[tracing::instrument(name = "Creating socket for ping", level = "debug", skip(self))]
+    fn prepare_socket_for_ping(&self, source: &SocketAddr, target: &SocketAddr) -> io::Result<UdpSocket> {
+        let socket_domain = if source.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
+        let socket = Socket::new(socket_domain, Type::DGRAM, None)?;
+
+        ping_client_socket_options::apply_socket_options(&socket, &self.config, source)?;
+
+        socket.bind(&SockAddr::from(source.clone()))?;
+
+        // Connecting the socket makes the OS filter out the datagrams from other endpoints, and also allows us to
+        // receive the ICMP port unreachable as connection refused error, without using any raw socket.
+        let target_addr = ping_client_flow_label::prepare_flow_label(&socket, target)?;
+        socket.connect(&target_addr)?;
+        socket.set_nonblocking(true)?;
+
+        return UdpSocket::from_std(socket.into());
+    }
+
+    fn build_ping_packet(sequence: u64) -> Vec<u8> {
+        let timestamp = chrono::Utc::now().timestamp_nanos() as u64;
+
+        let mut packet = Vec::with_capacity(UDP_PING_PACKET_SIZE);
+        packet.extend_from_slice(UDP_PING_PACKET_MAGIC);
+        packet.extend_from_slice(&sequence.to_be_bytes());
+        packet.extend_from_slice(&timestamp.to_be_bytes());
+        return packet;
+    }
+
+    fn parse_ping_packet_sequence(packet: &[u8]) -> Option<u64> {
+        if packet.len() < UDP_PING_PACKET_SIZE || &packet[0..4] != UDP_PING_PACKET_MAGIC {
+            return None;
+        }
+
+        return Some(u64::from_be_bytes(packet[4..12].try_into().unwrap()));
+    }
+
+    async fn wait_for_echo(socket: &UdpSocket, sequence: u64) -> io::Result<()> {
+        let mut read_buffer = vec![0 as u8; 1500];
+        loop {
+            let read_size = socket.recv(&mut read_buffer).await?;
+            match PingClientUdp::parse_ping_packet_sequence(&read_buffer[..read_size]) {
+                Some(echo_sequence) if echo_sequence == sequence => return Ok(()),
+                echo_sequence => {
+                    tracing::debug!("Unexpected echo received, skipping; expected_sequence={}, echo_sequence={:?}", sequence, echo_sequence);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PingClient for PingClientUdp {
+    fn protocol(&self) -> &'static str {
+        "UDP"
+    }
+
+    async fn prepare_ping(&mut self, _: &SocketAddr) -> Result<(), PingClientError> {
+        Ok(())
+    }
+
+    async fn ping(&self, source: &SocketAddr, target: &SocketAddr) -> PingClientResult<PingClientPingResultDetails> {
+        return self.ping_target(source, target).await;
+    }
+}
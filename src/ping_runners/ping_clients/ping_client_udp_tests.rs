@@ -0,0 +1,129 @@
+use crate::ping_clients::ping_client_test_common::*;
+use crate::stub_servers::stub_server_factory;
+use crate::{ping_clients::ping_client_factory, rnp_test_common, PingClientConfig, RnpStubServerConfig, RnpSupportedProtocol};
+use futures_intrusive::sync::ManualResetEvent;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[test]
+fn ping_client_udp_should_work_when_pinging_good_host() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11341".parse::<SocketAddr>().unwrap();
+    let server_config = create_udp_stub_server_default_config(&server_address);
+    start_run_udp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_udp_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::UDP, &config, None);
+        ping_client_should_work_when_pinging_good_host(&mut ping_client, &server_address).await;
+    });
+}
+
+#[test]
+fn ping_client_udp_should_timeout_when_echo_is_delayed() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    let server_address = "127.0.0.1:11342".parse::<SocketAddr>().unwrap();
+    let mut server_config = create_udp_stub_server_default_config(&server_address);
+    server_config.sleep_before_write = Duration::from_millis(1000);
+    start_run_udp_stub_server(&rt, server_config);
+
+    rt.block_on(async move {
+        let config = create_ping_client_udp_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::UDP, &config, None);
+        let source = "0.0.0.0:0".parse::<SocketAddr>().unwrap();
+        ping_client_result_should_be_expected(
+            &mut ping_client,
+            &source,
+            &server_address,
+            Duration::from_millis(200),
+            &ExpectedTestCaseResult::Timeout,
+        )
+        .await;
+    });
+}
+
+#[test]
+fn ping_client_udp_should_fail_when_pinging_non_existing_port() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_udp_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::UDP, &config, None);
+
+        let expected_result = if cfg!(windows) {
+            ExpectedTestCaseResult::Failed("An existing connection was forcibly closed by the remote host. (os error 10054)")
+        } else {
+            ExpectedTestCaseResult::Failed("connection refused")
+        };
+        ping_client_should_fail_when_pinging_non_existing_port(&mut ping_client, &expected_result).await;
+    });
+}
+
+#[test]
+fn ping_client_udp_should_fail_when_binding_invalid_source_ip() {
+    rnp_test_common::initialize();
+    let rt = Runtime::new().unwrap();
+
+    rt.block_on(async move {
+        let config = create_ping_client_udp_default_config();
+        let mut ping_client = ping_client_factory::new_ping_client(&RnpSupportedProtocol::UDP, &config, None);
+
+        let expected_result = ExpectedTestCaseResult::Failed("The requested address is not valid in its context. (os error 10049)");
+        ping_client_should_fail_when_binding_invalid_source_ip(&mut ping_client, &expected_result).await;
+    });
+}
+
+fn create_udp_stub_server_default_config(server_address: &SocketAddr) -> RnpStubServerConfig {
+    return RnpStubServerConfig {
+        protocol: RnpSupportedProtocol::UDP,
+        server_address: server_address.clone(),
+        close_on_accept: false,
+        sleep_before_write: Duration::ZERO,
+        write_chunk_size: 0,
+        write_count_limit: 0,
+        report_interval: Duration::from_secs(1),
+        wait_before_disconnect: Duration::ZERO,
+        server_certificate_path: None,
+        server_private_key_path: None,
+        alpn_protocols: vec![],
+    };
+}
+
+fn start_run_udp_stub_server(rt: &Runtime, stub_server_config: RnpStubServerConfig) {
+    let ready_event = Arc::new(ManualResetEvent::new(false));
+    let ready_event_clone = ready_event.clone();
+    rt.spawn(async move {
+        let _ = stub_server_factory::run(&stub_server_config, Arc::new(ManualResetEvent::new(false)), ready_event_clone).await;
+    });
+    rt.block_on(ready_event.wait());
+}
+
+fn create_ping_client_udp_default_config() -> PingClientConfig {
+    return PingClientConfig {
+        wait_timeout: Duration::from_millis(300),
+        time_to_live: None,
+        flow_label: None,
+        type_of_service: None,
+        interface: None,
+        fwmark: None,
+        check_disconnect: false,
+        wait_before_disconnect: Duration::ZERO,
+        disconnect_timeout: Duration::ZERO,
+        server_name: None,
+        log_tls_key: false,
+        alpn_protocol: None,
+        use_timer_rtt: false,
+        http_method: String::from("GET"),
+        http_path: String::from("/"),
+        http_host: None,
+        http_headers: vec![],
+        http_expected_status_codes: vec![],
+    };
+}
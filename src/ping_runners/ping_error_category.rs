@@ -0,0 +1,141 @@
+use crate::*;
+use std::fmt;
+use std::io;
+
+#[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+use quinn::ConnectionError;
+
+/// Category of the ping failures, which is used to group failures without parsing the error messages. App handshake
+/// failures are also categorized here, even if the ping itself is counted as succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PingErrorCategory {
+    ConnectionRefused,
+    ConnectionReset,
+    HostUnreachable,
+    NetworkUnreachable,
+    Timeout,
+    AddressInUse,
+    PreparationFailed,
+    TlsHandshakeFailed,
+    Other,
+}
+
+impl fmt::Display for PingErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl PingErrorCategory {
+    pub fn from_ping_result(ping_result: &PingResult) -> Option<PingErrorCategory> {
+        if ping_result.is_timed_out() {
+            return Some(PingErrorCategory::Timeout);
+        }
+
+        match ping_result.error() {
+            Some(PingClientError::PreparationFailed(_)) => return Some(PingErrorCategory::PreparationFailed),
+            Some(PingClientError::PingFailed(e)) => return Some(PingErrorCategory::from_error(e.as_ref())),
+            None => (),
+        }
+
+        // For TLS and QUIC, the app handshake is the TLS handshake, which covers the ALPN negotiation too. So whatever
+        // goes wrong there, it is a TLS failure, even if the underlying error is something like timeout.
+        if let Some(PingClientWarning::AppHandshakeFailed(e)) = ping_result.warning() {
+            return match ping_result.protocol() {
+                "TLS" | "QUIC" => Some(PingErrorCategory::TlsHandshakeFailed),
+                _ => Some(PingErrorCategory::from_error(e.as_ref())),
+            };
+        }
+
+        return None;
+    }
+
+    pub fn from_error(error: &(dyn std::error::Error + 'static)) -> PingErrorCategory {
+        if let Some(io_error) = error.downcast_ref::<io::Error>() {
+            return PingErrorCategory::from_io_error(io_error);
+        }
+
+        #[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+        {
+            if error.downcast_ref::<rustls::TLSError>().is_some() {
+                return PingErrorCategory::TlsHandshakeFailed;
+            }
+
+            if let Some(connection_error) = error.downcast_ref::<ConnectionError>() {
+                return match connection_error {
+                    ConnectionError::TimedOut => PingErrorCategory::Timeout,
+                    ConnectionError::Reset => PingErrorCategory::ConnectionReset,
+                    ConnectionError::TransportError(_) | ConnectionError::ConnectionClosed(_) | ConnectionError::ApplicationClosed(_) => {
+                        PingErrorCategory::TlsHandshakeFailed
+                    }
+                    ConnectionError::VersionMismatch | ConnectionError::LocallyClosed => PingErrorCategory::Other,
+                };
+            }
+        }
+
+        return PingErrorCategory::Other;
+    }
+
+    fn from_io_error(io_error: &io::Error) -> PingErrorCategory {
+        // tokio-rustls wraps the TLS errors into io errors, so we need to check the inner error first.
+        if let Some(inner_error) = io_error.get_ref() {
+            let category = PingErrorCategory::from_error(inner_error);
+            if category != PingErrorCategory::Other {
+                return category;
+            }
+        }
+
+        return match io_error.kind() {
+            io::ErrorKind::ConnectionRefused => PingErrorCategory::ConnectionRefused,
+            io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted => PingErrorCategory::ConnectionReset,
+            io::ErrorKind::HostUnreachable => PingErrorCategory::HostUnreachable,
+            io::ErrorKind::NetworkUnreachable => PingErrorCategory::NetworkUnreachable,
+            io::ErrorKind::TimedOut => PingErrorCategory::Timeout,
+            io::ErrorKind::AddrInUse => PingErrorCategory::AddressInUse,
+            _ => PingErrorCategory::Other,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnp_test_common;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn error_category_should_be_detected_from_io_error() {
+        let test_cases = vec![
+            (io::ErrorKind::ConnectionRefused, PingErrorCategory::ConnectionRefused),
+            (io::ErrorKind::ConnectionReset, PingErrorCategory::ConnectionReset),
+            (io::ErrorKind::ConnectionAborted, PingErrorCategory::ConnectionReset),
+            (io::ErrorKind::HostUnreachable, PingErrorCategory::HostUnreachable),
+            (io::ErrorKind::NetworkUnreachable, PingErrorCategory::NetworkUnreachable),
+            (io::ErrorKind::TimedOut, PingErrorCategory::Timeout),
+            (io::ErrorKind::AddrInUse, PingErrorCategory::AddressInUse),
+            (io::ErrorKind::InvalidData, PingErrorCategory::Other),
+        ];
+
+        for (error_kind, expected_category) in test_cases {
+            let error = io::Error::new(error_kind, "test error");
+            assert_eq!(expected_category, PingErrorCategory::from_error(&error), "error_kind = {:?}", error_kind);
+        }
+    }
+
+    #[test]
+    fn error_category_should_be_detected_from_ping_result() {
+        let results = rnp_test_common::generate_ping_result_test_samples();
+        let categories: Vec<Option<PingErrorCategory>> = results.iter().map(PingErrorCategory::from_ping_result).collect();
+        assert_eq!(
+            vec![
+                None,
+                Some(PingErrorCategory::Timeout),
+                Some(PingErrorCategory::ConnectionReset),
+                None,
+                Some(PingErrorCategory::ConnectionRefused),
+                Some(PingErrorCategory::PreparationFailed),
+            ],
+            categories
+        );
+    }
+}
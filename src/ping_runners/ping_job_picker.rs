@@ -0,0 +1,622 @@
+use crate::{PingFlowLabel, PingPortPicker, PingPortPickingStrategy, PingRateLimiter, PortRangeList, PING_FLOW_LABEL_MAX};
+use contracts::requires;
+use std::collections::VecDeque;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PingJob {
+    pub target: SocketAddr,
+    pub source_ip: IpAddr,
+    pub source_port: u16,
+
+    /// Only set for IPv6 targets, when the flow label is enabled.
+    pub flow_label: Option<u32>,
+
+    /// 0 for normal pings, otherwise the index of the re-probe on the same target and source port, starting from 1.
+    pub reprobe_index: u32,
+}
+
+pub struct PingJobPicker {
+    // Pairs of target and source IP to ping, rotated after all targets are pinged from the same source IP.
+    paths: Vec<(SocketAddr, IpAddr)>,
+    port_picker: PingPortPicker,
+    port_count: usize,
+    next_job_index: usize,
+
+    reprobe_count: u32,
+    reprobe_interval: Duration,
+    reprobe_jobs: VecDeque<(Instant, PingJob)>,
+
+    rate_limiter: Option<PingRateLimiter>,
+    rate_limited_until: Option<Instant>,
+
+    deadline: Option<Instant>,
+    is_port_cross_product_enabled: bool,
+    flow_label: Option<PingFlowLabel>,
+}
+
+impl PingJobPicker {
+    /// Both ping count and skip count are counted per target and source IP, i.e. each pair of them is pinged for the
+    /// same times. The pairs are rotated in every ping, so each ping is still using a different source port as before,
+    /// and concurrent pings never share the same source port.
+    ///
+    /// Each target is only paired with the source IPs in the same IP family, and the unspecified source IP is paired with
+    /// all targets.
+    #[allow(unreachable_code)]
+    #[requires(targets.len() > 0)]
+    #[requires(source_ips.len() > 0)]
+    #[requires(targets.iter().all(|target| source_ips.iter().any(|source_ip| PingJobPicker::pair_source_ip(source_ip, target).is_some())))]
+    pub fn new(
+        ping_count_per_target: Option<u32>,
+        targets: Vec<SocketAddr>,
+        source_ips: Vec<IpAddr>,
+        port_ranges: PortRangeList,
+        skip_ping_count_per_target: u32,
+        port_picking_strategy: PingPortPickingStrategy,
+        port_picking_seed: u64,
+    ) -> PingJobPicker {
+        let paths: Vec<(SocketAddr, IpAddr)> = source_ips
+            .iter()
+            .flat_map(|source_ip| targets.iter().filter_map(move |target| Some((*target, PingJobPicker::pair_source_ip(source_ip, target)?))))
+            .collect();
+        let path_count = paths.len() as u32;
+        let port_count = port_ranges.calculate_total_port_count() as usize;
+        let skip_job_count = skip_ping_count_per_target.saturating_mul(path_count);
+
+        let port_picker = PingPortPicker::new(
+            ping_count_per_target.map(|ping_count| ping_count.saturating_mul(path_count)),
+            port_ranges,
+            skip_job_count,
+            port_picking_strategy,
+            port_picking_seed,
+        );
+
+        return PingJobPicker {
+            paths,
+            port_picker,
+            port_count,
+            next_job_index: skip_job_count as usize,
+            reprobe_count: 0,
+            reprobe_interval: Duration::ZERO,
+            reprobe_jobs: VecDeque::new(),
+            rate_limiter: None,
+            rate_limited_until: None,
+            deadline: None,
+            is_port_cross_product_enabled: false,
+            flow_label: None,
+        };
+    }
+
+    /// Wait for the cooldown before picking the same source port again, which is disabled by default.
+    pub fn enable_port_cooldown(&mut self, port_cooldown: Duration) {
+        self.port_picker.enable_port_cooldown(port_cooldown);
+    }
+
+    /// Limit the rate of all jobs, including the re-probes, which is shared by all workers that use this picker.
+    pub fn enable_rate_limit(&mut self, rate_limiter: PingRateLimiter) {
+        self.rate_limiter = Some(rate_limiter);
+    }
+
+    /// Ping every target from every source port, instead of only spreading the targets over the source ports, which is
+    /// disabled by default. Each source port moves on to the next target in every cycle of the source ports, so after as
+    /// many cycles as the targets (times the source IPs), every pair is pinged at least once, whatever order the ports
+    /// are picked in, except for the random strategy.
+    pub fn enable_port_cross_product(&mut self) {
+        self.is_port_cross_product_enabled = true;
+    }
+
+    /// Set the flow label of the pings to IPv6 targets, which is disabled by default. When rotating, the flow label moves
+    /// on in every ping, so it is scanned along with the source ports.
+    pub fn enable_flow_label(&mut self, flow_label: PingFlowLabel) {
+        self.flow_label = Some(flow_label);
+    }
+
+    /// Stop picking any job, including the re-probes, once the deadline is reached, even if the ping count is not.
+    pub fn enable_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Re-probe each failed ping for the specified times, which is disabled by default.
+    pub fn enable_reprobes(&mut self, reprobe_count: u32, reprobe_interval: Duration) {
+        self.reprobe_count = reprobe_count;
+        self.reprobe_interval = reprobe_interval;
+
+        // Re-probes reuse the failed source ports out of the picking order, so the ports in use need to be tracked to avoid
+        // sending 2 pings from the same port at the same time.
+        if reprobe_count > 0 {
+            self.port_picker.enable_port_in_use_tracking();
+        }
+    }
+
+    /// The time to pick the next job again, when no job is returned before all pings are done, because the rate limit,
+    /// the re-probes or the next source port are not ready yet. Returns None, when there is nothing to wait for.
+    pub fn next_job_available_time(&self) -> Option<Instant> {
+        let next_reprobe_time = self.reprobe_jobs.iter().map(|(due_time, job)| self.get_reprobe_available_time(due_time, job)).min();
+        let next_port_available_time = self.port_picker.next_port_available_time();
+        let next_job_available_time = [self.rate_limited_until, next_reprobe_time, next_port_available_time].iter().flatten().min().copied();
+
+        // No more job will be picked after the deadline, so there is no point to wait past it.
+        if self.is_deadline_reached(Instant::now()) {
+            return None;
+        }
+
+        return match self.deadline {
+            Some(deadline) => next_job_available_time.map(|time| time.min(deadline)),
+            None => next_job_available_time,
+        };
+    }
+
+    fn is_deadline_reached(&self, now: Instant) -> bool {
+        return matches!(self.deadline, Some(deadline) if now >= deadline);
+    }
+
+    /// Re-probe the same target and source port when a normal ping failed, to tell whether the failure is persistent or
+    /// transient. Once started, all re-probes are sent regardless of their results, so the outcome can be compared.
+    pub fn on_job_completed(&mut self, job: &PingJob, is_failed: bool) {
+        self.port_picker.release_port(job.source_port);
+
+        if job.reprobe_index >= self.reprobe_count || (job.reprobe_index == 0 && !is_failed) {
+            return;
+        }
+
+        let reprobe_job = PingJob { reprobe_index: job.reprobe_index + 1, ..*job };
+        self.reprobe_jobs.push_back((Instant::now() + self.reprobe_interval, reprobe_job));
+    }
+
+    // Re-probes go through the same port cooldown and in use tracking as the normal jobs, so a re-probe waiting for its
+    // source port doesn't block the other re-probes that are ready.
+    fn fetch_next_reprobe_job(&mut self) -> Option<PingJob> {
+        let now = Instant::now();
+        for index in 0..self.reprobe_jobs.len() {
+            let (due_time, job) = self.reprobe_jobs[index];
+            if due_time > now {
+                break;
+            }
+
+            if self.port_picker.try_pick_port(job.source_port).is_ok() {
+                return self.reprobe_jobs.remove(index).map(|(_, job)| job);
+            }
+        }
+
+        return None;
+    }
+
+    fn get_reprobe_available_time(&self, due_time: &Instant, job: &PingJob) -> Instant {
+        return match self.port_picker.get_port_available_time(job.source_port) {
+            Some(port_available_time) => port_available_time.max(*due_time),
+            None => *due_time,
+        };
+    }
+
+    fn fetch_next_job(&mut self) -> Option<PingJob> {
+        let now = Instant::now();
+        if self.is_deadline_reached(now) {
+            return None;
+        }
+
+        self.rate_limited_until = self.rate_limiter.as_ref().and_then(|rate_limiter| rate_limiter.next_ping_time(now));
+        if self.rate_limited_until.is_some() {
+            return None;
+        }
+
+        let job = self.fetch_next_job_without_rate_limit()?;
+        if let Some(rate_limiter) = &mut self.rate_limiter {
+            rate_limiter.acquire(now);
+        }
+
+        return Some(job);
+    }
+
+    fn fetch_next_job_without_rate_limit(&mut self) -> Option<PingJob> {
+        if let Some(reprobe_job) = self.fetch_next_reprobe_job() {
+            return Some(reprobe_job);
+        }
+
+        let source_port = self.port_picker.next()?;
+
+        // When the port count is a multiple of the target count, rotating targets alone will make each target always land
+        // on the same subset of source ports. To avoid it, we shift the target by 1 whenever all ports are used once.
+        // Source IPs are rotated after all targets, so every target is pinged from every source IP in the same IP family.
+        let port_cycle = self.next_job_index.checked_div(self.port_count).unwrap_or(0);
+        let path_index = if self.is_port_cross_product_enabled {
+            (self.port_picker.get_port_index(source_port) as usize + port_cycle) % self.paths.len()
+        } else {
+            (self.next_job_index + port_cycle) % self.paths.len()
+        };
+        let (target, source_ip) = self.paths[path_index];
+        let flow_label = self.get_flow_label(&target);
+        self.next_job_index += 1;
+
+        return Some(PingJob { target, source_ip, source_port, flow_label, reprobe_index: 0 });
+    }
+
+    // Pings cannot be sent from a source IP in the other IP family, except the unspecified one, which is changed to the
+    // unspecified IP in the same family as the target.
+    fn pair_source_ip(source_ip: &IpAddr, target: &SocketAddr) -> Option<IpAddr> {
+        return match (source_ip, target) {
+            (IpAddr::V4(_), SocketAddr::V4(_)) | (IpAddr::V6(_), SocketAddr::V6(_)) => Some(*source_ip),
+            (source_ip, SocketAddr::V4(_)) if source_ip.is_unspecified() => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            (source_ip, SocketAddr::V6(_)) if source_ip.is_unspecified() => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            _ => None,
+        };
+    }
+
+    fn get_flow_label(&self, target: &SocketAddr) -> Option<u32> {
+        if !target.is_ipv6() {
+            return None;
+        }
+
+        return match self.flow_label? {
+            PingFlowLabel::Fixed(flow_label) => Some(flow_label),
+            PingFlowLabel::Rotate => Some((self.next_job_index % PING_FLOW_LABEL_MAX as usize) as u32 + 1),
+        };
+    }
+}
+
+impl Iterator for PingJobPicker {
+    type Item = PingJob;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.fetch_next_job();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::net::Ipv4Addr;
+
+    const SOURCE_IP: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+    #[test]
+    fn ping_job_picker_should_work_with_single_target() {
+        let target: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        assert_eq!(
+            vec![
+                PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 0 },
+                PingJob { target, source_ip: SOURCE_IP, source_port: 1025, flow_label: None, reprobe_index: 0 },
+                PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 0 }
+            ],
+            PingJobPicker::new(
+                Some(3),
+                vec![target],
+                vec![SOURCE_IP],
+                PortRangeList { ranges: vec![(1024..=1025)] },
+                0,
+                PingPortPickingStrategy::Sequential,
+                0,
+            )
+            .collect::<Vec<PingJob>>()
+        );
+    }
+
+    #[test]
+    fn ping_job_picker_should_rotate_targets() {
+        let targets: Vec<SocketAddr> = vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.2:443".parse().unwrap()];
+        assert_eq!(
+            vec![
+                PingJob { target: targets[0], source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: SOURCE_IP, source_port: 1025, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[0], source_ip: SOURCE_IP, source_port: 1026, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: SOURCE_IP, source_port: 1027, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[0], source_ip: SOURCE_IP, source_port: 1025, flow_label: None, reprobe_index: 0 },
+            ],
+            PingJobPicker::new(
+                Some(3),
+                targets.clone(),
+                vec![SOURCE_IP],
+                PortRangeList { ranges: vec![(1024..=1027)] },
+                0,
+                PingPortPickingStrategy::Sequential,
+                0,
+            )
+            .collect::<Vec<PingJob>>()
+        );
+    }
+
+    #[test]
+    fn ping_job_picker_should_skip_pings_for_all_targets() {
+        let targets: Vec<SocketAddr> = vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.2:443".parse().unwrap()];
+        assert_eq!(
+            vec![
+                PingJob { target: targets[0], source_ip: SOURCE_IP, source_port: 1026, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: SOURCE_IP, source_port: 1027, flow_label: None, reprobe_index: 0 }
+            ],
+            PingJobPicker::new(
+                Some(2),
+                targets.clone(),
+                vec![SOURCE_IP],
+                PortRangeList { ranges: vec![(1024..=1031)] },
+                1,
+                PingPortPickingStrategy::Sequential,
+                0,
+            )
+            .collect::<Vec<PingJob>>()
+        );
+    }
+
+    #[test]
+    fn ping_job_picker_should_rotate_source_ips_after_targets() {
+        let targets: Vec<SocketAddr> = vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.2:443".parse().unwrap()];
+        let source_ips: Vec<IpAddr> = vec!["10.0.1.1".parse().unwrap(), "10.0.1.2".parse().unwrap()];
+        assert_eq!(
+            vec![
+                PingJob { target: targets[0], source_ip: source_ips[0], source_port: 1024, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: source_ips[0], source_port: 1025, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[0], source_ip: source_ips[1], source_port: 1026, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: source_ips[1], source_port: 1027, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: source_ips[0], source_port: 1024, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[0], source_ip: source_ips[1], source_port: 1025, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: source_ips[1], source_port: 1026, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[0], source_ip: source_ips[0], source_port: 1027, flow_label: None, reprobe_index: 0 },
+            ],
+            PingJobPicker::new(
+                Some(2),
+                targets.clone(),
+                source_ips.clone(),
+                PortRangeList { ranges: vec![(1024..=1027)] },
+                0,
+                PingPortPickingStrategy::Sequential,
+                0
+            )
+            .collect::<Vec<PingJob>>()
+        );
+    }
+
+    #[test]
+    fn ping_job_picker_should_pair_targets_with_source_ips_in_same_ip_family() {
+        let targets: Vec<SocketAddr> = vec!["10.0.0.1:443".parse().unwrap(), "[2001:db8::1]:443".parse().unwrap()];
+        let source_ips: Vec<IpAddr> = vec!["10.0.1.1".parse().unwrap(), "2001:db8:1::1".parse().unwrap(), "0.0.0.0".parse().unwrap()];
+        assert_eq!(
+            vec![
+                PingJob { target: targets[0], source_ip: source_ips[0], source_port: 1024, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: source_ips[1], source_port: 1025, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[0], source_ip: source_ips[2], source_port: 1026, flow_label: None, reprobe_index: 0 },
+                PingJob { target: targets[1], source_ip: "::".parse().unwrap(), source_port: 1027, flow_label: None, reprobe_index: 0 },
+            ],
+            PingJobPicker::new(
+                Some(1),
+                targets.clone(),
+                source_ips.clone(),
+                PortRangeList { ranges: vec![(1024..=1027)] },
+                0,
+                PingPortPickingStrategy::Sequential,
+                0
+            )
+            .collect::<Vec<PingJob>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn ping_job_picker_should_panic_when_target_has_no_source_ip_in_same_ip_family() {
+        PingJobPicker::new(
+            Some(3),
+            vec!["10.0.0.1:443".parse().unwrap(), "[2001:db8::1]:443".parse().unwrap()],
+            vec!["10.0.1.1".parse().unwrap()],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+    }
+
+    #[test]
+    fn ping_job_picker_should_set_flow_label_for_ipv6_targets_only() {
+        let targets: Vec<SocketAddr> = vec!["10.0.0.1:443".parse().unwrap(), "[2001:db8::1]:443".parse().unwrap()];
+        let create_picker = |flow_label: PingFlowLabel| {
+            let mut picker = PingJobPicker::new(
+                Some(2),
+                targets.clone(),
+                vec![SOURCE_IP],
+                PortRangeList { ranges: vec![(1024..=1027)] },
+                0,
+                PingPortPickingStrategy::Sequential,
+                0,
+            );
+            picker.enable_flow_label(flow_label);
+            picker.map(|job| job.flow_label).collect::<Vec<Option<u32>>>()
+        };
+
+        assert_eq!(vec![None, Some(100), None, Some(100)], create_picker(PingFlowLabel::Fixed(100)));
+        assert_eq!(vec![None, Some(2), None, Some(4)], create_picker(PingFlowLabel::Rotate));
+    }
+
+    #[test]
+    fn ping_job_picker_should_ping_every_target_from_every_source_port_with_cross_product() {
+        let targets: Vec<SocketAddr> = vec!["10.0.0.1:8000".parse().unwrap(), "10.0.0.1:8001".parse().unwrap(), "10.0.0.1:8002".parse().unwrap()];
+        let mut picker = PingJobPicker::new(
+            Some(2),
+            targets.clone(),
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Shuffle,
+            42,
+        );
+        picker.enable_port_cross_product();
+
+        let mut pairs: Vec<(u16, u16)> = picker.map(|job| (job.source_port, job.target.port())).collect();
+        pairs.sort();
+        assert_eq!(vec![(1024, 8000), (1024, 8001), (1024, 8002), (1025, 8000), (1025, 8001), (1025, 8002)], pairs);
+    }
+
+    #[test]
+    fn ping_job_picker_should_reprobe_failed_jobs() {
+        let target: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let mut picker = PingJobPicker::new(
+            Some(2),
+            vec![target],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+        picker.enable_reprobes(2, Duration::ZERO);
+
+        let first_job = picker.next().unwrap();
+        picker.on_job_completed(&first_job, true);
+        assert!(picker.next_job_available_time().is_some());
+
+        // Re-probes are picked before the normal jobs, and will be sent again even if they succeeded.
+        let first_reprobe_job = picker.next().unwrap();
+        assert_eq!(PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 1 }, first_reprobe_job);
+        picker.on_job_completed(&first_reprobe_job, false);
+
+        let second_reprobe_job = picker.next().unwrap();
+        assert_eq!(PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 2 }, second_reprobe_job);
+        picker.on_job_completed(&second_reprobe_job, true);
+        assert_eq!(None, picker.next_job_available_time());
+
+        // Succeeded normal jobs are never re-probed.
+        let second_job = picker.next().unwrap();
+        assert_eq!(PingJob { target, source_ip: SOURCE_IP, source_port: 1025, flow_label: None, reprobe_index: 0 }, second_job);
+        picker.on_job_completed(&second_job, false);
+        assert_eq!(None, picker.next_job_available_time());
+        assert_eq!(None, picker.next());
+    }
+
+    #[test]
+    fn ping_job_picker_should_wait_for_reprobe_interval() {
+        let target: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let mut picker = PingJobPicker::new(
+            Some(1),
+            vec![target],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+        picker.enable_reprobes(1, Duration::from_secs(60));
+
+        let job = picker.next().unwrap();
+        picker.on_job_completed(&job, true);
+        assert!(picker.next_job_available_time().is_some());
+        assert_eq!(None, picker.next());
+    }
+
+    #[test]
+    fn ping_job_picker_should_wait_for_reprobe_port_to_be_released_and_cooled_down() {
+        let target: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let mut picker = PingJobPicker::new(
+            Some(3),
+            vec![target],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+        picker.enable_reprobes(1, Duration::from_millis(20));
+
+        let first_job = picker.next().unwrap();
+        picker.on_job_completed(&first_job, true);
+        let second_job = picker.next().unwrap();
+        let third_job = picker.next().unwrap();
+        assert_eq!((1025, 1024), (second_job.source_port, third_job.source_port));
+
+        // Port 1024 is picked again by the normal job before the re-probe is due, so the re-probe waits for it to be released.
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(None, picker.next());
+        assert!(picker.next_job_available_time().is_some());
+
+        picker.on_job_completed(&third_job, false);
+        assert_eq!(Some(PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 1 }), picker.next());
+
+        // Re-probes also wait for the port cooldown.
+        let mut picker = PingJobPicker::new(
+            Some(1),
+            vec![target],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+        picker.enable_port_cooldown(Duration::from_secs(60));
+        picker.enable_reprobes(1, Duration::ZERO);
+
+        let job = picker.next().unwrap();
+        picker.on_job_completed(&job, true);
+        assert_eq!(None, picker.next());
+        assert!(picker.next_job_available_time().unwrap() > Instant::now() + Duration::from_secs(50));
+    }
+
+    #[test]
+    fn ping_job_picker_should_wait_for_rate_limit() {
+        let target: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let mut picker = PingJobPicker::new(
+            Some(2),
+            vec![target],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+        picker.enable_rate_limit(PingRateLimiter::new(0.001, 0.0, false));
+
+        assert_eq!(Some(PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 0 }), picker.next());
+        assert_eq!(None, picker.next());
+        assert!(picker.next_job_available_time().unwrap() > Instant::now() + Duration::from_secs(900));
+    }
+
+    #[test]
+    fn ping_job_picker_should_stop_at_deadline() {
+        let target: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let mut picker = PingJobPicker::new(
+            None,
+            vec![target],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+        picker.enable_rate_limit(PingRateLimiter::new(0.001, 0.0, false));
+
+        // The rate limit asks to wait far beyond the deadline, which should be capped at the deadline.
+        let deadline = Instant::now() + Duration::from_millis(50);
+        picker.enable_deadline(deadline);
+        assert_eq!(Some(PingJob { target, source_ip: SOURCE_IP, source_port: 1024, flow_label: None, reprobe_index: 0 }), picker.next());
+        assert_eq!(None, picker.next());
+        assert_eq!(Some(deadline), picker.next_job_available_time());
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(None, picker.next());
+        assert_eq!(None, picker.next_job_available_time());
+    }
+
+    #[test]
+    #[should_panic]
+    fn ping_job_picker_should_panic_when_target_list_is_empty() {
+        PingJobPicker::new(
+            Some(3),
+            vec![],
+            vec![SOURCE_IP],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn ping_job_picker_should_panic_when_source_ip_list_is_empty() {
+        PingJobPicker::new(
+            Some(3),
+            vec!["10.0.0.1:443".parse().unwrap()],
+            vec![],
+            PortRangeList { ranges: vec![(1024..=1025)] },
+            0,
+            PingPortPickingStrategy::Sequential,
+            0,
+        );
+    }
+}
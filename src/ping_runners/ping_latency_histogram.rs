@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+// Latency is recorded in microseconds into log-linear buckets (similar to HDR histogram). Each power of 2 range is split
+// into 128 sub buckets, so the error of the percentile values is always less than 1%, while the memory usage only grows
+// with the largest latency we have seen, instead of the number of pings.
+const SUB_BUCKET_BITS: u32 = 8;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+const SUB_BUCKET_HALF_COUNT: u64 = SUB_BUCKET_COUNT / 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PingLatencySummary {
+    pub sample_count: u64,
+    pub min: Duration,
+    pub max: Duration,
+    pub average: Duration,
+    pub std_dev: Duration,
+    pub jitter: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct PingLatencyHistogram {
+    bucket_counts: Vec<u64>,
+    sample_count: u64,
+    min_in_us: u64,
+    max_in_us: u64,
+
+    // Mean and variance are calculated with Welford's online algorithm to avoid losing precision in long runs.
+    mean_in_us: f64,
+    squared_distance_sum: f64,
+
+    last_sample_in_us: Option<u64>,
+    jitter_sum_in_us: u128,
+}
+
+impl PingLatencyHistogram {
+    pub fn new() -> PingLatencyHistogram {
+        return PingLatencyHistogram {
+            bucket_counts: Vec::new(),
+            sample_count: 0,
+            min_in_us: u64::MAX,
+            max_in_us: 0,
+            mean_in_us: 0.0,
+            squared_distance_sum: 0.0,
+            last_sample_in_us: None,
+            jitter_sum_in_us: 0,
+        };
+    }
+
+    pub fn record(&mut self, latency: &Duration) {
+        let latency_in_us = latency.as_micros().min(u64::MAX as u128) as u64;
+
+        let bucket_index = PingLatencyHistogram::get_bucket_index(latency_in_us);
+        if bucket_index >= self.bucket_counts.len() {
+            self.bucket_counts.resize(bucket_index + 1, 0);
+        }
+        self.bucket_counts[bucket_index] += 1;
+
+        self.sample_count += 1;
+        self.min_in_us = std::cmp::min(self.min_in_us, latency_in_us);
+        self.max_in_us = std::cmp::max(self.max_in_us, latency_in_us);
+
+        let distance_to_old_mean = latency_in_us as f64 - self.mean_in_us;
+        self.mean_in_us += distance_to_old_mean / self.sample_count as f64;
+        self.squared_distance_sum += distance_to_old_mean * (latency_in_us as f64 - self.mean_in_us);
+
+        // Jitter is the mean absolute difference between consecutive samples.
+        if let Some(last_sample_in_us) = self.last_sample_in_us {
+            self.jitter_sum_in_us += (latency_in_us as i128 - last_sample_in_us as i128).unsigned_abs();
+        }
+        self.last_sample_in_us = Some(latency_in_us);
+    }
+
+    pub fn sample_count(&self) -> u64 {
+        self.sample_count
+    }
+    pub fn min(&self) -> Duration {
+        if self.sample_count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_micros(self.min_in_us)
+    }
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_in_us)
+    }
+    pub fn average(&self) -> Duration {
+        PingLatencyHistogram::duration_from_micros_f64(self.mean_in_us)
+    }
+
+    pub fn std_dev(&self) -> Duration {
+        if self.sample_count == 0 {
+            return Duration::ZERO;
+        }
+
+        return PingLatencyHistogram::duration_from_micros_f64((self.squared_distance_sum / self.sample_count as f64).sqrt());
+    }
+
+    pub fn jitter(&self) -> Duration {
+        if self.sample_count < 2 {
+            return Duration::ZERO;
+        }
+
+        return PingLatencyHistogram::duration_from_micros_f64(self.jitter_sum_in_us as f64 / (self.sample_count - 1) as f64);
+    }
+
+    /// Get the latency at the given percentile, e.g. 99.9 for P99.9. The value returned is the highest latency in the
+    /// bucket which the percentile falls into, capped by the max latency we have seen.
+    pub fn value_at_percentile(&self, percentile: f64) -> Duration {
+        if self.sample_count == 0 {
+            return Duration::ZERO;
+        }
+
+        let percentile = percentile.clamp(0.0, 100.0);
+        let target_count = std::cmp::max(1, ((percentile / 100.0) * self.sample_count as f64).ceil() as u64);
+
+        let mut accumulated_count = 0;
+        for (bucket_index, bucket_count) in self.bucket_counts.iter().enumerate() {
+            accumulated_count += bucket_count;
+            if accumulated_count >= target_count {
+                let value_in_us = std::cmp::min(PingLatencyHistogram::get_bucket_highest_value(bucket_index), self.max_in_us);
+                return Duration::from_micros(value_in_us);
+            }
+        }
+
+        return self.max();
+    }
+
+    pub fn summary(&self) -> PingLatencySummary {
+        return PingLatencySummary {
+            sample_count: self.sample_count,
+            min: self.min(),
+            max: self.max(),
+            average: self.average(),
+            std_dev: self.std_dev(),
+            jitter: self.jitter(),
+            p50: self.value_at_percentile(50.0),
+            p90: self.value_at_percentile(90.0),
+            p95: self.value_at_percentile(95.0),
+            p99: self.value_at_percentile(99.0),
+            p999: self.value_at_percentile(99.9),
+        };
+    }
+
+    fn duration_from_micros_f64(value_in_us: f64) -> Duration {
+        return Duration::from_nanos((value_in_us * 1000.0).round() as u64);
+    }
+
+    fn get_bucket_index(value_in_us: u64) -> usize {
+        if value_in_us < SUB_BUCKET_COUNT {
+            return value_in_us as usize;
+        }
+
+        // For larger values, we keep the highest 8 bits, which lands in [128, 256) after the shift.
+        let shift = (63 - value_in_us.leading_zeros()) - (SUB_BUCKET_BITS - 1);
+        let sub_bucket_index = value_in_us >> shift;
+        return (SUB_BUCKET_COUNT + (shift as u64 - 1) * SUB_BUCKET_HALF_COUNT + (sub_bucket_index - SUB_BUCKET_HALF_COUNT)) as usize;
+    }
+
+    fn get_bucket_highest_value(bucket_index: usize) -> u64 {
+        let bucket_index = bucket_index as u64;
+        if bucket_index < SUB_BUCKET_COUNT {
+            return bucket_index;
+        }
+
+        let shift = (bucket_index - SUB_BUCKET_COUNT) / SUB_BUCKET_HALF_COUNT + 1;
+        let sub_bucket_index = (bucket_index - SUB_BUCKET_COUNT) % SUB_BUCKET_HALF_COUNT + SUB_BUCKET_HALF_COUNT;
+        let highest_value = ((sub_bucket_index as u128 + 1) << shift) - 1;
+        return std::cmp::min(highest_value, u64::MAX as u128) as u64;
+    }
+}
+
+impl Default for PingLatencyHistogram {
+    fn default() -> Self {
+        PingLatencyHistogram::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bucket_index_should_work() {
+        for value in [0, 1, 255, 256, 257, 511, 512, 515, 516, 1000, 12345, 2_000_000, u64::MAX / 2, u64::MAX] {
+            let bucket_index = PingLatencyHistogram::get_bucket_index(value);
+            assert!(value <= PingLatencyHistogram::get_bucket_highest_value(bucket_index), "value = {}", value);
+            if bucket_index > 0 {
+                assert!(value > PingLatencyHistogram::get_bucket_highest_value(bucket_index - 1), "value = {}", value);
+            }
+        }
+
+        assert_eq!(255, PingLatencyHistogram::get_bucket_index(255));
+        assert_eq!(256, PingLatencyHistogram::get_bucket_index(257));
+        assert_eq!(383, PingLatencyHistogram::get_bucket_index(511));
+        assert_eq!(384, PingLatencyHistogram::get_bucket_index(515));
+    }
+
+    #[test]
+    fn empty_histogram_should_return_zero() {
+        let histogram = PingLatencyHistogram::new();
+        let summary = histogram.summary();
+        assert_eq!(0, summary.sample_count);
+        assert_eq!(Duration::ZERO, summary.min);
+        assert_eq!(Duration::ZERO, summary.max);
+        assert_eq!(Duration::ZERO, summary.std_dev);
+        assert_eq!(Duration::ZERO, summary.jitter);
+        assert_eq!(Duration::ZERO, summary.p99);
+    }
+
+    #[test]
+    fn histogram_statistics_should_work() {
+        let mut histogram = PingLatencyHistogram::new();
+        for latency_in_ms in [10, 20, 10, 20] {
+            histogram.record(&Duration::from_millis(latency_in_ms));
+        }
+
+        let summary = histogram.summary();
+        assert_eq!(4, summary.sample_count);
+        assert_eq!(Duration::from_millis(10), summary.min);
+        assert_eq!(Duration::from_millis(20), summary.max);
+        assert_eq!(Duration::from_millis(15), summary.average);
+        assert_eq!(Duration::from_millis(5), summary.std_dev);
+        assert_eq!(Duration::from_millis(10), summary.jitter);
+    }
+
+    #[test]
+    fn histogram_percentiles_should_work() {
+        let mut histogram = PingLatencyHistogram::new();
+        for latency_in_us in 1..=10000 {
+            histogram.record(&Duration::from_micros(latency_in_us));
+        }
+
+        // Percentiles are bucketed, so we only check they are within the precision we promised.
+        for (percentile, expected_in_us) in [(50.0, 5000.0), (90.0, 9000.0), (95.0, 9500.0), (99.0, 9900.0), (99.9, 9990.0)] {
+            let actual_in_us = histogram.value_at_percentile(percentile).as_micros() as f64;
+            assert!((actual_in_us - expected_in_us).abs() / expected_in_us < 0.01, "percentile = {}, actual = {}", percentile, actual_in_us);
+        }
+
+        assert_eq!(Duration::from_micros(1), histogram.value_at_percentile(0.0));
+        assert_eq!(Duration::from_micros(10000), histogram.value_at_percentile(100.0));
+    }
+}
@@ -1,25 +1,66 @@
-use crate::PortRangeList;
+use crate::{PingPortPickingStrategy, PortRangeList};
 use contracts::requires;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// The picker is not told when a port will be released, so the pings waiting for a port in use check it again after this.
+const PORT_IN_USE_RETRY_INTERVAL: Duration = Duration::from_millis(10);
 
 pub struct PingPortPicker {
     remaining_ping_count: Option<u32>,
 
     port_ranges: PortRangeList,
-    next_port: u16,
-    next_port_range_index: usize,
+    port_count: u32,
+    strategy: PingPortPickingStrategy,
+    picked_port_count: u64,
+    shuffled_port_indexes: Vec<u32>,
+    rng: StdRng,
+
+    port_cooldown: Duration,
+    port_last_used_times: HashMap<u16, Instant>,
+    cooling_down_port: Option<u16>,
+
+    is_port_in_use_tracked: bool,
+    ports_in_use: HashSet<u16>,
 }
 
 impl PingPortPicker {
+    /// The random strategies are driven by the seed, so pickers created with the same seed pick the same ports. This is
+    /// how the skipped ports, e.g. the ones used in warmup, stay the same as the ones picked by another picker before.
+    ///
     #[allow(unreachable_code)]
     #[requires(port_ranges.ranges.len() > 0)]
     #[requires(port_ranges.ranges.iter().filter(|r| r.start() == &0 || r.end() == &0 || r.start() > r.end()).count() == 0)]
-    pub fn new(ping_count: Option<u32>, mut port_ranges: PortRangeList, skip_port_count: u32) -> PingPortPicker {
+    pub fn new(
+        ping_count: Option<u32>,
+        mut port_ranges: PortRangeList,
+        skip_port_count: u32,
+        strategy: PingPortPickingStrategy,
+        seed: u64,
+    ) -> PingPortPicker {
         port_ranges.ranges.sort_by(|a, b| a.start().cmp(b.start()));
 
-        let next_port = *port_ranges.ranges[0].start();
+        let port_count = port_ranges.ranges.iter().map(|r| (*r.end() - *r.start()) as u32 + 1).sum();
 
-        let mut port_picker = PingPortPicker { remaining_ping_count: ping_count, port_ranges, next_port, next_port_range_index: 0 };
+        let mut port_picker = PingPortPicker {
+            remaining_ping_count: ping_count,
+            port_ranges,
+            port_count,
+            strategy,
+            picked_port_count: 0,
+            shuffled_port_indexes: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+            port_cooldown: Duration::ZERO,
+            port_last_used_times: HashMap::new(),
+            cooling_down_port: None,
+            is_port_in_use_tracked: false,
+            ports_in_use: HashSet::new(),
+        };
 
+        // The skipped ports are not used by this picker, so they are skipped without cooldown.
         for _ in 0..skip_port_count {
             port_picker.next();
         }
@@ -27,33 +68,175 @@ impl PingPortPicker {
         return port_picker;
     }
 
+    /// With port cooldown set, a port is not picked again until the cooldown passed since it was picked last time. When
+    /// the next port is still cooling down, `next` returns `None` before the ping count is used up, and
+    /// `next_port_available_time` tells when to try again.
+    pub fn enable_port_cooldown(&mut self, port_cooldown: Duration) {
+        self.port_cooldown = port_cooldown;
+    }
+
+    /// With port in use tracking enabled, a port is not picked again until it is released by `release_port`, which is
+    /// disabled by default. This is needed when the ports are also used out of the picking order, e.g. by re-probes.
+    pub fn enable_port_in_use_tracking(&mut self) {
+        self.is_port_in_use_tracked = true;
+    }
+
+    /// The time when the port kept for cooling down can be picked, or None if no port is waiting for cooldown.
+    pub fn next_port_available_time(&self) -> Option<Instant> {
+        let port = self.cooling_down_port?;
+        return Some(self.get_port_available_time(port).unwrap_or_else(Instant::now));
+    }
+
+    /// Pick the specified port out of the picking order, which follows the same cooldown and in use tracking as the other
+    /// ports. Returns the time to try again, when the port is not available yet.
+    pub fn try_pick_port(&mut self, port: u16) -> Result<(), Instant> {
+        if let Some(port_available_time) = self.get_port_available_time(port) {
+            return Err(port_available_time);
+        }
+
+        self.mark_port_used(port);
+        return Ok(());
+    }
+
+    /// Release the port after the ping is done, so it can be picked again.
+    pub fn release_port(&mut self, port: u16) {
+        self.ports_in_use.remove(&port);
+    }
+
+    /// The time when the port can be picked again, or None if it is available now.
+    pub fn get_port_available_time(&self, port: u16) -> Option<Instant> {
+        let now = Instant::now();
+        if self.ports_in_use.contains(&port) {
+            return Some(now + PORT_IN_USE_RETRY_INTERVAL);
+        }
+
+        let port_available_time = *self.port_last_used_times.get(&port)? + self.port_cooldown;
+        if port_available_time <= now {
+            return None;
+        }
+
+        return Some(port_available_time);
+    }
+
+    fn mark_port_used(&mut self, port: u16) {
+        if self.is_port_in_use_tracked {
+            self.ports_in_use.insert(port);
+        }
+
+        if self.port_cooldown > Duration::ZERO {
+            self.port_last_used_times.insert(port, Instant::now());
+        }
+    }
+
     fn fetch_next_available_port(&mut self) -> Option<u16> {
         match self.remaining_ping_count {
             Some(remaining_ping_count) if remaining_ping_count == 0 => return None,
-            Some(remaining_ping_count) => self.remaining_ping_count = Some(remaining_ping_count - 1),
-            None => (),
+            _ => (),
+        }
+
+        let port = match self.cooling_down_port.take() {
+            Some(port) => port,
+            None => self.fetch_next_port(),
+        };
+
+        if let Some(port_available_time) = self.get_port_available_time(port) {
+            tracing::debug!(
+                "Source port is still cooling down or in use, waiting for it; port={}, wait_time={:?}",
+                port,
+                port_available_time.saturating_duration_since(Instant::now())
+            );
+            self.cooling_down_port = Some(port);
+            return None;
+        }
+
+        if let Some(remaining_ping_count) = self.remaining_ping_count {
+            self.remaining_ping_count = Some(remaining_ping_count - 1);
         }
 
-        return Some(self.fetch_next_available_port_from_port_ranges());
+        self.mark_port_used(port);
+        return Some(port);
     }
 
-    fn fetch_next_available_port_from_port_ranges(&mut self) -> u16 {
-        let port = self.next_port;
-        self.next_port = if self.next_port >= *(self.port_ranges.ranges[self.next_port_range_index].end()) {
-            self.next_port_range_index += 1;
-            if self.next_port_range_index >= self.port_ranges.ranges.len() {
-                self.next_port_range_index = 0;
+    fn fetch_next_port(&mut self) -> u16 {
+        let port_index = self.fetch_next_port_index();
+        let port = self.get_port_by_index(port_index);
+
+        // Random picking can land on any port, so we skip the cooling down ones by moving on to the next available port,
+        // instead of waiting. The other strategies walk all ports in cycles, in which the next port is already the least
+        // recently used one.
+        if self.strategy == PingPortPickingStrategy::Random && self.get_port_available_time(port).is_some() {
+            for offset in 1..self.port_count {
+                let available_port = self.get_port_by_index((port_index + offset) % self.port_count);
+                if self.get_port_available_time(available_port).is_none() {
+                    return available_port;
+                }
             }
+        }
+
+        return port;
+    }
+
+    fn fetch_next_port_index(&mut self) -> u32 {
+        let index_in_cycle = (self.picked_port_count % self.port_count as u64) as u32;
+        self.picked_port_count += 1;
 
-            *self.port_ranges.ranges[self.next_port_range_index].start()
-        } else {
-            self.next_port + 1
+        return match self.strategy {
+            PingPortPickingStrategy::Sequential => index_in_cycle,
+            PingPortPickingStrategy::Random => self.rng.gen_range(0..self.port_count),
+            PingPortPickingStrategy::Shuffle => {
+                if index_in_cycle == 0 {
+                    self.shuffled_port_indexes = (0..self.port_count).collect();
+                    self.shuffled_port_indexes.shuffle(&mut self.rng);
+                }
+
+                self.shuffled_port_indexes[index_in_cycle as usize]
+            }
+            PingPortPickingStrategy::Stride(step) => {
+                // With a step sharing the factor d with the port count, every port_count / d steps loop back to the same
+                // port, so we shift the walk by 1 port for each loop to cover the rest of the ports.
+                let loop_length = self.port_count / gcd(step % self.port_count, self.port_count);
+                let position = index_in_cycle as u64 * step as u64 + (index_in_cycle / loop_length) as u64;
+                (position % self.port_count as u64) as u32
+            }
         };
+    }
 
-        return port;
+    fn get_port_by_index(&self, mut port_index: u32) -> u16 {
+        for range in &self.port_ranges.ranges {
+            let range_port_count = (*range.end() - *range.start()) as u32 + 1;
+            if port_index < range_port_count {
+                return *range.start() + port_index as u16;
+            }
+
+            port_index -= range_port_count;
+        }
+
+        unreachable!();
+    }
+
+    /// The position of the port in all port ranges, which stays the same no matter which strategy picks it.
+    pub fn get_port_index(&self, port: u16) -> u32 {
+        let mut port_index = 0;
+        for range in &self.port_ranges.ranges {
+            if range.contains(&port) {
+                return port_index + (port - *range.start()) as u32;
+            }
+
+            port_index += (*range.end() - *range.start()) as u32 + 1;
+        }
+
+        unreachable!();
     }
 }
 
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        return a;
+    }
+
+    return gcd(b, a % b);
+}
+
 impl Iterator for PingPortPicker {
     type Item = u16;
 
@@ -68,51 +251,163 @@ mod tests {
 
     #[test]
     fn ping_port_picker_should_work_with_port_range_1() {
-        assert_eq!(vec![1024, 1024, 1024], PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1024..=1024)] }, 0).collect::<Vec<u16>>());
+        assert_eq!(
+            vec![1024, 1024, 1024],
+            PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1024..=1024)] }, 0, PingPortPickingStrategy::Sequential, 0)
+                .collect::<Vec<u16>>()
+        );
     }
 
     #[test]
     fn ping_port_picker_should_work_with_limited_ping_count() {
-        assert_eq!(vec![1024, 1025], PingPortPicker::new(Some(2), PortRangeList { ranges: vec![(1024..=1027)] }, 0).collect::<Vec<u16>>());
+        assert_eq!(
+            vec![1024, 1025],
+            PingPortPicker::new(Some(2), PortRangeList { ranges: vec![(1024..=1027)] }, 0, PingPortPickingStrategy::Sequential, 0)
+                .collect::<Vec<u16>>()
+        );
     }
 
     #[test]
     fn ping_port_picker_should_work_with_ping_count_larger_than_range() {
         assert_eq!(
             vec![1024, 1025, 1026, 1027, 1024, 1025],
-            PingPortPicker::new(Some(6), PortRangeList { ranges: vec![(1024..=1027)] }, 0).collect::<Vec<u16>>()
+            PingPortPicker::new(Some(6), PortRangeList { ranges: vec![(1024..=1027)] }, 0, PingPortPickingStrategy::Sequential, 0)
+                .collect::<Vec<u16>>()
         );
     }
 
     #[test]
     #[should_panic]
     fn ping_port_picker_should_panic_on_zero_min_port() {
-        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(0..=1024)] }, 0);
+        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(0..=1024)] }, 0, PingPortPickingStrategy::Sequential, 0);
     }
 
     #[test]
     #[should_panic]
     fn ping_port_picker_should_panic_on_zero_max_port() {
-        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1024..=0)] }, 0);
+        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1024..=0)] }, 0, PingPortPickingStrategy::Sequential, 0);
     }
 
     #[test]
     #[should_panic]
     fn ping_port_picker_should_panic_when_min_port_is_larger_than_max_port() {
-        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1028..=1024)] }, 0);
+        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1028..=1024)] }, 0, PingPortPickingStrategy::Sequential, 0);
     }
 
     #[test]
     fn ping_port_picker_should_work_with_port_list() {
         assert_eq!(
             vec![1024, 1025, 1026, 1024, 1025],
-            PingPortPicker::new(Some(5), PortRangeList { ranges: vec![(1024..=1024), (1025..=1025), (1026..=1026)] }, 0).collect::<Vec<u16>>()
+            PingPortPicker::new(
+                Some(5),
+                PortRangeList { ranges: vec![(1024..=1024), (1025..=1025), (1026..=1026)] },
+                0,
+                PingPortPickingStrategy::Sequential,
+                0
+            )
+            .collect::<Vec<u16>>()
         );
     }
 
     #[test]
     #[should_panic]
     fn ping_port_picker_should_panic_when_port_list_is_empty() {
-        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![] }, 0);
+        PingPortPicker::new(Some(3), PortRangeList { ranges: vec![] }, 0, PingPortPickingStrategy::Sequential, 0);
+    }
+
+    #[test]
+    fn ping_port_picker_should_wait_for_port_cooldown() {
+        let mut port_picker = PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1024..=1025)] }, 0, PingPortPickingStrategy::Sequential, 0);
+        port_picker.enable_port_cooldown(Duration::from_secs(60));
+        assert_eq!(Some(1024), port_picker.next());
+        assert_eq!(Some(1025), port_picker.next());
+        assert_eq!(None, port_picker.next_port_available_time());
+
+        // Port 1024 is still cooling down, so it is kept as the next port without using up the ping count.
+        assert_eq!(None, port_picker.next());
+        assert!(port_picker.next_port_available_time().unwrap() > Instant::now() + Duration::from_secs(50));
+        assert_eq!(Some(1), port_picker.remaining_ping_count);
+
+        port_picker.port_last_used_times.clear();
+        assert_eq!(Some(1024), port_picker.next());
+        assert_eq!(None, port_picker.next_port_available_time());
+    }
+
+    #[test]
+    fn ping_port_picker_should_wait_for_ports_in_use() {
+        let mut port_picker = PingPortPicker::new(Some(3), PortRangeList { ranges: vec![(1024..=1025)] }, 0, PingPortPickingStrategy::Sequential, 0);
+        port_picker.enable_port_in_use_tracking();
+        assert_eq!(Some(1024), port_picker.next());
+        assert_eq!(Err(()), port_picker.try_pick_port(1024).map_err(|_| ()));
+        assert_eq!(Ok(()), port_picker.try_pick_port(1025));
+
+        // Both ports are in use, so port 1025 is kept as the next port until it is released.
+        assert_eq!(None, port_picker.next());
+        assert!(port_picker.next_port_available_time().is_some());
+        assert_eq!(Some(2), port_picker.remaining_ping_count);
+
+        port_picker.release_port(1025);
+        assert_eq!(Some(1025), port_picker.next());
+        assert_eq!(None, port_picker.next_port_available_time());
+    }
+
+    #[test]
+    fn ping_port_picker_should_skip_cooling_down_ports_when_picking_randomly() {
+        let port_ranges = PortRangeList { ranges: vec![(1024..=1031)] };
+        let mut port_picker = PingPortPicker::new(Some(8), port_ranges, 0, PingPortPickingStrategy::Random, 1);
+        port_picker.enable_port_cooldown(Duration::from_secs(60));
+        let ports = port_picker.collect::<Vec<u16>>();
+
+        let mut sorted_ports = ports.clone();
+        sorted_ports.sort();
+        assert_eq!((1024..=1031).collect::<Vec<u16>>(), sorted_ports);
+    }
+
+    #[test]
+    fn ping_port_picker_should_map_port_to_index() {
+        let port_picker =
+            PingPortPicker::new(None, PortRangeList { ranges: vec![(2000..=2001), (1024..=1026)] }, 0, PingPortPickingStrategy::Sequential, 0);
+        assert_eq!(vec![0, 1, 2, 3, 4], [1024, 1025, 1026, 2000, 2001].iter().map(|port| port_picker.get_port_index(*port)).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn ping_port_picker_should_work_with_stride() {
+        assert_eq!(
+            vec![1024, 1027, 1025, 1028, 1026, 1024],
+            PingPortPicker::new(Some(6), PortRangeList { ranges: vec![(1024..=1028)] }, 0, PingPortPickingStrategy::Stride(3), 0)
+                .collect::<Vec<u16>>()
+        );
+
+        // Step 2 shares a factor with 6 ports, so the walk is shifted after every 3 ports to cover all ports.
+        assert_eq!(
+            vec![1024, 1026, 1028, 1025, 1027, 1029, 1024],
+            PingPortPicker::new(Some(7), PortRangeList { ranges: vec![(1024..=1029)] }, 0, PingPortPickingStrategy::Stride(2), 0)
+                .collect::<Vec<u16>>()
+        );
+    }
+
+    #[test]
+    fn ping_port_picker_should_use_all_ports_once_per_cycle_when_shuffled() {
+        let port_ranges = PortRangeList { ranges: vec![(1024..=1031), (2000..=2001)] };
+        let ports = PingPortPicker::new(Some(20), port_ranges.clone(), 0, PingPortPickingStrategy::Shuffle, 1).collect::<Vec<u16>>();
+
+        let expected_ports: Vec<u16> = (1024..=1031).chain(2000..=2001).collect();
+        for cycle in ports.chunks(10) {
+            let mut sorted_cycle = cycle.to_vec();
+            sorted_cycle.sort();
+            assert_eq!(expected_ports, sorted_cycle);
+        }
+        assert_ne!(ports[0..10], ports[10..20]);
+    }
+
+    #[test]
+    fn ping_port_picker_should_skip_the_same_ports_with_the_same_seed() {
+        let port_ranges = PortRangeList { ranges: vec![(1024..=2047)] };
+        for strategy in [PingPortPickingStrategy::Random, PingPortPickingStrategy::Shuffle] {
+            let all_ports = PingPortPicker::new(Some(10), port_ranges.clone(), 0, strategy, 42).collect::<Vec<u16>>();
+            let skipped_ports = PingPortPicker::new(Some(10), port_ranges.clone(), 3, strategy, 42).collect::<Vec<u16>>();
+            assert_eq!(all_ports[3..], skipped_ports[..], "strategy = {}", strategy);
+            assert!(all_ports.iter().all(|port| port_ranges.ranges[0].contains(port)));
+        }
     }
 }
@@ -0,0 +1,105 @@
+use crate::RNP_MIN_PING_RATE;
+use contracts::requires;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+/// Token bucket that limits the pings sent by all workers to a constant rate. The bucket holds at most 1 token, so when
+/// the workers fall behind, the pings are not sent in bursts to catch up.
+///
+/// The interval between pings can be randomized, either with a jitter in both directions or following the exponential
+/// distribution (pings arrive as a Poisson process), which avoids the pings being synchronized with other periodic
+/// traffic and also avoids sending bursts to the rate limiters on the path, such as SNAT.
+pub struct PingRateLimiter {
+    ping_interval_in_secs: f64,
+    jitter: f64,
+    is_poisson: bool,
+    next_ping_time: Option<Instant>,
+    rng: StdRng,
+}
+
+impl PingRateLimiter {
+    #[allow(unreachable_code)]
+    #[requires(ping_rate >= RNP_MIN_PING_RATE)]
+    #[requires((0.0..=1.0).contains(&jitter))]
+    pub fn new(ping_rate: f64, jitter: f64, is_poisson: bool) -> PingRateLimiter {
+        return PingRateLimiter { ping_interval_in_secs: 1.0 / ping_rate, jitter, is_poisson, next_ping_time: None, rng: StdRng::from_entropy() };
+    }
+
+    /// The time when the next ping can be sent, or None if it can be sent now.
+    pub fn next_ping_time(&self, now: Instant) -> Option<Instant> {
+        return self.next_ping_time.filter(|next_ping_time| *next_ping_time > now);
+    }
+
+    /// Take the token for sending a ping now, which should only be called when `next_ping_time` returns None.
+    pub fn acquire(&mut self, now: Instant) {
+        let ping_interval = self.generate_ping_interval();
+
+        // Small delays in picking up the token are caught up to keep the rate, but at most 1 token is saved, so the
+        // pings are not sent in bursts after the workers are blocked for a while.
+        let token_time = match (self.next_ping_time, now.checked_sub(ping_interval)) {
+            (Some(next_ping_time), Some(earliest_token_time)) => next_ping_time.max(earliest_token_time),
+            _ => now,
+        };
+
+        self.next_ping_time = Some(token_time + ping_interval);
+    }
+
+    fn generate_ping_interval(&mut self) -> Duration {
+        let ping_interval_in_secs = if self.is_poisson {
+            // Inverse transform sampling of exponential distribution. 1 - x is used to avoid ln(0).
+            -(1.0 - self.rng.gen::<f64>()).ln() * self.ping_interval_in_secs
+        } else if self.jitter > 0.0 {
+            self.ping_interval_in_secs * (1.0 + self.rng.gen_range(-self.jitter..=self.jitter))
+        } else {
+            self.ping_interval_in_secs
+        };
+
+        return Duration::from_secs_f64(ping_interval_in_secs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ping_rate_limiter_should_limit_pings_at_constant_rate() {
+        let start_time = Instant::now();
+        let mut rate_limiter = PingRateLimiter::new(10.0, 0.0, false);
+        assert_eq!(None, rate_limiter.next_ping_time(start_time));
+
+        rate_limiter.acquire(start_time);
+        assert_eq!(Some(start_time + Duration::from_millis(100)), rate_limiter.next_ping_time(start_time));
+
+        // Picking up the token late doesn't delay the pings after it.
+        rate_limiter.acquire(start_time + Duration::from_millis(120));
+        assert_eq!(Some(start_time + Duration::from_millis(200)), rate_limiter.next_ping_time(start_time + Duration::from_millis(120)));
+
+        // But only 1 token is saved when the pings are blocked for a long time.
+        let blocked_time = start_time + Duration::from_secs(10);
+        assert_eq!(None, rate_limiter.next_ping_time(blocked_time));
+        rate_limiter.acquire(blocked_time);
+        assert_eq!(None, rate_limiter.next_ping_time(blocked_time));
+        rate_limiter.acquire(blocked_time);
+        assert_eq!(Some(blocked_time + Duration::from_millis(100)), rate_limiter.next_ping_time(blocked_time));
+    }
+
+    #[test]
+    fn ping_rate_limiter_should_randomize_ping_intervals() {
+        let ping_count = 10000;
+        for (jitter, is_poisson) in [(0.5, false), (0.0, true)] {
+            let mut rate_limiter = PingRateLimiter::new(1000.0, jitter, is_poisson);
+            let ping_intervals: Vec<Duration> = (0..ping_count).map(|_| rate_limiter.generate_ping_interval()).collect();
+
+            let average_ping_interval = ping_intervals.iter().sum::<Duration>() / ping_count;
+            assert!(average_ping_interval > Duration::from_micros(950) && average_ping_interval < Duration::from_micros(1050));
+            assert!(ping_intervals.iter().any(|ping_interval| *ping_interval != ping_intervals[0]));
+
+            if !is_poisson {
+                assert!(ping_intervals.iter().all(|x| *x >= Duration::from_micros(500) && *x <= Duration::from_micros(1500)));
+            }
+        }
+    }
+}
@@ -1,10 +1,20 @@
 use crate::ping_clients::ping_client::PingClientError;
 use crate::ping_clients::ping_client::PingClientWarning;
-use crate::PingResultDto;
+use crate::ping_clients::ping_client::{
+    PingResultExtraDetails, PING_RESULT_EXTRA_DETAIL_FLOW_LABEL, PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS,
+    PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE,
+};
+use crate::{PingErrorCategory, PingResultDto};
 use chrono::{offset::Utc, DateTime};
 use contracts::requires;
 use std::{net::SocketAddr, time::Duration};
 
+// OS retransmits SYN after 1s and doubles the wait each time, so when the first SYN is lost, the connect RTT lands right
+// after 1s, 3s, 7s and so on.
+const SYN_RETRANSMIT_TIMES_IN_MS: [u128; 3] = [1000, 3000, 7000];
+const SYN_RETRANSMIT_RTT_TOLERANCE_IN_MS: u128 = 200;
+const TCP_BASED_PROTOCOLS: [&str; 3] = ["TCP", "TLS", "HTTP"];
+
 #[derive(Debug)]
 pub struct PingResult {
     ping_time: DateTime<Utc>,
@@ -12,9 +22,16 @@ pub struct PingResult {
     protocol: &'static str,
     target: SocketAddr,
     source: SocketAddr,
+    local_addr: Option<SocketAddr>,
     is_warmup: bool,
+    is_reprobe: bool,
     is_succeeded: bool,
     round_trip_time: Duration,
+    handshake_time: Option<Duration>,
+    status_code: Option<u16>,
+    time_to_first_byte: Option<Duration>,
+    total_time: Option<Duration>,
+    extra_details: PingResultExtraDetails,
     is_timed_out: bool,
     warning: Option<PingClientWarning>,
     error: Option<PingClientError>,
@@ -31,8 +48,14 @@ impl PingResult {
         target: SocketAddr,
         source: SocketAddr,
         is_warmup: bool,
+        is_reprobe: bool,
         is_succeeded: bool,
         round_trip_time: Duration,
+        handshake_time: Option<Duration>,
+        status_code: Option<u16>,
+        time_to_first_byte: Option<Duration>,
+        total_time: Option<Duration>,
+        extra_details: PingResultExtraDetails,
         is_timed_out: bool,
         warning: Option<PingClientWarning>,
         error: Option<PingClientError>,
@@ -43,15 +66,27 @@ impl PingResult {
             protocol,
             target,
             source,
+            local_addr: None,
             is_warmup,
+            is_reprobe,
             is_succeeded,
             round_trip_time,
+            handshake_time,
+            status_code,
+            time_to_first_byte,
+            total_time,
+            extra_details,
             is_timed_out,
             warning,
             error,
         }
     }
 
+    pub fn with_local_addr(mut self, local_addr: Option<SocketAddr>) -> PingResult {
+        self.local_addr = local_addr;
+        return self;
+    }
+
     pub fn ping_time(&self) -> &DateTime<Utc> {
         &self.ping_time
     }
@@ -64,18 +99,48 @@ impl PingResult {
     pub fn target(&self) -> SocketAddr {
         self.target
     }
+    /// Address the ping is actually sent from, which is reported by the OS after the ping, e.g. the IP picked for 0.0.0.0.
     pub fn source(&self) -> SocketAddr {
+        self.local_addr.unwrap_or(self.source)
+    }
+    /// Address the ping is configured to send from, which stays the same no matter the ping succeeded or not, so it should
+    /// be used for breaking down the results by source.
+    pub fn configured_source(&self) -> SocketAddr {
         self.source
     }
+    pub fn flow_label(&self) -> Option<u32> {
+        self.extra_details.get(PING_RESULT_EXTRA_DETAIL_FLOW_LABEL).and_then(|flow_label| flow_label.parse().ok())
+    }
+    pub fn type_of_service(&self) -> Option<u8> {
+        self.extra_details.get(PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE).and_then(|type_of_service| type_of_service.parse().ok())
+    }
     pub fn is_warmup(&self) -> bool {
         self.is_warmup
     }
+    pub fn is_reprobe(&self) -> bool {
+        self.is_reprobe
+    }
     pub fn is_succeeded(&self) -> bool {
         self.is_succeeded
     }
     pub fn round_trip_time(&self) -> Duration {
         self.round_trip_time
     }
+    pub fn handshake_time(&self) -> Option<Duration> {
+        self.handshake_time
+    }
+    pub fn status_code(&self) -> Option<u16> {
+        self.status_code
+    }
+    pub fn time_to_first_byte(&self) -> Option<Duration> {
+        self.time_to_first_byte
+    }
+    pub fn total_time(&self) -> Option<Duration> {
+        self.total_time
+    }
+    pub fn extra_details(&self) -> &PingResultExtraDetails {
+        &self.extra_details
+    }
     pub fn is_timed_out(&self) -> bool {
         self.is_timed_out
     }
@@ -85,6 +150,28 @@ impl PingResult {
     pub fn error(&self) -> &Option<PingClientError> {
         &self.error
     }
+
+    /// Check if the ping succeeded only after the SYN is retransmitted. The SYN retransmit count in TCP_INFO is used when
+    /// the ping client reports it, otherwise we check if the RTT of the TCP based pings matches the SYN retransmit timers.
+    pub fn is_succeeded_after_retransmit(&self) -> bool {
+        if !self.is_succeeded() {
+            return false;
+        }
+
+        if let Some(syn_retransmits) = self.extra_details().get(PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS) {
+            return matches!(syn_retransmits.parse::<u32>(), Ok(count) if count > 0);
+        }
+
+        if !TCP_BASED_PROTOCOLS.contains(&self.protocol()) {
+            return false;
+        }
+
+        let rtt_in_ms = self.round_trip_time().as_millis();
+        return SYN_RETRANSMIT_TIMES_IN_MS.iter().any(|retransmit_time_in_ms| {
+            rtt_in_ms >= *retransmit_time_in_ms && rtt_in_ms < retransmit_time_in_ms + SYN_RETRANSMIT_RTT_TOLERANCE_IN_MS
+        });
+    }
+
     pub fn is_preparation_error(&self) -> bool {
         if let Some(PingClientError::PreparationFailed(_)) = self.error() {
             true
@@ -139,8 +226,15 @@ impl PingResult {
             source_ip: self.source().ip(),
             source_port: self.source().port(),
             is_warmup: self.is_warmup(),
+            is_reprobe: self.is_reprobe(),
             is_succeeded: self.is_succeeded(),
             rtt_in_ms: self.round_trip_time().as_micros() as f64 / 1000.0,
+            handshake_time_in_ms: self.handshake_time().map(|t| t.as_micros() as f64 / 1000.0),
+            status_code: self.status_code(),
+            ttfb_in_ms: self.time_to_first_byte().map(|t| t.as_micros() as f64 / 1000.0),
+            total_time_in_ms: self.total_time().map(|t| t.as_micros() as f64 / 1000.0),
+            extra_details: self.extra_details().clone(),
+            error_category: PingErrorCategory::from_ping_result(self).map_or(String::from(""), |c| c.to_string()),
             is_timed_out: self.is_timed_out(),
             preparation_error,
             ping_error,
@@ -166,6 +260,10 @@ impl PingResult {
 mod tests {
     use crate::ping_result::PingResult;
     use crate::rnp_test_common;
+    use crate::{
+        PingResultExtraDetails, PING_RESULT_EXTRA_DETAIL_FLOW_LABEL, PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS,
+        PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE,
+    };
     use chrono::Utc;
     use pretty_assertions::assert_eq;
     use std::net::SocketAddr;
@@ -180,8 +278,19 @@ mod tests {
             "1.2.3.4:443".parse().unwrap(),
             "5.6.7.8:8080".parse().unwrap(),
             true,
+            false,
             true,
             Duration::from_millis(10),
+            Some(Duration::from_millis(5)),
+            Some(200),
+            Some(Duration::from_millis(15)),
+            Some(Duration::from_millis(18)),
+            vec![
+                (PING_RESULT_EXTRA_DETAIL_FLOW_LABEL.to_string(), "12345".to_string()),
+                (PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE.to_string(), "184".to_string()),
+            ]
+            .into_iter()
+            .collect(),
             false,
             None,
             None,
@@ -191,21 +300,68 @@ mod tests {
         assert_eq!("TCP", r.protocol());
         assert_eq!("1.2.3.4:443".parse::<SocketAddr>().unwrap(), r.target());
         assert_eq!("5.6.7.8:8080".parse::<SocketAddr>().unwrap(), r.source());
+        assert_eq!(Some(12345), r.flow_label());
+        assert_eq!(Some(184), r.type_of_service());
         assert!(r.is_warmup());
         assert!(r.is_succeeded());
         assert_eq!(Duration::from_millis(10), r.round_trip_time());
+        assert_eq!(Some(Duration::from_millis(5)), r.handshake_time());
+        assert_eq!(Some(200), r.status_code());
+        assert_eq!(Some(Duration::from_millis(15)), r.time_to_first_byte());
+        assert_eq!(Some(Duration::from_millis(18)), r.total_time());
         assert!(r.error().is_none());
         assert!(r.warning().is_none());
     }
 
+    #[test]
+    fn syn_retransmit_should_be_detected() {
+        let create_result = |protocol: &'static str, rtt_in_ms: u64, syn_retransmits: Option<&str>| {
+            let mut extra_details = PingResultExtraDetails::new();
+            if let Some(syn_retransmits) = syn_retransmits {
+                extra_details.insert(PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS.to_string(), syn_retransmits.to_string());
+            }
+
+            PingResult::new(
+                &Utc::now(),
+                1,
+                protocol,
+                "1.2.3.4:443".parse().unwrap(),
+                "5.6.7.8:8080".parse().unwrap(),
+                false,
+                false,
+                true,
+                Duration::from_millis(rtt_in_ms),
+                None,
+                None,
+                None,
+                None,
+                extra_details,
+                false,
+                None,
+                None,
+            )
+        };
+
+        // TCP_INFO always wins when it is reported.
+        assert!(create_result("TCP", 20, Some("1")).is_succeeded_after_retransmit());
+        assert!(!create_result("TCP", 1010, Some("0")).is_succeeded_after_retransmit());
+
+        // Otherwise, RTT is matched against the SYN retransmit timers for TCP based protocols.
+        assert!(create_result("TLS", 1010, None).is_succeeded_after_retransmit());
+        assert!(create_result("HTTP", 3150, None).is_succeeded_after_retransmit());
+        assert!(!create_result("TCP", 20, None).is_succeeded_after_retransmit());
+        assert!(!create_result("TCP", 1500, None).is_succeeded_after_retransmit());
+        assert!(!create_result("UDP", 1010, None).is_succeeded_after_retransmit());
+    }
+
     #[test]
     fn format_ping_result_as_log_should_work() {
         let results = rnp_test_common::generate_ping_result_test_samples();
         assert_eq!(
             vec![
-                "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 (warmup) succeeded: RTT=10.00ms",
+                "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 (warmup) succeeded: RTT=10.00ms, Status=200, TTFB=15.00ms, Total=18.00ms",
                 "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 failed: Timed out, RTT = 1000.00ms",
-                "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 succeeded, but app handshake failed: RTT=20.00ms, Error = connect aborted",
+                "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 succeeded, but app handshake failed: RTT=20.00ms, Handshake=5.00ms, Error = connect aborted",
                 "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 succeeded, but disconnect failed: RTT=20.00ms, Error = disconnect timeout",
                 "Reaching TCP 1.2.3.4:443 from 5.6.7.8:8080 failed: connect failed",
                 "Unable to perform ping to TCP 1.2.3.4:443 from 5.6.7.8:8080, because failed preparing to ping: Error = address in use",
@@ -219,12 +375,12 @@ mod tests {
         let results = rnp_test_common::generate_ping_result_test_samples();
         assert_eq!(
             vec![
-                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":true,\"IsSucceeded\":true,\"RttInMs\":10.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"\"}",
-                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":false,\"RttInMs\":1000.00,\"IsTimedOut\":true,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"\"}",
-                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":true,\"RttInMs\":20.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"connect aborted\",\"DisconnectError\":\"\"}",
-                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":true,\"RttInMs\":20.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"disconnect timeout\"}",
-                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":false,\"RttInMs\":0.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"connect failed\",\"HandshakeError\":\"\",\"DisconnectError\":\"\"}",
-                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":false,\"RttInMs\":0.00,\"IsTimedOut\":false,\"PreparationError\":\"address in use\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"\"}",
+                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":true,\"IsSucceeded\":true,\"RttInMs\":10.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"\",\"HandshakeTimeInMs\":null,\"StatusCode\":200,\"TtfbInMs\":15.00,\"TotalTimeInMs\":18.00,\"ExtraDetails\":{\"TcpMss\":\"1460\",\"TcpSynRetransmits\":\"0\"},\"ErrorCategory\":\"\",\"IsReprobe\":false}",
+                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":false,\"RttInMs\":1000.00,\"IsTimedOut\":true,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"\",\"HandshakeTimeInMs\":null,\"StatusCode\":null,\"TtfbInMs\":null,\"TotalTimeInMs\":null,\"ExtraDetails\":{},\"ErrorCategory\":\"Timeout\",\"IsReprobe\":false}",
+                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":true,\"RttInMs\":20.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"connect aborted\",\"DisconnectError\":\"\",\"HandshakeTimeInMs\":5.00,\"StatusCode\":null,\"TtfbInMs\":null,\"TotalTimeInMs\":null,\"ExtraDetails\":{},\"ErrorCategory\":\"ConnectionReset\",\"IsReprobe\":false}",
+                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":true,\"RttInMs\":20.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"disconnect timeout\",\"HandshakeTimeInMs\":null,\"StatusCode\":null,\"TtfbInMs\":null,\"TotalTimeInMs\":null,\"ExtraDetails\":{},\"ErrorCategory\":\"\",\"IsReprobe\":false}",
+                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":false,\"RttInMs\":0.00,\"IsTimedOut\":false,\"PreparationError\":\"\",\"PingError\":\"connect failed\",\"HandshakeError\":\"\",\"DisconnectError\":\"\",\"HandshakeTimeInMs\":null,\"StatusCode\":null,\"TtfbInMs\":null,\"TotalTimeInMs\":null,\"ExtraDetails\":{},\"ErrorCategory\":\"ConnectionRefused\",\"IsReprobe\":false}",
+                "{\"UtcTime\":\"2021-07-06T09:10:11.012Z\",\"WorkerId\":1,\"Protocol\":\"TCP\",\"TargetIp\":\"1.2.3.4\",\"TargetPort\":443,\"SourceIp\":\"5.6.7.8\",\"SourcePort\":8080,\"IsWarmup\":false,\"IsSucceeded\":false,\"RttInMs\":0.00,\"IsTimedOut\":false,\"PreparationError\":\"address in use\",\"PingError\":\"\",\"HandshakeError\":\"\",\"DisconnectError\":\"\",\"HandshakeTimeInMs\":null,\"StatusCode\":null,\"TtfbInMs\":null,\"TotalTimeInMs\":null,\"ExtraDetails\":{},\"ErrorCategory\":\"PreparationFailed\",\"IsReprobe\":false}",
             ],
             results.into_iter().map(|x| x.format_as_json_string()).collect::<Vec<String>>()
         );
@@ -235,12 +391,12 @@ mod tests {
         let results = rnp_test_common::generate_ping_result_test_samples();
         assert_eq!(
             vec![
-                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,true,true,10.00,false,\"\",\"\",\"\",\"\"",
-                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,false,1000.00,true,\"\",\"\",\"\",\"\"",
-                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,true,20.00,false,\"\",\"\",\"connect aborted\",\"\"",
-                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,true,20.00,false,\"\",\"\",\"\",\"disconnect timeout\"",
-                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,false,0.00,false,\"\",\"connect failed\",\"\",\"\"",
-                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,false,0.00,false,\"address in use\",\"\",\"\",\"\"",
+                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,true,true,10.00,false,\"\",\"\",\"\",\"\",,200,15.00,18.00,\"TcpMss=1460;TcpSynRetransmits=0\",,false",
+                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,false,1000.00,true,\"\",\"\",\"\",\"\",,,,,\"\",Timeout,false",
+                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,true,20.00,false,\"\",\"\",\"connect aborted\",\"\",5.00,,,,\"\",ConnectionReset,false",
+                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,true,20.00,false,\"\",\"\",\"\",\"disconnect timeout\",,,,,\"\",,false",
+                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,false,0.00,false,\"\",\"connect failed\",\"\",\"\",,,,,\"\",ConnectionRefused,false",
+                "2021-07-06T09:10:11.012Z,1,TCP,1.2.3.4,443,5.6.7.8,8080,false,false,0.00,false,\"address in use\",\"\",\"\",\"\",,,,,\"\",PreparationFailed,false",
             ],
             results.into_iter().map(|x| x.format_as_csv_string()).collect::<Vec<String>>()
         );
@@ -1,4 +1,4 @@
-use crate::{ping_result_processors::ping_result_processor_factory, PingResult, PingResultProcessor, PingResultProcessorConfig};
+use crate::{ping_result_processors::ping_result_processor_factory, PingResult, PingResultProcessor, PingResultProcessorConfig, PingRunSummary};
 use contracts::requires;
 use futures_intrusive::sync::ManualResetEvent;
 use std::sync::Arc;
@@ -9,6 +9,8 @@ pub struct PingResultProcessingWorker {
 
     receiver: mpsc::UnboundedReceiver<PingResult>,
     processors: Vec<Box<dyn PingResultProcessor + Send + Sync>>,
+    result_stream_sender: Option<mpsc::Sender<PingResult>>,
+    summary: PingRunSummary,
 }
 
 impl PingResultProcessingWorker {
@@ -19,11 +21,16 @@ impl PingResultProcessingWorker {
         stop_event: Arc<ManualResetEvent>,
         ping_stop_event: Arc<ManualResetEvent>,
         receiver: mpsc::UnboundedReceiver<PingResult>,
-    ) -> JoinHandle<()> {
+        result_stream_sender: Option<mpsc::Sender<PingResult>>,
+    ) -> JoinHandle<PingRunSummary> {
         let join_handle = task::spawn(async move {
             let processors = ping_result_processor_factory::new(&config, extra_ping_result_processors, ping_stop_event);
-            let mut worker = PingResultProcessingWorker { stop_event, receiver, processors };
+            let mut worker = PingResultProcessingWorker { stop_event, receiver, processors, result_stream_sender, summary: PingRunSummary::new() };
             worker.run_worker().await;
+
+            // The exit failure reason is set by the console logger, so we can only get it after all processors are done.
+            worker.summary.exit_failure_reason = config.exit_failure_reason.as_ref().and_then(|reason| reason.lock().unwrap().clone());
+            return worker.summary;
         });
 
         return join_handle;
@@ -49,6 +56,7 @@ impl PingResultProcessingWorker {
             tokio::select! {
                 Some(ping_result) = self.receiver.recv() => {
                     self.process_ping_result(&ping_result);
+                    self.forward_ping_result_to_stream(ping_result).await;
                 }
 
                 _ = self.stop_event.wait() => {
@@ -66,6 +74,7 @@ impl PingResultProcessingWorker {
 
         while let Some(ping_result) = self.receiver.recv().await {
             self.process_ping_result(&ping_result);
+            self.forward_ping_result_to_stream(ping_result).await;
         }
 
         tracing::debug!("All pending ping results are processed, exiting ping result processing worker loop.");
@@ -73,11 +82,26 @@ impl PingResultProcessingWorker {
 
     #[tracing::instrument(name = "Processing ping result", level = "debug", skip(self), fields(processor_count = %self.processors.len()))]
     fn process_ping_result(&mut self, ping_result: &PingResult) {
+        self.summary.update(ping_result);
+
         for processor in &mut self.processors {
             processor.process_ping_result(ping_result);
         }
     }
 
+    async fn forward_ping_result_to_stream(&mut self, ping_result: PingResult) {
+        let sender = match &self.result_stream_sender {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        // Waiting here gives backpressure to the stream consumer. If the stream is dropped, we stop forwarding.
+        if sender.send(ping_result).await.is_err() {
+            tracing::debug!("Ping result stream is closed, stop forwarding ping results.");
+            self.result_stream_sender = None;
+        }
+    }
+
     #[tracing::instrument(name = "Signal all ping result processors done", level = "debug", skip(self), fields(processor_count = %self.processors.len()))]
     fn signal_all_processors_done(&mut self) {
         for processor in &mut self.processors {
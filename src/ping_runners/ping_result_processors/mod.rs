@@ -1,10 +1,13 @@
 pub mod ping_result_processor;
+mod ping_result_processor_bad_path_logger;
 mod ping_result_processor_console_logger;
 mod ping_result_processor_csv_logger;
 pub mod ping_result_processor_factory;
 mod ping_result_processor_json_logger;
+mod ping_result_processor_json_summary_logger;
 mod ping_result_processor_latency_bucket_logger;
 mod ping_result_processor_latency_scatter_logger;
+mod ping_result_processor_prometheus_exporter;
 mod ping_result_processor_result_scatter_logger;
 mod ping_result_processor_text_logger;
 
@@ -0,0 +1,329 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing;
+
+// A source port is reported as a bad path when its loss or latency is more than 3 standard errors worse than the whole
+// population, which is unlikely to happen by chance (p < 0.0015), even with thousands of source ports.
+const BAD_PATH_Z_SCORE_THRESHOLD: f64 = 3.0;
+
+#[derive(Debug, Default)]
+struct SourcePortPathStatistics {
+    outcome: PingSourcePortOutcome,
+    latency_sum_in_us: u128,
+}
+
+impl SourcePortPathStatistics {
+    fn average_latency_in_us(&self) -> f64 {
+        if self.outcome.success_count == 0 {
+            return 0.0;
+        }
+
+        return self.latency_sum_in_us as f64 / self.outcome.success_count as f64;
+    }
+}
+
+#[derive(Debug, Default)]
+struct TargetPathStatistics {
+    // Latency is only tracked for succeeded pings, because timed out pings are recorded with the wait timeout as RTT.
+    population_outcome: PingSourcePortOutcome,
+    population_latency_histogram: PingLatencyHistogram,
+
+    // Keyed by source IP and port, since the same source port on different source IPs can go through different paths,
+    // e.g. with different SNAT mappings.
+    sources: BTreeMap<SocketAddr, SourcePortPathStatistics>,
+}
+
+#[derive(Debug, PartialEq)]
+struct BadPath {
+    source: SocketAddr,
+    ping_count: u32,
+    loss_ratio: f64,
+    average_latency: Duration,
+    is_loss_worse: bool,
+    is_latency_worse: bool,
+}
+
+impl TargetPathStatistics {
+    fn update(&mut self, ping_result: &PingResult) {
+        self.population_outcome.update(ping_result);
+
+        let source_port_statistics = self.sources.entry(ping_result.configured_source()).or_default();
+        source_port_statistics.outcome.update(ping_result);
+
+        if ping_result.is_succeeded() {
+            self.population_latency_histogram.record(&ping_result.round_trip_time());
+            source_port_statistics.latency_sum_in_us += ping_result.round_trip_time().as_micros();
+        }
+    }
+
+    fn find_bad_paths(&self) -> Vec<BadPath> {
+        let population_loss_ratio = self.population_outcome.failure_count as f64 / self.population_outcome.ping_count as f64;
+        let population_latency_summary = self.population_latency_histogram.summary();
+        let population_average_latency_in_us = population_latency_summary.average.as_micros() as f64;
+        let population_latency_std_dev_in_us = population_latency_summary.std_dev.as_micros() as f64;
+
+        let mut bad_paths = Vec::new();
+        for (source, statistics) in &self.sources {
+            // The loss of each source port follows binomial distribution, if it shares the same loss ratio as the population.
+            let ping_count = statistics.outcome.ping_count as f64;
+            let loss_ratio = statistics.outcome.failure_count as f64 / ping_count;
+            let loss_std_error = (population_loss_ratio * (1.0 - population_loss_ratio) / ping_count).sqrt();
+            let is_loss_worse = loss_std_error > 0.0 && (loss_ratio - population_loss_ratio) / loss_std_error > BAD_PATH_Z_SCORE_THRESHOLD;
+
+            let average_latency_in_us = statistics.average_latency_in_us();
+            let latency_std_error = population_latency_std_dev_in_us / (statistics.outcome.success_count as f64).sqrt();
+            let is_latency_worse = statistics.outcome.success_count > 0
+                && latency_std_error > 0.0
+                && (average_latency_in_us - population_average_latency_in_us) / latency_std_error > BAD_PATH_Z_SCORE_THRESHOLD;
+
+            if is_loss_worse || is_latency_worse {
+                bad_paths.push(BadPath {
+                    source: *source,
+                    ping_count: statistics.outcome.ping_count,
+                    loss_ratio,
+                    average_latency: Duration::from_micros(average_latency_in_us.round() as u64),
+                    is_loss_worse,
+                    is_latency_worse,
+                });
+            }
+        }
+
+        return bad_paths;
+    }
+
+    fn source_ip_count(&self) -> usize {
+        let mut source_ips: Vec<IpAddr> = self.sources.keys().map(|source| source.ip()).collect();
+        source_ips.dedup();
+        return source_ips.len();
+    }
+}
+
+pub struct PingResultProcessorBadPathLogger {
+    common_config: Arc<PingResultProcessorCommonConfig>,
+    target_statistics: BTreeMap<SocketAddr, TargetPathStatistics>,
+}
+
+impl PingResultProcessorBadPathLogger {
+    #[tracing::instrument(name = "Creating ping result bad path logger", level = "debug")]
+    pub fn new(common_config: Arc<PingResultProcessorCommonConfig>) -> PingResultProcessorBadPathLogger {
+        return PingResultProcessorBadPathLogger { common_config, target_statistics: BTreeMap::new() };
+    }
+
+    // Merge the ports into ranges, so the output can be used in --src-ports directly.
+    fn format_source_ports(bad_paths: &[BadPath]) -> String {
+        let source_ports: Vec<u16> = bad_paths.iter().map(|bad_path| bad_path.source.port()).collect();
+        return PortRangeList::from_values(&source_ports).to_string();
+    }
+
+    fn output_bad_paths_to_console(target: &SocketAddr, statistics: &TargetPathStatistics) {
+        println!("\n--- Target: {:?} ---", target);
+        let bad_paths = statistics.find_bad_paths();
+        if bad_paths.is_empty() {
+            println!("No bad path found.");
+            return;
+        }
+
+        // Bad paths are sorted by source IP, so each source IP gets its own table, which can be re-run separately.
+        let is_multiple_source_ips = statistics.source_ip_count() > 1;
+        let mut source_ip_start = 0;
+        for index in 1..=bad_paths.len() {
+            if index < bad_paths.len() && bad_paths[index].source.ip() == bad_paths[source_ip_start].source.ip() {
+                continue;
+            }
+
+            let source_ip_bad_paths = &bad_paths[source_ip_start..index];
+            source_ip_start = index;
+
+            let source_ip = source_ip_bad_paths[0].source.ip();
+            if is_multiple_source_ips {
+                println!("\nFrom source IP {}:", source_ip);
+            }
+
+            PingResultProcessorBadPathLogger::output_bad_path_table_to_console(source_ip_bad_paths);
+
+            let source_ip_option = if is_multiple_source_ips { format!("-s {} ", source_ip) } else { "".to_string() };
+            println!(
+                "\nRe-run the bad paths with: {}--src-ports {}",
+                source_ip_option,
+                PingResultProcessorBadPathLogger::format_source_ports(source_ip_bad_paths)
+            );
+        }
+    }
+
+    fn output_bad_path_table_to_console(bad_paths: &[BadPath]) {
+        println!("{:>5} | {:>6} | {:>8} | {:>10} | {}", "Src", "Pings", "Loss", "Avg RTT", "Reason");
+        println!("{:->6}|{:->8}|{:->10}|{:->12}|{:->10}", "", "", "", "", "");
+        for bad_path in bad_paths {
+            let mut reasons = Vec::new();
+            if bad_path.is_loss_worse {
+                reasons.push("Loss");
+            }
+            if bad_path.is_latency_worse {
+                reasons.push("Latency");
+            }
+
+            println!(
+                "{:>5} | {:>6} | {:>7.2}% | {:>8.2}ms | {}",
+                bad_path.source.port(),
+                bad_path.ping_count,
+                bad_path.loss_ratio * 100.0,
+                bad_path.average_latency.as_micros() as f64 / 1000.0,
+                reasons.join(", ")
+            );
+        }
+    }
+}
+
+impl PingResultProcessor for PingResultProcessorBadPathLogger {
+    fn name(&self) -> &'static str {
+        "BadPathLogger"
+    }
+    fn config(&self) -> &PingResultProcessorCommonConfig {
+        self.common_config.as_ref()
+    }
+
+    fn process_ping_result(&mut self, ping_result: &PingResult) {
+        if self.has_quiet_level(RNP_QUIET_LEVEL_NO_PING_SUMMARY) {
+            return;
+        }
+
+        // Skip warmup pings and preparation errors in analysis, since they don't reflect the path status. Re-probes are
+        // skipped too, because they are only sent on the failed source ports, which would skew the comparison.
+        if ping_result.is_warmup() || ping_result.is_reprobe() || ping_result.is_preparation_error() {
+            return;
+        }
+
+        self.target_statistics.entry(ping_result.target()).or_default().update(ping_result);
+    }
+
+    fn rundown(&mut self) {
+        if self.has_quiet_level(RNP_QUIET_LEVEL_NO_PING_SUMMARY) {
+            return;
+        }
+
+        println!("\n=== Bad paths (source ports with loss or latency significantly worse than others) ===");
+        for (target, statistics) in &self.target_statistics {
+            PingResultProcessorBadPathLogger::output_bad_paths_to_console(target, statistics);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+
+    fn create_ping_result(source_port: u16, is_succeeded: bool, rtt_in_ms: u64) -> PingResult {
+        return create_ping_result_from_source_ip("5.6.7.8", source_port, is_succeeded, rtt_in_ms);
+    }
+
+    fn create_ping_result_from_source_ip(source_ip: &str, source_port: u16, is_succeeded: bool, rtt_in_ms: u64) -> PingResult {
+        return PingResult::new(
+            &Utc::now(),
+            1,
+            "TCP",
+            "1.2.3.4:443".parse().unwrap(),
+            SocketAddr::new(source_ip.parse().unwrap(), source_port),
+            false,
+            false,
+            is_succeeded,
+            Duration::from_millis(rtt_in_ms),
+            None,
+            None,
+            None,
+            None,
+            PingResultExtraDetails::new(),
+            !is_succeeded,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn bad_paths_should_be_detected() {
+        let mut logger = PingResultProcessorBadPathLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NONE }));
+
+        for iteration in 0..10 {
+            for source_port in 1000..1100 {
+                let ping_result = match source_port {
+                    // Port 1010 and 1011 lose most of the pings.
+                    1010 | 1011 if iteration < 8 => create_ping_result(source_port, false, 1000),
+
+                    // Port 1050 always has higher latency.
+                    1050 => create_ping_result(source_port, true, 50),
+
+                    // Other ports has a little loss and jitter, which should not be reported.
+                    _ if source_port % 10 == iteration => create_ping_result(source_port, false, 1000),
+                    _ => create_ping_result(source_port, true, 10 + (source_port % 3) as u64),
+                };
+                logger.process_ping_result(&ping_result);
+            }
+        }
+
+        let bad_paths = logger.target_statistics.values().next().unwrap().find_bad_paths();
+        assert_eq!(vec![1010, 1011, 1050], bad_paths.iter().map(|x| x.source.port()).collect::<Vec<u16>>());
+        assert!(bad_paths[0].is_loss_worse && !bad_paths[0].is_latency_worse);
+        assert!(!bad_paths[2].is_loss_worse && bad_paths[2].is_latency_worse);
+        assert_eq!(Duration::from_millis(50), bad_paths[2].average_latency);
+
+        assert_eq!("1010-1011,1050", PingResultProcessorBadPathLogger::format_source_ports(&bad_paths));
+    }
+
+    #[test]
+    fn bad_paths_should_be_detected_per_source_ip() {
+        let mut logger = PingResultProcessorBadPathLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NONE }));
+
+        for iteration in 0..10 {
+            for source_ip in ["5.6.7.8", "5.6.7.9"] {
+                for source_port in 1000..1050 {
+                    // Port 1010 only loses pings on the first source IP, e.g. due to a different SNAT mapping.
+                    let is_succeeded = !(source_ip == "5.6.7.8" && source_port == 1010 && iteration < 8);
+                    logger.process_ping_result(&create_ping_result_from_source_ip(source_ip, source_port, is_succeeded, 10));
+                }
+            }
+        }
+
+        let statistics = logger.target_statistics.values().next().unwrap();
+        assert_eq!(2, statistics.source_ip_count());
+        assert_eq!(
+            vec!["5.6.7.8:1010".parse::<SocketAddr>().unwrap()],
+            statistics.find_bad_paths().iter().map(|x| x.source).collect::<Vec<SocketAddr>>()
+        );
+    }
+
+    #[test]
+    fn bad_paths_should_be_keyed_by_configured_source_when_source_ip_is_unspecified() {
+        let mut logger = PingResultProcessorBadPathLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NONE }));
+
+        // Only succeeded pings get the local address picked by the OS, the timed out ones still have 0.0.0.0 as source.
+        for iteration in 0..10 {
+            for source_port in 1000..1050 {
+                let ping_result = if source_port % 10 == iteration {
+                    create_ping_result_from_source_ip("0.0.0.0", source_port, false, 1000)
+                } else {
+                    create_ping_result_from_source_ip("0.0.0.0", source_port, true, 10)
+                        .with_local_addr(Some(SocketAddr::new("5.6.7.8".parse().unwrap(), source_port)))
+                };
+                logger.process_ping_result(&ping_result);
+            }
+        }
+
+        let statistics = logger.target_statistics.values().next().unwrap();
+        assert_eq!(1, statistics.source_ip_count());
+        assert_eq!(Vec::<BadPath>::new(), statistics.find_bad_paths());
+    }
+
+    #[test]
+    fn no_bad_path_should_be_reported_when_all_paths_are_the_same() {
+        let mut logger = PingResultProcessorBadPathLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NONE }));
+        for source_port in 1000..1100 {
+            logger.process_ping_result(&create_ping_result(source_port, true, 10));
+        }
+
+        assert_eq!(Vec::<BadPath>::new(), logger.target_statistics.values().next().unwrap().find_bad_paths());
+    }
+}
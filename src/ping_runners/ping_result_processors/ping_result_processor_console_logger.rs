@@ -1,7 +1,8 @@
 use crate::*;
 use futures_intrusive::sync::ManualResetEvent;
+use std::collections::BTreeMap;
 use std::io::{stdout, Write};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tracing;
@@ -14,16 +15,7 @@ pub struct PingResultProcessorConsoleLogger {
     exit_on_fail: bool,
     exit_failure_reason: Option<Arc<Mutex<Option<PingResultDto>>>>,
 
-    protocol: Option<String>,
-    target: Option<SocketAddr>,
-    ping_count: u32,
-    success_count: u32,
-    failure_count: u32,
-    handshake_failed_count: u32,
-    disconnect_failed_count: u32,
-    min_latency_in_us: u128,
-    max_latency_in_us: u128,
-    average_latency_in_us: f64,
+    statistics: PingRunStatistics,
 }
 
 impl PingResultProcessorConsoleLogger {
@@ -40,61 +32,97 @@ impl PingResultProcessorConsoleLogger {
             ping_stop_event,
             exit_on_fail,
             exit_failure_reason,
-            protocol: None,
-            target: None,
-            ping_count: 0,
-            success_count: 0,
-            failure_count: 0,
-            handshake_failed_count: 0,
-            disconnect_failed_count: 0,
-            min_latency_in_us: u128::MAX,
-            max_latency_in_us: u128::MIN,
-            average_latency_in_us: 0.0,
+            statistics: PingRunStatistics::new(),
         };
     }
 
-    fn update_statistics(&mut self, ping_result: &PingResult) {
-        // Skip warmup pings in analysis.
-        if ping_result.is_warmup() {
-            return;
-        }
+    fn output_statistics_to_console(statistics: &PingResultStatistics, title: &str) {
+        println!("\n=== Connect statistics for {} ===", title);
 
-        // Skip preparation errors in analysis, since it is not a remote issue.
-        if ping_result.is_preparation_error() {
-            return;
+        let mut warning: String = String::from("");
+        if statistics.syn_retransmitted_count > 0 || statistics.handshake_failed_count > 0 || statistics.disconnect_failed_count > 0 {
+            let mut warning_messages = Vec::new();
+            if statistics.syn_retransmitted_count > 0 {
+                warning_messages.push(format!("After SYN Retransmit = {}", statistics.syn_retransmitted_count));
+            }
+            if statistics.handshake_failed_count > 0 {
+                warning_messages.push(format!("App Handshake Failed = {}", statistics.handshake_failed_count));
+            }
+            if statistics.disconnect_failed_count > 0 {
+                warning_messages.push(format!("Disconnect Failed = {}", statistics.disconnect_failed_count));
+            }
+            warning = format!(" ({})", warning_messages.join(", "));
         }
 
-        // Save some info for outputting summary.
-        if self.target.is_none() {
-            self.protocol = Some(ping_result.protocol().to_string());
-            self.target = Some(ping_result.target());
+        println!(
+            "- Connects: Sent = {}, Succeeded = {}{}, Failed = {} ({:.2}%).",
+            statistics.ping_count,
+            statistics.success_count,
+            warning,
+            statistics.failure_count,
+            (statistics.failure_count as f64 * 100.0) / (statistics.ping_count as f64),
+        );
+
+        if !statistics.error_category_counts.is_empty() {
+            let error_category_messages: Vec<String> =
+                statistics.error_category_counts.iter().map(|(category, count)| format!("{} = {}", category, count)).collect();
+            println!("- Errors: {}.", error_category_messages.join(", "));
         }
 
-        self.ping_count += 1;
-        if ping_result.is_succeeded() {
-            self.success_count += 1;
+        // If we haven't received any data, the latency data won't be updated correctly,
+        // os we output the data differently.
+        let latency = statistics.latency_summary();
+        if latency.sample_count == 0 {
+            println!("- Round trip time: Minimum = 0ms, Maximum = 0ms, Average = 0ms.");
         } else {
-            self.failure_count += 1;
+            println!(
+                "- Round trip time: Minimum = {:.2}ms, Maximum = {:.2}ms, Average = {:.2}ms.",
+                latency.min.as_micros() as f64 / 1000.0,
+                latency.max.as_micros() as f64 / 1000.0,
+                latency.average.as_micros() as f64 / 1000.0
+            );
+            println!(
+                "- Round trip time percentiles: P50 = {:.2}ms, P90 = {:.2}ms, P95 = {:.2}ms, P99 = {:.2}ms, P99.9 = {:.2}ms.",
+                latency.p50.as_micros() as f64 / 1000.0,
+                latency.p90.as_micros() as f64 / 1000.0,
+                latency.p95.as_micros() as f64 / 1000.0,
+                latency.p99.as_micros() as f64 / 1000.0,
+                latency.p999.as_micros() as f64 / 1000.0
+            );
+            println!(
+                "- Round trip time variation: Standard deviation = {:.2}ms, Jitter = {:.2}ms.",
+                latency.std_dev.as_micros() as f64 / 1000.0,
+                latency.jitter.as_micros() as f64 / 1000.0
+            );
         }
+    }
 
-        if let Some(warning) = ping_result.warning() {
-            match warning {
-                PingClientWarning::AppHandshakeFailed(_) => self.handshake_failed_count += 1,
-                PingClientWarning::DisconnectFailed(_) => self.disconnect_failed_count += 1,
-            }
-        };
-
-        let latency_in_us = ping_result.round_trip_time().as_micros();
-        if latency_in_us == 0 {
-            // Latency data not set.
-            return;
-        }
+    fn output_reprobe_outcomes_to_console(target: &SocketAddr, reprobe_outcomes: &BTreeMap<SocketAddr, PingReprobeOutcome>) {
+        println!("\n--- Target: {:?} ---", target);
 
-        self.min_latency_in_us = std::cmp::min(latency_in_us, self.min_latency_in_us);
-        self.max_latency_in_us = std::cmp::max(latency_in_us, self.max_latency_in_us);
+        // The sources are sorted by source IP, so the ports of each source IP are listed together.
+        let mut source_ips: Vec<IpAddr> = reprobe_outcomes.keys().map(|source| source.ip()).collect();
+        source_ips.dedup();
+        for source_ip in &source_ips {
+            let (persistent_failure_sources, transient_failure_sources): (Vec<(&SocketAddr, &PingReprobeOutcome)>, Vec<_>) =
+                reprobe_outcomes.iter().filter(|(source, _)| source.ip() == *source_ip).partition(|(_, outcome)| outcome.is_persistent_failure());
+            let persistent_failure_ports: Vec<u16> = persistent_failure_sources.iter().map(|(source, _)| source.port()).collect();
+            let transient_failure_ports: Vec<u16> = transient_failure_sources.iter().map(|(source, _)| source.port()).collect();
 
-        // Calculate moving average. Ping count already added 1 above.
-        self.average_latency_in_us += (latency_in_us as f64 - self.average_latency_in_us) / (self.ping_count as f64);
+            let source_ip_message = if source_ips.len() > 1 { format!(" from {}", source_ip) } else { "".to_string() };
+            println!(
+                "- Persistent failures (bad paths){}: Count = {}, Source ports = {}.",
+                source_ip_message,
+                persistent_failure_ports.len(),
+                PortRangeList::from_values(&persistent_failure_ports)
+            );
+            println!(
+                "- Transient failures (random loss){}: Count = {}, Source ports = {}.",
+                source_ip_message,
+                transient_failure_ports.len(),
+                PortRangeList::from_values(&transient_failure_ports)
+            );
+        }
     }
 
     fn output_result_to_console(&mut self, ping_result: &PingResult) {
@@ -122,7 +150,7 @@ impl PingResultProcessorConsoleLogger {
 
         self.last_console_flush_time = Some(now);
 
-        print!("\r{0} pings finished.", self.ping_count);
+        print!("\r{0} pings finished.", self.statistics.total_statistics.ping_count);
 
         // Console buffer flushes whenever it sees line breaks or buffer full, so we need
         // to force flush stdout to make the line taking effect.
@@ -144,7 +172,7 @@ impl PingResultProcessor for PingResultProcessorConsoleLogger {
         // because in no summary level, we still need to count the number of pings and output
         // to console, which is part of the summary.
         if !self.has_quiet_level(RNP_QUIET_LEVEL_NO_OUTPUT) {
-            self.update_statistics(ping_result);
+            self.statistics.update(ping_result);
         }
 
         self.output_result_to_console(ping_result);
@@ -178,44 +206,45 @@ impl PingResultProcessor for PingResultProcessorConsoleLogger {
         }
 
         // Didn't received any result, skip output statistics.
-        if self.target.is_none() {
-            return;
-        }
+        let protocol = match &self.statistics.protocol {
+            Some(protocol) => protocol,
+            None => return,
+        };
 
-        println!("\n=== Connect statistics for {} {:?} ===", self.protocol.as_ref().unwrap(), self.target.as_ref().unwrap(),);
+        for (target, statistics) in &self.statistics.target_statistics {
+            PingResultProcessorConsoleLogger::output_statistics_to_console(statistics, &format!("{} {:?}", protocol, target));
+        }
 
-        let mut warning: String = String::from("");
-        if self.handshake_failed_count > 0 || self.disconnect_failed_count > 0 {
-            let mut warning_messages = Vec::new();
-            if self.handshake_failed_count > 0 {
-                warning_messages.push(format!("App Handshake Failed = {}", self.handshake_failed_count));
-            }
-            if self.disconnect_failed_count > 0 {
-                warning_messages.push(format!("Disconnect Failed = {}", self.disconnect_failed_count));
+        // Each source IP can have a different SNAT mapping, so they are shown separately when more than 1 is used.
+        if self.statistics.source_ip_statistics.len() > 1 {
+            for (source_ip, statistics) in &self.statistics.source_ip_statistics {
+                PingResultProcessorConsoleLogger::output_statistics_to_console(statistics, &format!("{} from {}", protocol, source_ip));
             }
-            warning = format!(" ({})", warning_messages.join(", "));
+        }
+
+        let target_count = self.statistics.target_statistics.len();
+        let source_ip_count = self.statistics.source_ip_statistics.len();
+        let total_title = match (target_count, source_ip_count) {
+            (0..=1, 0..=1) => None,
+            (_, 0..=1) => Some(format!("{} all {} targets", protocol, target_count)),
+            (0..=1, _) => Some(format!("{} all {} source IPs", protocol, source_ip_count)),
+            _ => Some(format!("{} all {} targets from all {} source IPs", protocol, target_count, source_ip_count)),
+        };
+        if let Some(total_title) = total_title {
+            PingResultProcessorConsoleLogger::output_statistics_to_console(&self.statistics.total_statistics, &total_title);
         }
 
         println!(
-            "- Connects: Sent = {}, Succeeded = {}{}, Failed = {} ({:.2}%).",
-            self.ping_count,
-            self.success_count,
-            warning,
-            self.failure_count,
-            (self.failure_count as f64 * 100.0) / (self.ping_count as f64),
+            "\n=== Run time ===\n- Elapsed = {:.2}s, Achieved rate = {:.2} pings/s.",
+            self.statistics.elapsed_time().as_secs_f64(),
+            self.statistics.achieved_ping_rate()
         );
 
-        // If we haven't received any data, the min/max/average data won't be updated correctly,
-        // os we output the data differently.
-        if self.min_latency_in_us == u128::MAX {
-            println!("- Round trip time: Minimum = 0ms, Maximum = 0ms, Average = 0ms.");
-        } else {
-            println!(
-                "- Round trip time: Minimum = {:.2}ms, Maximum = {:.2}ms, Average = {:.2}ms.",
-                self.min_latency_in_us as f64 / 1000.0,
-                self.max_latency_in_us as f64 / 1000.0,
-                self.average_latency_in_us / 1000.0
-            );
+        if !self.statistics.reprobe_outcomes.is_empty() {
+            println!("\n=== Re-probes of failed source ports (failed in all re-probes = persistent, otherwise transient) ===");
+            for (target, reprobe_outcomes) in &self.statistics.reprobe_outcomes {
+                PingResultProcessorConsoleLogger::output_reprobe_outcomes_to_console(target, reprobe_outcomes);
+            }
         }
     }
 }
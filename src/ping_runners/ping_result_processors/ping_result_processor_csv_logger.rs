@@ -34,7 +34,7 @@ impl PingResultProcessor for PingResultProcessorCsvLogger {
     fn initialize(&mut self) {
         // Writer CSV header
         self.log_file
-            .write("UtcTime,WorkerId,Protocol,TargetIp,TargetPort,SourceIp,SourcePort,IsWarmup,IsSucceeded,RttInMs,IsTimedOut,PreparationError,PingError,HandshakeError,DisconnectError\n".as_bytes())
+            .write("UtcTime,WorkerId,Protocol,TargetIp,TargetPort,SourceIp,SourcePort,IsWarmup,IsSucceeded,RttInMs,IsTimedOut,PreparationError,PingError,HandshakeError,DisconnectError,HandshakeTimeInMs,StatusCode,TtfbInMs,TotalTimeInMs,ExtraDetails,ErrorCategory,IsReprobe\n".as_bytes())
             .expect(&format!(
                 "Failed to write logs to csv file! Path = {}",
                 self.log_path.display()
@@ -53,6 +53,7 @@ mod tests {
     use crate::PingResultDto;
     use chrono::{TimeZone, Utc};
     use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
 
     #[test]
     fn ping_result_process_csv_logger_should_work() {
@@ -90,6 +91,15 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: Some(200),
+                    ttfb_in_ms: Some(15f64),
+                    total_time_in_ms: Some(18f64),
+                    extra_details: vec![(String::from("TcpMss"), String::from("1460")), (String::from("TcpSynRetransmits"), String::from("0"))]
+                        .into_iter()
+                        .collect(),
+                    error_category: "".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -107,6 +117,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "Timeout".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -124,6 +141,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "connect aborted".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: Some(5f64),
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "ConnectionReset".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -141,6 +165,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "disconnect timeout".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -158,6 +189,13 @@ mod tests {
                     ping_error: "connect failed".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "ConnectionRefused".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -175,6 +213,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "PreparationFailed".to_string(),
+                    is_reprobe: false,
                 },
             ],
             actual_logged_records,
@@ -1,8 +1,11 @@
+use crate::ping_result_processors::ping_result_processor_bad_path_logger::PingResultProcessorBadPathLogger;
 use crate::ping_result_processors::ping_result_processor_console_logger::PingResultProcessorConsoleLogger;
 use crate::ping_result_processors::ping_result_processor_csv_logger::PingResultProcessorCsvLogger;
 use crate::ping_result_processors::ping_result_processor_json_logger::PingResultProcessorJsonLogger;
+use crate::ping_result_processors::ping_result_processor_json_summary_logger::PingResultProcessorJsonSummaryLogger;
 use crate::ping_result_processors::ping_result_processor_latency_bucket_logger::PingResultProcessorLatencyBucketLogger;
 use crate::ping_result_processors::ping_result_processor_latency_scatter_logger::PingResultProcessorLatencyScatterLogger;
+use crate::ping_result_processors::ping_result_processor_prometheus_exporter::PingResultProcessorPrometheusExporter;
 use crate::ping_result_processors::ping_result_processor_result_scatter_logger::PingResultProcessorResultScatterLogger;
 use crate::ping_result_processors::ping_result_processor_text_logger::PingResultProcessorTextLogger;
 use crate::{PingResultProcessor, PingResultProcessorConfig};
@@ -37,6 +40,12 @@ pub fn new(
         processors.push(json_logger);
     }
 
+    if let Some(json_summary_log_path) = &config.json_summary_log_path {
+        let json_summary_logger: Box<dyn PingResultProcessor + Send + Sync> =
+            Box::new(PingResultProcessorJsonSummaryLogger::new(common_config.clone(), json_summary_log_path));
+        processors.push(json_summary_logger);
+    }
+
     if let Some(text_log_path) = &config.text_log_path {
         let text_logger: Box<dyn PingResultProcessor + Send + Sync> =
             Box::new(PingResultProcessorTextLogger::new(common_config.clone(), text_log_path));
@@ -55,12 +64,23 @@ pub fn new(
         processors.push(latency_scatter_logger);
     }
 
+    if config.show_bad_paths {
+        let bad_path_logger: Box<dyn PingResultProcessor + Send + Sync> = Box::new(PingResultProcessorBadPathLogger::new(common_config.clone()));
+        processors.push(bad_path_logger);
+    }
+
     if let Some(latency_buckets) = &config.latency_buckets {
         let latency_bucket_logger: Box<dyn PingResultProcessor + Send + Sync> =
             Box::new(PingResultProcessorLatencyBucketLogger::new(common_config.clone(), latency_buckets));
         processors.push(latency_bucket_logger);
     }
 
+    if let Some(metrics_address) = &config.metrics_address {
+        let prometheus_exporter: Box<dyn PingResultProcessor + Send + Sync> =
+            Box::new(PingResultProcessorPrometheusExporter::new(common_config.clone(), metrics_address));
+        processors.push(prometheus_exporter);
+    }
+
     // Move all extra ping result processors into the processors
     processors.append(&mut extra_ping_result_processors);
 
@@ -83,10 +103,13 @@ mod tests {
             exit_failure_reason: None,
             csv_log_path: None,
             json_log_path: None,
+            json_summary_log_path: None,
             text_log_path: None,
             show_result_scatter: false,
             show_latency_scatter: false,
+            show_bad_paths: false,
             latency_buckets: None,
+            metrics_address: None,
         };
 
         let ping_clients = new(&config, vec![], Arc::new(ManualResetEvent::new(false)));
@@ -101,13 +124,16 @@ mod tests {
             exit_failure_reason: None,
             csv_log_path: Some(PathBuf::from("tests_data/ping_result_factory_tests/log.csv")),
             json_log_path: Some(PathBuf::from("tests_data/ping_result_factory_tests/log.json")),
+            json_summary_log_path: Some(PathBuf::from("tests_data/ping_result_factory_tests/summary.json")),
             text_log_path: Some(PathBuf::from("tests_data/ping_result_factory_tests/log.txt")),
             show_result_scatter: true,
             show_latency_scatter: true,
+            show_bad_paths: true,
             latency_buckets: Some(vec![0.1, 0.5, 1.0, 10.0]),
+            metrics_address: Some("127.0.0.1:11383".parse().unwrap()),
         };
 
         let ping_clients = new(&config, vec![], Arc::new(ManualResetEvent::new(false)));
-        assert_eq!(7, ping_clients.len());
+        assert_eq!(10, ping_clients.len());
     }
 }
@@ -66,6 +66,7 @@ mod tests {
     use crate::PingResultDto;
     use chrono::{TimeZone, Utc};
     use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
     use std::io::BufReader;
 
     #[test]
@@ -102,6 +103,15 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: Some(200),
+                    ttfb_in_ms: Some(15f64),
+                    total_time_in_ms: Some(18f64),
+                    extra_details: vec![(String::from("TcpMss"), String::from("1460")), (String::from("TcpSynRetransmits"), String::from("0"))]
+                        .into_iter()
+                        .collect(),
+                    error_category: "".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -119,6 +129,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "Timeout".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -136,6 +153,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "connect aborted".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: Some(5f64),
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "ConnectionReset".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -153,6 +177,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "disconnect timeout".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -170,6 +201,13 @@ mod tests {
                     ping_error: "connect failed".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "ConnectionRefused".to_string(),
+                    is_reprobe: false,
                 },
                 PingResultDto {
                     utc_time: Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 11, 12),
@@ -187,6 +225,13 @@ mod tests {
                     ping_error: "".to_string(),
                     handshake_error: "".to_string(),
                     disconnect_error: "".to_string(),
+                    handshake_time_in_ms: None,
+                    status_code: None,
+                    ttfb_in_ms: None,
+                    total_time_in_ms: None,
+                    extra_details: BTreeMap::new(),
+                    error_category: "PreparationFailed".to_string(),
+                    is_reprobe: false,
                 },
             ],
             actual_logged_records,
@@ -0,0 +1,101 @@
+use crate::*;
+use std::sync::Arc;
+use std::{fs::File, io::prelude::*, path::PathBuf};
+use tracing;
+
+pub struct PingResultProcessorJsonSummaryLogger {
+    common_config: Arc<PingResultProcessorCommonConfig>,
+    log_path: PathBuf,
+    log_file: File,
+    statistics: PingRunStatistics,
+}
+
+impl PingResultProcessorJsonSummaryLogger {
+    #[tracing::instrument(name = "Creating ping result json summary logger", level = "debug")]
+    pub fn new(common_config: Arc<PingResultProcessorCommonConfig>, log_path_buf: &PathBuf) -> PingResultProcessorJsonSummaryLogger {
+        return PingResultProcessorJsonSummaryLogger {
+            common_config,
+            log_path: log_path_buf.clone(),
+            log_file: rnp_utils::create_log_file(log_path_buf),
+            statistics: PingRunStatistics::new(),
+        };
+    }
+}
+
+impl PingResultProcessor for PingResultProcessorJsonSummaryLogger {
+    fn name(&self) -> &'static str {
+        "JsonSummaryLogger"
+    }
+    fn config(&self) -> &PingResultProcessorCommonConfig {
+        self.common_config.as_ref()
+    }
+
+    fn process_ping_result(&mut self, ping_result: &PingResult) {
+        self.statistics.update(ping_result);
+    }
+
+    fn rundown(&mut self) {
+        let log_content = self.statistics.create_dto().to_json_lite();
+        self.log_file.write(log_content.as_bytes()).expect(&format!("Failed to write summary to json file! Path = {}", self.log_path.display()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ping_result_processors::ping_result_processor_test_common;
+    use crate::PingRunStatisticsDto;
+    use pretty_assertions::assert_eq;
+    use std::io::BufReader;
+
+    #[test]
+    fn ping_result_process_json_summary_logger_should_work() {
+        let test_log_file_path = "tests_data/ping_result_processor_json_summary_logger_tests/test_summary.json";
+        let mut processor: Box<dyn PingResultProcessor + Send + Sync> = Box::new(PingResultProcessorJsonSummaryLogger::new(
+            Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NO_OUTPUT }),
+            &PathBuf::from(test_log_file_path),
+        ));
+        ping_result_processor_test_common::run_ping_result_processor_with_test_samples(&mut processor);
+
+        let actual_summary: PingRunStatisticsDto;
+        {
+            let test_log_file = File::open(test_log_file_path).unwrap();
+            let test_log_reader = BufReader::new(test_log_file);
+            actual_summary = serde_json::from_reader(test_log_reader).unwrap();
+        }
+
+        let expected_statistics = PingResultStatisticsDto {
+            target: Some(String::from("1.2.3.4:443")),
+            source_ip: None,
+            ping_count: 4,
+            success_count: 2,
+            failure_count: 2,
+            handshake_failed_count: 1,
+            disconnect_failed_count: 1,
+            syn_retransmitted_count: 0,
+            latency_sample_count: 3,
+            min_latency_in_ms: 20.0,
+            max_latency_in_ms: 1000.0,
+            average_latency_in_ms: 346.667,
+            std_dev_latency_in_ms: 461.976,
+            jitter_in_ms: 490.0,
+            p50_latency_in_ms: 20.095,
+            p90_latency_in_ms: 1000.0,
+            p95_latency_in_ms: 1000.0,
+            p99_latency_in_ms: 1000.0,
+            p999_latency_in_ms: 1000.0,
+        };
+
+        assert_eq!(
+            PingRunStatisticsDto {
+                protocol: String::from("TCP"),
+                targets: vec![expected_statistics.clone()],
+                source_ips: vec![PingResultStatisticsDto { target: None, source_ip: Some(String::from("5.6.7.8")), ..expected_statistics.clone() }],
+                total: PingResultStatisticsDto { target: None, ..expected_statistics },
+                elapsed_time_in_ms: 1000.0,
+                achieved_ping_rate: 4.0,
+            },
+            actual_summary
+        );
+    }
+}
@@ -1,17 +1,85 @@
 use crate::*;
 use contracts::requires;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing;
 
-pub struct PingResultProcessorLatencyBucketLogger {
-    common_config: Arc<PingResultProcessorCommonConfig>,
-    buckets_in_us: Vec<u128>,
-
+struct LatencyBucketStatistics {
     total_hit_count: u32,
     bucket_hit_counts: Vec<u32>,
     timed_out_hit_count: u32,
     failed_hit_count: u32,
+    syn_retransmitted_hit_count: u32,
+}
+
+impl LatencyBucketStatistics {
+    fn new(bucket_count: usize) -> LatencyBucketStatistics {
+        return LatencyBucketStatistics {
+            total_hit_count: 0,
+            bucket_hit_counts: vec![0; bucket_count],
+            timed_out_hit_count: 0,
+            failed_hit_count: 0,
+            syn_retransmitted_hit_count: 0,
+        };
+    }
+
+    fn update(&mut self, buckets_in_us: &[u128], ping_result: &PingResult) {
+        self.total_hit_count += 1;
+
+        if ping_result.is_timed_out() {
+            self.timed_out_hit_count += 1;
+        } else if let Some(_) = ping_result.error() {
+            self.failed_hit_count += 1;
+        } else if ping_result.is_succeeded_after_retransmit() {
+            // The latency is mostly the SYN retransmit timer instead of the real path latency, so we count them separately.
+            self.syn_retransmitted_hit_count += 1;
+        } else {
+            self.track_latency_in_buckets(buckets_in_us, &ping_result.round_trip_time());
+        }
+    }
+
+    fn track_latency_in_buckets(&mut self, buckets_in_us: &[u128], latency: &Duration) {
+        // find the bucket from min to max
+        for (bucket_index, bucket_time_upper_bound_in_us) in buckets_in_us.iter().enumerate() {
+            if latency.as_micros() < *bucket_time_upper_bound_in_us {
+                self.bucket_hit_counts[bucket_index] += 1;
+                return;
+            }
+        }
+
+        unreachable!();
+    }
+
+    fn output_to_console(&self, buckets_in_us: &[u128]) {
+        println!("{:>15} | {}", "Latency Range", "Count");
+        println!("{:->17}------------ ", "+");
+
+        for (bucket_index, bucket_time_upper_bound_in_us) in buckets_in_us.iter().enumerate() {
+            let bucket_range = if bucket_index < buckets_in_us.len() - 1 {
+                format!("< {:.2}ms", *bucket_time_upper_bound_in_us as f64 / 1000.0)
+            } else {
+                format!(">= {:.2}ms", buckets_in_us[bucket_index - 1] as f64 / 1000.0)
+            };
+
+            println!("{:>15} | {}", bucket_range, self.bucket_hit_counts[bucket_index]);
+        }
+
+        println!("{:>15} | {}", "SYN Retransmit", self.syn_retransmitted_hit_count);
+        println!("{:>15} | {}", "Timed Out", self.timed_out_hit_count);
+        println!("{:>15} | {}", "Failed", self.failed_hit_count);
+        println!("{:->17}------------ ", "+");
+        println!("{:>15} | {}", "Total", self.total_hit_count);
+    }
+}
+
+pub struct PingResultProcessorLatencyBucketLogger {
+    common_config: Arc<PingResultProcessorCommonConfig>,
+    buckets_in_us: Vec<u128>,
+
+    total_statistics: LatencyBucketStatistics,
+    target_statistics: BTreeMap<SocketAddr, LatencyBucketStatistics>,
 }
 
 impl PingResultProcessorLatencyBucketLogger {
@@ -36,16 +104,14 @@ impl PingResultProcessorLatencyBucketLogger {
         return PingResultProcessorLatencyBucketLogger {
             common_config,
             buckets_in_us: normalized_buckets,
-            total_hit_count: 0,
-            bucket_hit_counts: vec![0; normalized_bucket_count],
-            timed_out_hit_count: 0,
-            failed_hit_count: 0,
+            total_statistics: LatencyBucketStatistics::new(normalized_bucket_count),
+            target_statistics: BTreeMap::new(),
         };
     }
 
     fn update_statistics(&mut self, ping_result: &PingResult) {
-        // Skip warmup pings in analysis.
-        if ping_result.is_warmup() {
+        // Skip warmup pings and re-probes in analysis, since re-probes only go to the failed source ports.
+        if ping_result.is_warmup() || ping_result.is_reprobe() {
             return;
         }
 
@@ -54,27 +120,13 @@ impl PingResultProcessorLatencyBucketLogger {
             return;
         }
 
-        self.total_hit_count += 1;
+        self.total_statistics.update(&self.buckets_in_us, ping_result);
 
-        if ping_result.is_timed_out() {
-            self.timed_out_hit_count += 1;
-        } else if let Some(_) = ping_result.error() {
-            self.failed_hit_count += 1;
-        } else {
-            self.track_latency_in_buckets(&ping_result.round_trip_time());
-        }
-    }
-
-    fn track_latency_in_buckets(&mut self, latency: &Duration) {
-        // find the bucket from min to max
-        for (bucket_index, bucket_time_upper_bound_in_us) in self.buckets_in_us.iter().enumerate() {
-            if latency.as_micros() < *bucket_time_upper_bound_in_us {
-                self.bucket_hit_counts[bucket_index] += 1;
-                return;
-            }
-        }
-
-        unreachable!();
+        let bucket_count = self.buckets_in_us.len();
+        self.target_statistics
+            .entry(ping_result.target())
+            .or_insert_with(|| LatencyBucketStatistics::new(bucket_count))
+            .update(&self.buckets_in_us, ping_result);
     }
 }
 
@@ -100,23 +152,19 @@ impl PingResultProcessor for PingResultProcessorLatencyBucketLogger {
         }
 
         println!("\n=== Latency buckets (in milliseconds) ===\n");
-        println!("{:>15} | {}", "Latency Range", "Count");
-        println!("{:->17}------------ ", "+");
 
-        for (bucket_index, bucket_time_upper_bound_in_us) in self.buckets_in_us.iter().enumerate() {
-            let bucket_range = if bucket_index < self.buckets_in_us.len() - 1 {
-                format!("< {:.2}ms", *bucket_time_upper_bound_in_us as f64 / 1000.0)
-            } else {
-                format!(">= {:.2}ms", self.buckets_in_us[bucket_index - 1] as f64 / 1000.0)
-            };
+        // With multiple targets, the buckets of each target are shown first, then the ones that combine all targets.
+        if self.target_statistics.len() > 1 {
+            for (target, statistics) in &self.target_statistics {
+                println!("--- Target: {:?} ---\n", target);
+                statistics.output_to_console(&self.buckets_in_us);
+                println!();
+            }
 
-            println!("{:>15} | {}", bucket_range, self.bucket_hit_counts[bucket_index]);
+            println!("--- All {} targets ---\n", self.target_statistics.len());
         }
 
-        println!("{:>15} | {}", "Timed Out", self.timed_out_hit_count);
-        println!("{:>15} | {}", "Failed", self.failed_hit_count);
-        println!("{:->17}------------ ", "+");
-        println!("{:>15} | {}", "Total", self.total_hit_count);
+        self.total_statistics.output_to_console(&self.buckets_in_us);
     }
 }
 
@@ -135,8 +183,22 @@ mod tests {
         );
         ping_results.iter().for_each(|x| logger.update_statistics(x));
 
-        assert_eq!(4, logger.total_hit_count);
-        assert_eq!(1, logger.timed_out_hit_count);
-        assert_eq!(1, logger.failed_hit_count);
+        assert_eq!(4, logger.total_statistics.total_hit_count);
+        assert_eq!(1, logger.total_statistics.timed_out_hit_count);
+        assert_eq!(1, logger.total_statistics.failed_hit_count);
+
+        let target_hit_count: u32 = logger.target_statistics.values().map(|x| x.total_hit_count).sum();
+        assert_eq!(4, target_hit_count);
+    }
+
+    #[test]
+    fn latency_bucket_logger_should_skip_reprobes() {
+        let mut logger = PingResultProcessorLatencyBucketLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: 0 }), &vec![0.1, 0.5]);
+        let ping_results = rnp_test_common::generate_ping_result_test_samples();
+        logger.update_statistics(&ping_results[1]);
+        logger.update_statistics(&rnp_test_common::generate_reprobe_ping_result_test_sample());
+
+        assert_eq!(1, logger.total_statistics.total_hit_count);
+        assert_eq!(1, logger.total_statistics.timed_out_hit_count);
     }
 }
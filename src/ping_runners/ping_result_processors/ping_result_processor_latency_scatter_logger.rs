@@ -1,5 +1,6 @@
 use crate::*;
 use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tracing;
 
@@ -12,15 +13,28 @@ struct LatencyHits {
     results: Vec<f64>,
 }
 
+type PingLatencyHistory = Vec<BTreeMap<usize, LatencyHits>>;
+
 pub struct PingResultProcessorLatencyScatterLogger {
     common_config: Arc<PingResultProcessorCommonConfig>,
-    ping_history: Vec<BTreeMap<usize, LatencyHits>>,
+    ping_history: PingLatencyHistory,
+    target_ping_histories: BTreeMap<SocketAddr, PingLatencyHistory>,
+    source_ip_ping_histories: BTreeMap<IpAddr, PingLatencyHistory>,
+
+    // Indexed by destination port instead of source port, for the target IPs that are pinged on multiple ports.
+    destination_port_ping_histories: BTreeMap<IpAddr, PingLatencyHistory>,
 }
 
 impl PingResultProcessorLatencyScatterLogger {
     #[tracing::instrument(name = "Creating ping result latency scatter logger", level = "debug")]
     pub fn new(common_config: Arc<PingResultProcessorCommonConfig>) -> PingResultProcessorLatencyScatterLogger {
-        return PingResultProcessorLatencyScatterLogger { common_config, ping_history: vec![BTreeMap::new()] };
+        return PingResultProcessorLatencyScatterLogger {
+            common_config,
+            ping_history: vec![BTreeMap::new()],
+            target_ping_histories: BTreeMap::new(),
+            source_ip_ping_histories: BTreeMap::new(),
+            destination_port_ping_histories: BTreeMap::new(),
+        };
     }
 
     fn get_ping_history_item_pos(&self, port: u32) -> (usize, usize) {
@@ -29,6 +43,56 @@ impl PingResultProcessorLatencyScatterLogger {
         return (row, col);
     }
 
+    fn update_ping_history(ping_history: &mut PingLatencyHistory, row: usize, col: usize, latency: Option<f64>) {
+        let bit_mask_bit = 1 << col;
+
+        // Find the last iteration and update the result.
+        loop {
+            let last_iteration = ping_history.last_mut().expect("Ping history should always be non-empty.");
+
+            let last_iteration_results = last_iteration.entry(row).or_insert(LatencyHits { bitmask: 0, results: vec![f64::NAN; COUNT_PER_ROW] });
+
+            // If the source port is already tested in the last iteration, it means a new iteration is started,
+            // hence create a new iteration and update there.
+            if last_iteration_results.bitmask & bit_mask_bit != 0 {
+                ping_history.push(BTreeMap::new());
+                continue;
+            }
+
+            last_iteration_results.bitmask |= bit_mask_bit;
+
+            if let Some(latency) = latency {
+                last_iteration_results.results[col] = latency;
+            }
+
+            break;
+        }
+    }
+
+    fn output_ping_history_to_console(ping_history: &PingLatencyHistory, port_title: &str) {
+        println!(
+            "{:>7} | {:>8} | {} (\"{}\" = Fail, \"{}\" = Not Tested)",
+            "Iter #",
+            format!("{} Port", port_title),
+            "Results",
+            SCATTER_SYMBOL_FAILED.trim(),
+            SCATTER_SYMBOL_NOT_TESTED.trim()
+        );
+        println!(
+            "{:->9}{:->11}-{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}",
+            "+", "+", 0, 1, 2, 3, 4, 5, 6, 7, 8, 9
+        );
+
+        for (iteration_index, iteration) in ping_history.iter().enumerate() {
+            for (port_bucket, latency_hits) in iteration {
+                print!("{:>7} | {:>8} | ", iteration_index, port_bucket);
+
+                let result = PingResultProcessorLatencyScatterLogger::convert_latency_hits_to_string(latency_hits);
+                println!("{}", result);
+            }
+        }
+    }
+
     fn convert_latency_hits_to_string(hits: &LatencyHits) -> String {
         let mut s: String = String::new();
 
@@ -66,8 +130,8 @@ impl PingResultProcessor for PingResultProcessorLatencyScatterLogger {
             return;
         }
 
-        // Skip warmup pings in analysis.
-        if ping_result.is_warmup() {
+        // Skip warmup pings and re-probes in analysis, since re-probes only go to the failed source ports.
+        if ping_result.is_warmup() || ping_result.is_reprobe() {
             return;
         }
 
@@ -76,30 +140,30 @@ impl PingResultProcessor for PingResultProcessorLatencyScatterLogger {
             return;
         }
 
-        let (row, col) = self.get_ping_history_item_pos(ping_result.source().port() as u32);
-        let bit_mask_bit = 1 << col;
-
-        // Find the last iteration and update the result.
-        loop {
-            let last_iteration = self.ping_history.last_mut().expect("Ping history should always be non-empty.");
+        let (row, col) = self.get_ping_history_item_pos(ping_result.configured_source().port() as u32);
+        let latency = match ping_result.error() {
+            None => Some(ping_result.round_trip_time().as_micros() as f64 / 1000.0),
+            Some(_) => None,
+        };
 
-            let last_iteration_results = last_iteration.entry(row).or_insert(LatencyHits { bitmask: 0, results: vec![f64::NAN; COUNT_PER_ROW] });
-
-            // If the source port is already tested in the last iteration, it means a new iteration is started,
-            // hence create a new iteration and update there.
-            if last_iteration_results.bitmask & bit_mask_bit != 0 {
-                self.ping_history.push(BTreeMap::new());
-                continue;
-            }
+        PingResultProcessorLatencyScatterLogger::update_ping_history(&mut self.ping_history, row, col, latency);
 
-            last_iteration_results.bitmask |= bit_mask_bit;
+        let target_ping_history = self.target_ping_histories.entry(ping_result.target()).or_insert_with(|| vec![BTreeMap::new()]);
+        PingResultProcessorLatencyScatterLogger::update_ping_history(target_ping_history, row, col, latency);
 
-            if let None = ping_result.error() {
-                last_iteration_results.results[col] = ping_result.round_trip_time().as_micros() as f64 / 1000.0;
-            }
+        let source_ip_ping_history =
+            self.source_ip_ping_histories.entry(ping_result.configured_source().ip()).or_insert_with(|| vec![BTreeMap::new()]);
+        PingResultProcessorLatencyScatterLogger::update_ping_history(source_ip_ping_history, row, col, latency);
 
-            break;
-        }
+        let (destination_port_row, destination_port_col) = self.get_ping_history_item_pos(ping_result.target().port() as u32);
+        let destination_port_ping_history =
+            self.destination_port_ping_histories.entry(ping_result.target().ip()).or_insert_with(|| vec![BTreeMap::new()]);
+        PingResultProcessorLatencyScatterLogger::update_ping_history(
+            destination_port_ping_history,
+            destination_port_row,
+            destination_port_col,
+            latency,
+        );
     }
 
     fn rundown(&mut self) {
@@ -109,25 +173,43 @@ impl PingResultProcessor for PingResultProcessorLatencyScatterLogger {
 
         println!("\n=== Latency scatter map (in milliseconds) ===\n");
 
-        println!(
-            "{:>7} | {:>8} | {} (\"{}\" = Fail, \"{}\" = Not Tested)",
-            "Iter #",
-            "Src Port",
-            "Results",
-            SCATTER_SYMBOL_FAILED.trim(),
-            SCATTER_SYMBOL_NOT_TESTED.trim()
-        );
-        println!(
-            "{:->9}{:->11}-{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}{:-^9.2}",
-            "+", "+", 0, 1, 2, 3, 4, 5, 6, 7, 8, 9
-        );
+        // With multiple targets or source IPs, the scatter map of each of them is shown first, then the one that combines
+        // all pings.
+        let target_count = self.target_ping_histories.len();
+        if target_count > 1 {
+            for (target, target_ping_history) in &self.target_ping_histories {
+                println!("--- Target: {:?} ---\n", target);
+                PingResultProcessorLatencyScatterLogger::output_ping_history_to_console(target_ping_history, "Src");
+                println!();
+            }
+        }
 
-        for (iteration_index, iteration) in self.ping_history.iter().enumerate() {
-            for (port_bucket, latency_hits) in iteration {
-                print!("{:>7} | {:>8} | ", iteration_index, port_bucket);
+        let source_ip_count = self.source_ip_ping_histories.len();
+        if source_ip_count > 1 {
+            for (source_ip, source_ip_ping_history) in &self.source_ip_ping_histories {
+                println!("--- Source IP: {} ---\n", source_ip);
+                PingResultProcessorLatencyScatterLogger::output_ping_history_to_console(source_ip_ping_history, "Src");
+                println!();
+            }
+        }
 
-                let result = PingResultProcessorLatencyScatterLogger::convert_latency_hits_to_string(latency_hits);
-                println!("{}", result);
+        let total_title = match (target_count, source_ip_count) {
+            (0..=1, 0..=1) => None,
+            (_, 0..=1) => Some(format!("All {} targets", target_count)),
+            (0..=1, _) => Some(format!("All {} source IPs", source_ip_count)),
+            _ => Some(format!("All {} targets from all {} source IPs", target_count, source_ip_count)),
+        };
+        if let Some(total_title) = total_title {
+            println!("--- {} ---\n", total_title);
+        }
+
+        PingResultProcessorLatencyScatterLogger::output_ping_history_to_console(&self.ping_history, "Src");
+
+        // The destination ports are shown in the same way as the source ports, for the target IPs pinged on multiple ports.
+        for (target_ip, destination_port_ping_history) in &self.destination_port_ping_histories {
+            if self.target_ping_histories.keys().filter(|target| target.ip() == *target_ip).count() > 1 {
+                println!("\n--- Destination ports of {} ---\n", target_ip);
+                PingResultProcessorLatencyScatterLogger::output_ping_history_to_console(destination_port_ping_history, "Dst");
             }
         }
     }
@@ -136,6 +218,7 @@ impl PingResultProcessor for PingResultProcessorLatencyScatterLogger {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rnp_test_common;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -158,4 +241,16 @@ mod tests {
             formatted_results
         );
     }
+
+    #[test]
+    fn latency_scatter_logger_should_skip_reprobes() {
+        let mut logger = PingResultProcessorLatencyScatterLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: 0 }));
+        let ping_results = rnp_test_common::generate_ping_result_test_samples();
+        logger.process_ping_result(&ping_results[1]);
+        logger.process_ping_result(&rnp_test_common::generate_reprobe_ping_result_test_sample());
+
+        // The re-probe would start a new iteration on the same source port, if it was counted as a normal ping.
+        assert_eq!(1, logger.ping_history.len());
+        assert_eq!(0b1, logger.ping_history[0][&8080].bitmask);
+    }
 }
@@ -0,0 +1,334 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing;
+
+// Latency histogram buckets in seconds, which covers the typical latency from the same datacenter to cross continents.
+const PROMETHEUS_LATENCY_BUCKETS_IN_SECS: [f64; 12] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+// Scrape requests are tiny, anything larger than this is not a request we want to handle.
+const PROMETHEUS_MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct PingMetricsKey {
+    protocol: String,
+    target: SocketAddr,
+    source_ip: IpAddr,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PingMetrics {
+    sent_count: u64,
+    succeeded_count: u64,
+    failed_count: u64,
+    timed_out_count: u64,
+    handshake_failed_count: u64,
+    disconnect_failed_count: u64,
+    latency_bucket_counts: [u64; PROMETHEUS_LATENCY_BUCKETS_IN_SECS.len()],
+    latency_sum_in_secs: f64,
+    latency_count: u64,
+}
+
+type PingMetricsMap = BTreeMap<PingMetricsKey, PingMetrics>;
+
+// Counter name, help message and the function to get the counter value.
+type PingMetricsCounter = (&'static str, &'static str, fn(&PingMetrics) -> u64);
+
+pub struct PingResultProcessorPrometheusExporter {
+    common_config: Arc<PingResultProcessorCommonConfig>,
+    listen_address: SocketAddr,
+    listener: Option<std::net::TcpListener>,
+    server_join_handle: Option<JoinHandle<()>>,
+    metrics: Arc<Mutex<PingMetricsMap>>,
+}
+
+impl PingResultProcessorPrometheusExporter {
+    #[tracing::instrument(name = "Creating ping result prometheus exporter", level = "debug")]
+    pub fn new(common_config: Arc<PingResultProcessorCommonConfig>, listen_address: &SocketAddr) -> PingResultProcessorPrometheusExporter {
+        // Bind the address as early as possible, so we fail before any ping is sent, if the address is not available.
+        let listener = match std::net::TcpListener::bind(listen_address) {
+            Err(e) => panic!("Failed to start metrics endpoint on {}: {}", listen_address, e),
+            Ok(listener) => listener,
+        };
+
+        return PingResultProcessorPrometheusExporter {
+            common_config,
+            listen_address: *listen_address,
+            listener: Some(listener),
+            server_join_handle: None,
+            metrics: Arc::new(Mutex::new(BTreeMap::new())),
+        };
+    }
+
+    fn update_metrics(&mut self, ping_result: &PingResult) {
+        // Skip warmup pings and re-probes in analysis, since re-probes only go to the failed source ports.
+        if ping_result.is_warmup() || ping_result.is_reprobe() {
+            return;
+        }
+
+        // Skip preparation errors in analysis, since it is not a remote issue.
+        if ping_result.is_preparation_error() {
+            return;
+        }
+
+        let key = PingMetricsKey {
+            protocol: ping_result.protocol().to_string(),
+            target: ping_result.target(),
+            source_ip: ping_result.configured_source().ip(),
+        };
+
+        let mut metrics_map = self.metrics.lock().unwrap();
+        let metrics = metrics_map.entry(key).or_default();
+        metrics.sent_count += 1;
+
+        if ping_result.is_timed_out() {
+            metrics.timed_out_count += 1;
+        }
+
+        if !ping_result.is_succeeded() {
+            metrics.failed_count += 1;
+            return;
+        }
+
+        metrics.succeeded_count += 1;
+        match ping_result.warning() {
+            Some(PingClientWarning::AppHandshakeFailed(_)) => metrics.handshake_failed_count += 1,
+            Some(PingClientWarning::DisconnectFailed(_)) => metrics.disconnect_failed_count += 1,
+            None => (),
+        }
+
+        // Only the latency of succeeded pings is tracked, otherwise timeouts will be mixed into the latency histogram.
+        let latency_in_secs = ping_result.round_trip_time().as_secs_f64();
+        for (bucket_index, bucket_upper_bound) in PROMETHEUS_LATENCY_BUCKETS_IN_SECS.iter().enumerate() {
+            if latency_in_secs <= *bucket_upper_bound {
+                metrics.latency_bucket_counts[bucket_index] += 1;
+            }
+        }
+        metrics.latency_sum_in_secs += latency_in_secs;
+        metrics.latency_count += 1;
+    }
+
+    fn format_metrics(metrics_map: &PingMetricsMap) -> String {
+        let mut output = String::new();
+
+        let counters: [PingMetricsCounter; 6] = [
+            ("rnp_pings_total", "Total number of pings sent.", |m| m.sent_count),
+            ("rnp_pings_succeeded_total", "Total number of pings succeeded.", |m| m.succeeded_count),
+            ("rnp_pings_failed_total", "Total number of pings failed, including timed out ones.", |m| m.failed_count),
+            ("rnp_pings_timed_out_total", "Total number of pings timed out.", |m| m.timed_out_count),
+            ("rnp_pings_handshake_failed_total", "Total number of pings succeeded, but failed in app handshake.", |m| m.handshake_failed_count),
+            ("rnp_pings_disconnect_failed_total", "Total number of pings succeeded, but failed to disconnect.", |m| m.disconnect_failed_count),
+        ];
+
+        for (name, help, get_value) in counters.iter() {
+            let _ = writeln!(output, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+            for (key, metrics) in metrics_map {
+                let _ = writeln!(output, "{}{{{}}} {}", name, PingResultProcessorPrometheusExporter::format_labels(key), get_value(metrics));
+            }
+        }
+
+        let name = "rnp_ping_latency_seconds";
+        let _ = writeln!(output, "# HELP {} Round trip time of succeeded pings.\n# TYPE {} histogram", name, name);
+        for (key, metrics) in metrics_map {
+            let labels = PingResultProcessorPrometheusExporter::format_labels(key);
+            for (bucket_index, bucket_upper_bound) in PROMETHEUS_LATENCY_BUCKETS_IN_SECS.iter().enumerate() {
+                let _ =
+                    writeln!(output, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bucket_upper_bound, metrics.latency_bucket_counts[bucket_index]);
+            }
+            let _ = writeln!(output, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, metrics.latency_count);
+            let _ = writeln!(output, "{}_sum{{{}}} {}", name, labels, metrics.latency_sum_in_secs);
+            let _ = writeln!(output, "{}_count{{{}}} {}", name, labels, metrics.latency_count);
+        }
+
+        return output;
+    }
+
+    fn format_labels(key: &PingMetricsKey) -> String {
+        return format!(
+            "protocol=\"{}\",target=\"{}\",source_ip=\"{}\"",
+            PingResultProcessorPrometheusExporter::escape_label_value(&key.protocol),
+            key.target,
+            key.source_ip
+        );
+    }
+
+    fn escape_label_value(value: &str) -> String {
+        return value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    }
+
+    #[tracing::instrument(name = "Running metrics endpoint", level = "debug", skip(listener, metrics))]
+    async fn run_server(listen_address: SocketAddr, listener: TcpListener, metrics: Arc<Mutex<PingMetricsMap>>) {
+        loop {
+            let (connection, remote_address) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Failed to accept metrics scraping connection: {}", e);
+                    continue;
+                }
+            };
+
+            let metrics = metrics.clone();
+            tokio::spawn(async move {
+                if let Err(e) = PingResultProcessorPrometheusExporter::handle_request(connection, metrics).await {
+                    tracing::debug!("Failed to handle metrics scraping request: remote = {}, error = {}", remote_address, e);
+                }
+            });
+        }
+    }
+
+    async fn handle_request(mut connection: TcpStream, metrics: Arc<Mutex<PingMetricsMap>>) -> io::Result<()> {
+        let mut request = Vec::new();
+        let mut read_buffer = vec![0 as u8; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            let read_size = connection.read(&mut read_buffer[..]).await?;
+            if read_size == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection is closed before request is received."));
+            }
+
+            request.extend_from_slice(&read_buffer[..read_size]);
+            if request.len() > PROMETHEUS_MAX_REQUEST_SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Request is too large."));
+            }
+        }
+
+        let request = String::from_utf8_lossy(&request).to_string();
+        let mut request_line_parts = request.lines().next().unwrap_or("").split_whitespace();
+        let (status, content_type, body) = match (request_line_parts.next(), request_line_parts.next()) {
+            (Some("GET"), Some("/metrics")) => {
+                let body = PingResultProcessorPrometheusExporter::format_metrics(&metrics.lock().unwrap());
+                ("200 OK", "text/plain; version=0.0.4", body)
+            }
+            _ => ("404 Not Found", "text/plain", String::from("Not found. Metrics are served at /metrics.\n")),
+        };
+
+        let response =
+            format!("HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, content_type, body.len(), body);
+        connection.write_all(response.as_bytes()).await?;
+        connection.shutdown().await?;
+
+        return Ok(());
+    }
+}
+
+impl PingResultProcessor for PingResultProcessorPrometheusExporter {
+    fn name(&self) -> &'static str {
+        "PrometheusExporter"
+    }
+    fn config(&self) -> &PingResultProcessorCommonConfig {
+        self.common_config.as_ref()
+    }
+
+    fn initialize(&mut self) {
+        let listener = self.listener.take().unwrap();
+        listener.set_nonblocking(true).unwrap_or_else(|e| panic!("Failed to start metrics endpoint on {}: {}", self.listen_address, e));
+        let listener =
+            TcpListener::from_std(listener).unwrap_or_else(|e| panic!("Failed to start metrics endpoint on {}: {}", self.listen_address, e));
+
+        tracing::debug!("Metrics endpoint started: address = {}", self.listen_address);
+        self.server_join_handle =
+            Some(tokio::spawn(PingResultProcessorPrometheusExporter::run_server(self.listen_address, listener, self.metrics.clone())));
+    }
+
+    fn process_ping_result(&mut self, ping_result: &PingResult) {
+        self.update_metrics(ping_result);
+    }
+
+    fn rundown(&mut self) {
+        if let Some(server_join_handle) = self.server_join_handle.take() {
+            server_join_handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnp_test_common;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn prometheus_exporter_should_format_metrics() {
+        let mut exporter = PingResultProcessorPrometheusExporter::new(
+            Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NO_OUTPUT }),
+            &"127.0.0.1:11381".parse().unwrap(),
+        );
+        rnp_test_common::generate_ping_result_test_samples().iter().for_each(|x| exporter.update_metrics(x));
+
+        let metrics = PingResultProcessorPrometheusExporter::format_metrics(&exporter.metrics.lock().unwrap());
+        let labels = "protocol=\"TCP\",target=\"1.2.3.4:443\",source_ip=\"5.6.7.8\"";
+        for expected_line in [
+            "# TYPE rnp_pings_total counter".to_string(),
+            format!("rnp_pings_total{{{}}} 4", labels),
+            format!("rnp_pings_succeeded_total{{{}}} 2", labels),
+            format!("rnp_pings_failed_total{{{}}} 2", labels),
+            format!("rnp_pings_timed_out_total{{{}}} 1", labels),
+            format!("rnp_pings_handshake_failed_total{{{}}} 1", labels),
+            format!("rnp_pings_disconnect_failed_total{{{}}} 1", labels),
+            "# TYPE rnp_ping_latency_seconds histogram".to_string(),
+            format!("rnp_ping_latency_seconds_bucket{{{},le=\"0.01\"}} 0", labels),
+            format!("rnp_ping_latency_seconds_bucket{{{},le=\"0.025\"}} 2", labels),
+            format!("rnp_ping_latency_seconds_bucket{{{},le=\"+Inf\"}} 2", labels),
+            format!("rnp_ping_latency_seconds_sum{{{}}} 0.04", labels),
+            format!("rnp_ping_latency_seconds_count{{{}}} 2", labels),
+        ] {
+            assert!(metrics.lines().any(|line| line == expected_line), "Line not found: {}\n{}", expected_line, metrics);
+        }
+    }
+
+    #[test]
+    fn prometheus_exporter_should_skip_reprobes() {
+        let mut exporter = PingResultProcessorPrometheusExporter::new(
+            Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NO_OUTPUT }),
+            &"127.0.0.1:11383".parse().unwrap(),
+        );
+        let ping_results = rnp_test_common::generate_ping_result_test_samples();
+        exporter.update_metrics(&ping_results[1]);
+        exporter.update_metrics(&rnp_test_common::generate_reprobe_ping_result_test_sample());
+
+        let metrics = PingResultProcessorPrometheusExporter::format_metrics(&exporter.metrics.lock().unwrap());
+        let labels = "protocol=\"TCP\",target=\"1.2.3.4:443\",source_ip=\"5.6.7.8\"";
+        for expected_line in [format!("rnp_pings_total{{{}}} 1", labels), format!("rnp_pings_timed_out_total{{{}}} 1", labels)] {
+            assert!(metrics.lines().any(|line| line == expected_line), "Line not found: {}\n{}", expected_line, metrics);
+        }
+    }
+
+    #[test]
+    fn prometheus_exporter_should_serve_metrics() {
+        let rt = Runtime::new().unwrap();
+        let listen_address: SocketAddr = "127.0.0.1:11382".parse().unwrap();
+
+        let (metrics_response, not_found_response) = rt.block_on(async move {
+            let mut exporter: Box<dyn PingResultProcessor + Send + Sync> = Box::new(PingResultProcessorPrometheusExporter::new(
+                Arc::new(PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NO_OUTPUT }),
+                &listen_address,
+            ));
+            exporter.initialize();
+            rnp_test_common::generate_ping_result_test_samples().iter().for_each(|x| exporter.process_ping_result(x));
+
+            let metrics_response = send_http_request(&listen_address, "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+            let not_found_response = send_http_request(&listen_address, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+            exporter.rundown();
+
+            (metrics_response, not_found_response)
+        });
+
+        assert!(metrics_response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(metrics_response.contains("\r\n\r\n# HELP rnp_pings_total Total number of pings sent.\n"));
+        assert!(not_found_response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    async fn send_http_request(address: &SocketAddr, request: &str) -> String {
+        let mut connection = TcpStream::connect(address).await.unwrap();
+        connection.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        connection.read_to_string(&mut response).await.unwrap();
+        return response;
+    }
+}
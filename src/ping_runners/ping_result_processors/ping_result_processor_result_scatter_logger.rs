@@ -1,5 +1,6 @@
 use crate::*;
 use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tracing;
 
@@ -10,16 +11,30 @@ const SCATTER_SYMBOL_FAILED: char = 'X';
 const SCATTER_SYMBOL_PREPARE_FAILED: char = '-';
 const SCATTER_SYMBOL_HANDSHAKE_FAILED: char = 'H';
 const SCATTER_SYMBOL_DISCONNECT_FAILED: char = 'D';
+const SCATTER_SYMBOL_SYN_RETRANSMITTED: char = 'R';
+
+type PingResultHistory = Vec<BTreeMap<u32, Vec<char>>>;
 
 pub struct PingResultProcessorResultScatterLogger {
     common_config: Arc<PingResultProcessorCommonConfig>,
-    ping_history: Vec<BTreeMap<u32, Vec<char>>>,
+    ping_history: PingResultHistory,
+    target_ping_histories: BTreeMap<SocketAddr, PingResultHistory>,
+    source_ip_ping_histories: BTreeMap<IpAddr, PingResultHistory>,
+
+    // Indexed by destination port instead of source port, for the target IPs that are pinged on multiple ports.
+    destination_port_ping_histories: BTreeMap<IpAddr, PingResultHistory>,
 }
 
 impl PingResultProcessorResultScatterLogger {
     #[tracing::instrument(name = "Creating ping result result scatter logger", level = "debug")]
     pub fn new(common_config: Arc<PingResultProcessorCommonConfig>) -> PingResultProcessorResultScatterLogger {
-        return PingResultProcessorResultScatterLogger { common_config, ping_history: vec![BTreeMap::new()] };
+        return PingResultProcessorResultScatterLogger {
+            common_config,
+            ping_history: vec![BTreeMap::new()],
+            target_ping_histories: BTreeMap::new(),
+            source_ip_ping_histories: BTreeMap::new(),
+            destination_port_ping_histories: BTreeMap::new(),
+        };
     }
 
     fn get_ping_history_position(&self, port: u32) -> (u32, usize) {
@@ -28,6 +43,41 @@ impl PingResultProcessorResultScatterLogger {
         return (row, index as usize);
     }
 
+    fn update_ping_history(ping_history: &mut PingResultHistory, row: u32, index: usize, result: char) {
+        // Find the last iteration and update the result.
+        loop {
+            let last_iteration = ping_history.last_mut().expect("Ping history should always be non-empty.");
+
+            let last_iteration_results = last_iteration.entry(row).or_insert(vec![SCATTER_SYMBOL_NOT_TESTED_YET; COUNT_PER_ROW as usize]);
+
+            // If the source port is already tested in the last iteration, it means a new iteration is started,
+            // hence create a new iteration and update there.
+            if last_iteration_results[index] != SCATTER_SYMBOL_NOT_TESTED_YET {
+                ping_history.push(BTreeMap::new());
+                continue;
+            }
+
+            last_iteration_results[index] = result;
+
+            break;
+        }
+    }
+
+    fn output_ping_history_to_console(ping_history: &PingResultHistory, port_title: &str) {
+        println!("\n{:>5} | {:>5} | {}", "Iter", port_title, "Results");
+        println!("{:>5} | {:>5} | ", "#", "Port");
+        println!("{:->6}|{:->8}-0---4-5---9-0---4-5---9-", "", "+");
+
+        for (iteration_index, iteration) in ping_history.iter().enumerate() {
+            for (port_bucket, result_hits) in iteration {
+                print!("{:>5} | {:>5} | ", iteration_index, port_bucket);
+
+                let result = PingResultProcessorResultScatterLogger::convert_result_hits_to_string(result_hits);
+                println!("{}", result);
+            }
+        }
+    }
+
     fn convert_result_hits_to_string(hits: &Vec<char>) -> String {
         let mut s: String = String::new();
 
@@ -56,12 +106,12 @@ impl PingResultProcessor for PingResultProcessorResultScatterLogger {
             return;
         }
 
-        // Skip warmup pings in analysis.
-        if ping_result.is_warmup() {
+        // Skip warmup pings and re-probes in analysis, since re-probes only go to the failed source ports.
+        if ping_result.is_warmup() || ping_result.is_reprobe() {
             return;
         }
 
-        let (row, index) = self.get_ping_history_position(ping_result.source().port() as u32);
+        let (row, index) = self.get_ping_history_position(ping_result.configured_source().port() as u32);
         let result = if let Some(e) = ping_result.error() {
             match e {
                 PingClientError::PreparationFailed(_) => SCATTER_SYMBOL_PREPARE_FAILED,
@@ -72,27 +122,30 @@ impl PingResultProcessor for PingResultProcessorResultScatterLogger {
                 PingClientWarning::AppHandshakeFailed(_) => SCATTER_SYMBOL_HANDSHAKE_FAILED,
                 PingClientWarning::DisconnectFailed(_) => SCATTER_SYMBOL_DISCONNECT_FAILED,
             }
+        } else if ping_result.is_succeeded_after_retransmit() {
+            SCATTER_SYMBOL_SYN_RETRANSMITTED
         } else {
             SCATTER_SYMBOL_PASSED
         };
 
-        // Find the last iteration and update the result.
-        loop {
-            let last_iteration = self.ping_history.last_mut().expect("Ping history should always be non-empty.");
-
-            let last_iteration_results = last_iteration.entry(row).or_insert(vec![SCATTER_SYMBOL_NOT_TESTED_YET; COUNT_PER_ROW as usize]);
+        PingResultProcessorResultScatterLogger::update_ping_history(&mut self.ping_history, row, index, result);
 
-            // If the source port is already tested in the last iteration, it means a new iteration is started,
-            // hence create a new iteration and update there.
-            if last_iteration_results[index] != SCATTER_SYMBOL_NOT_TESTED_YET {
-                self.ping_history.push(BTreeMap::new());
-                continue;
-            }
+        let target_ping_history = self.target_ping_histories.entry(ping_result.target()).or_insert_with(|| vec![BTreeMap::new()]);
+        PingResultProcessorResultScatterLogger::update_ping_history(target_ping_history, row, index, result);
 
-            last_iteration_results[index] = result;
+        let source_ip_ping_history =
+            self.source_ip_ping_histories.entry(ping_result.configured_source().ip()).or_insert_with(|| vec![BTreeMap::new()]);
+        PingResultProcessorResultScatterLogger::update_ping_history(source_ip_ping_history, row, index, result);
 
-            break;
-        }
+        let (destination_port_row, destination_port_index) = self.get_ping_history_position(ping_result.target().port() as u32);
+        let destination_port_ping_history =
+            self.destination_port_ping_histories.entry(ping_result.target().ip()).or_insert_with(|| vec![BTreeMap::new()]);
+        PingResultProcessorResultScatterLogger::update_ping_history(
+            destination_port_ping_history,
+            destination_port_row,
+            destination_port_index,
+            result,
+        );
     }
 
     fn rundown(&mut self) {
@@ -102,20 +155,51 @@ impl PingResultProcessor for PingResultProcessorResultScatterLogger {
 
         println!("\n=== Ping result scatter map ===");
         println!(
-            "(\"{}\" = Ok, \"{}\" = Fail, \"{}\" = Not tested yet, \"{}\" = Preparation failed, \"{}\" = App handshake failed, \"{}\" = Disconnect failed)",
-            SCATTER_SYMBOL_PASSED, SCATTER_SYMBOL_FAILED, SCATTER_SYMBOL_NOT_TESTED_YET, SCATTER_SYMBOL_PREPARE_FAILED, SCATTER_SYMBOL_HANDSHAKE_FAILED, SCATTER_SYMBOL_DISCONNECT_FAILED
+            "(\"{}\" = Ok, \"{}\" = Fail, \"{}\" = Not tested yet, \"{}\" = Preparation failed, \"{}\" = App handshake failed, \"{}\" = Disconnect failed, \"{}\" = Ok after SYN retransmit)",
+            SCATTER_SYMBOL_PASSED,
+            SCATTER_SYMBOL_FAILED,
+            SCATTER_SYMBOL_NOT_TESTED_YET,
+            SCATTER_SYMBOL_PREPARE_FAILED,
+            SCATTER_SYMBOL_HANDSHAKE_FAILED,
+            SCATTER_SYMBOL_DISCONNECT_FAILED,
+            SCATTER_SYMBOL_SYN_RETRANSMITTED
         );
 
-        println!("\n{:>5} | {:>5} | {}", "Iter", "Src", "Results");
-        println!("{:>5} | {:>5} | ", "#", "Port");
-        println!("{:->6}|{:->8}-0---4-5---9-0---4-5---9-", "", "+");
+        // With multiple targets or source IPs, the scatter map of each of them is shown first, then the one that combines
+        // all pings.
+        let target_count = self.target_ping_histories.len();
+        if target_count > 1 {
+            for (target, target_ping_history) in &self.target_ping_histories {
+                println!("\n--- Target: {:?} ---", target);
+                PingResultProcessorResultScatterLogger::output_ping_history_to_console(target_ping_history, "Src");
+            }
+        }
 
-        for (iteration_index, iteration) in self.ping_history.iter().enumerate() {
-            for (port_bucket, result_hits) in iteration {
-                print!("{:>5} | {:>5} | ", iteration_index, port_bucket);
+        let source_ip_count = self.source_ip_ping_histories.len();
+        if source_ip_count > 1 {
+            for (source_ip, source_ip_ping_history) in &self.source_ip_ping_histories {
+                println!("\n--- Source IP: {} ---", source_ip);
+                PingResultProcessorResultScatterLogger::output_ping_history_to_console(source_ip_ping_history, "Src");
+            }
+        }
 
-                let result = PingResultProcessorResultScatterLogger::convert_result_hits_to_string(result_hits);
-                println!("{}", result);
+        let total_title = match (target_count, source_ip_count) {
+            (0..=1, 0..=1) => None,
+            (_, 0..=1) => Some(format!("All {} targets", target_count)),
+            (0..=1, _) => Some(format!("All {} source IPs", source_ip_count)),
+            _ => Some(format!("All {} targets from all {} source IPs", target_count, source_ip_count)),
+        };
+        if let Some(total_title) = total_title {
+            println!("\n--- {} ---", total_title);
+        }
+
+        PingResultProcessorResultScatterLogger::output_ping_history_to_console(&self.ping_history, "Src");
+
+        // The destination ports are shown in the same way as the source ports, for the target IPs pinged on multiple ports.
+        for (target_ip, destination_port_ping_history) in &self.destination_port_ping_histories {
+            if self.target_ping_histories.keys().filter(|target| target.ip() == *target_ip).count() > 1 {
+                println!("\n--- Destination ports of {} ---", target_ip);
+                PingResultProcessorResultScatterLogger::output_ping_history_to_console(destination_port_ping_history, "Dst");
             }
         }
     }
@@ -124,6 +208,7 @@ impl PingResultProcessor for PingResultProcessorResultScatterLogger {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rnp_test_common;
 
     #[test]
     fn convert_result_info_to_string_should_work() {
@@ -141,4 +226,15 @@ mod tests {
 
         assert_eq!(vec!["..... ..... ..... .....", "O.... ..... ..... .....", ".X-HD ..... ..... .....",], formatted_results);
     }
+
+    #[test]
+    fn result_scatter_logger_should_skip_reprobes() {
+        let mut logger = PingResultProcessorResultScatterLogger::new(Arc::new(PingResultProcessorCommonConfig { quiet_level: 0 }));
+        let ping_results = rnp_test_common::generate_ping_result_test_samples();
+        logger.process_ping_result(&ping_results[1]);
+        logger.process_ping_result(&rnp_test_common::generate_reprobe_ping_result_test_sample());
+
+        // The re-probe would start a new iteration on the same source port, if it was counted as a normal ping.
+        assert_eq!(1, logger.ping_history.len());
+    }
 }
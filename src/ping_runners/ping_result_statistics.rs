@@ -0,0 +1,356 @@
+use crate::*;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct PingResultStatistics {
+    pub ping_count: u32,
+    pub success_count: u32,
+    pub failure_count: u32,
+    pub handshake_failed_count: u32,
+    pub disconnect_failed_count: u32,
+
+    // Pings succeeded only after SYN retransmit, which are also counted in success count.
+    pub syn_retransmitted_count: u32,
+
+    // Failures and app handshake failures, grouped by error category.
+    pub error_category_counts: BTreeMap<PingErrorCategory, u32>,
+    pub latency_histogram: PingLatencyHistogram,
+}
+
+impl PingResultStatistics {
+    pub fn new() -> PingResultStatistics {
+        return PingResultStatistics {
+            ping_count: 0,
+            success_count: 0,
+            failure_count: 0,
+            handshake_failed_count: 0,
+            disconnect_failed_count: 0,
+            syn_retransmitted_count: 0,
+            error_category_counts: BTreeMap::new(),
+            latency_histogram: PingLatencyHistogram::new(),
+        };
+    }
+
+    pub fn update(&mut self, ping_result: &PingResult) {
+        self.ping_count += 1;
+        if ping_result.is_succeeded() {
+            self.success_count += 1;
+
+            if ping_result.is_succeeded_after_retransmit() {
+                self.syn_retransmitted_count += 1;
+            }
+        } else {
+            self.failure_count += 1;
+        }
+
+        if let Some(error_category) = PingErrorCategory::from_ping_result(ping_result) {
+            *self.error_category_counts.entry(error_category).or_insert(0) += 1;
+        }
+
+        if let Some(warning) = ping_result.warning() {
+            match warning {
+                PingClientWarning::AppHandshakeFailed(_) => self.handshake_failed_count += 1,
+                PingClientWarning::DisconnectFailed(_) => self.disconnect_failed_count += 1,
+            }
+        };
+
+        let latency = ping_result.round_trip_time();
+        if latency.as_micros() == 0 {
+            // Latency data not set.
+            return;
+        }
+
+        self.latency_histogram.record(&latency);
+    }
+
+    pub fn latency_summary(&self) -> PingLatencySummary {
+        return self.latency_histogram.summary();
+    }
+
+    pub fn create_dto(&self, target: Option<SocketAddr>) -> PingResultStatisticsDto {
+        let latency_summary = self.latency_summary();
+        return PingResultStatisticsDto {
+            target: target.map(|target| target.to_string()),
+            source_ip: None,
+            ping_count: self.ping_count,
+            success_count: self.success_count,
+            failure_count: self.failure_count,
+            handshake_failed_count: self.handshake_failed_count,
+            disconnect_failed_count: self.disconnect_failed_count,
+            syn_retransmitted_count: self.syn_retransmitted_count,
+            latency_sample_count: latency_summary.sample_count,
+            min_latency_in_ms: latency_summary.min.as_secs_f64() * 1000.0,
+            max_latency_in_ms: latency_summary.max.as_secs_f64() * 1000.0,
+            average_latency_in_ms: latency_summary.average.as_secs_f64() * 1000.0,
+            std_dev_latency_in_ms: latency_summary.std_dev.as_secs_f64() * 1000.0,
+            jitter_in_ms: latency_summary.jitter.as_secs_f64() * 1000.0,
+            p50_latency_in_ms: latency_summary.p50.as_secs_f64() * 1000.0,
+            p90_latency_in_ms: latency_summary.p90.as_secs_f64() * 1000.0,
+            p95_latency_in_ms: latency_summary.p95.as_secs_f64() * 1000.0,
+            p99_latency_in_ms: latency_summary.p99.as_secs_f64() * 1000.0,
+            p999_latency_in_ms: latency_summary.p999.as_secs_f64() * 1000.0,
+        };
+    }
+}
+
+impl Default for PingResultStatistics {
+    fn default() -> Self {
+        PingResultStatistics::new()
+    }
+}
+
+/// Outcome of the re-probes of a failed ping, which are sent with the same target and source port. If none of them
+/// succeeded, the failure is persistent, which usually means a bad ECMP path. Otherwise, it is transient, e.g. random loss.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PingReprobeOutcome {
+    pub reprobe_count: u32,
+    pub success_count: u32,
+}
+
+impl PingReprobeOutcome {
+    pub fn update(&mut self, ping_result: &PingResult) {
+        self.reprobe_count += 1;
+        if ping_result.is_succeeded() {
+            self.success_count += 1;
+        }
+    }
+
+    pub fn is_persistent_failure(&self) -> bool {
+        self.success_count == 0
+    }
+}
+
+/// Statistics of a whole ping run, which is tracked for each target and each source IP as well as all pings together.
+/// Warmup pings and preparation errors are not counted, since they don't reflect the remote status. Re-probes are
+/// tracked separately, because they are only sent on failed paths and would skew the loss and latency of the run.
+#[derive(Debug, Clone)]
+pub struct PingRunStatistics {
+    pub protocol: Option<String>,
+    pub target_statistics: BTreeMap<SocketAddr, PingResultStatistics>,
+    pub source_ip_statistics: BTreeMap<IpAddr, PingResultStatistics>,
+    pub total_statistics: PingResultStatistics,
+
+    // Re-probe outcomes of each target, keyed by the source IP and port, since the same source port on different source
+    // IPs can go through different paths.
+    pub reprobe_outcomes: BTreeMap<SocketAddr, BTreeMap<SocketAddr, PingReprobeOutcome>>,
+
+    // Time span of the counted pings, from the start of the first ping to the end of the last one.
+    pub first_ping_time: Option<DateTime<Utc>>,
+    pub last_ping_end_time: Option<DateTime<Utc>>,
+}
+
+impl PingRunStatistics {
+    pub fn new() -> PingRunStatistics {
+        return PingRunStatistics {
+            protocol: None,
+            target_statistics: BTreeMap::new(),
+            source_ip_statistics: BTreeMap::new(),
+            total_statistics: PingResultStatistics::new(),
+            reprobe_outcomes: BTreeMap::new(),
+            first_ping_time: None,
+            last_ping_end_time: None,
+        };
+    }
+
+    pub fn update(&mut self, ping_result: &PingResult) {
+        // Skip warmup pings in analysis.
+        if ping_result.is_warmup() {
+            return;
+        }
+
+        // Skip preparation errors in analysis, since it is not a remote issue.
+        if ping_result.is_preparation_error() {
+            return;
+        }
+
+        // Save some info for outputting summary.
+        if self.protocol.is_none() {
+            self.protocol = Some(ping_result.protocol().to_string());
+        }
+
+        if ping_result.is_reprobe() {
+            self.reprobe_outcomes.entry(ping_result.target()).or_default().entry(ping_result.configured_source()).or_default().update(ping_result);
+            return;
+        }
+
+        self.target_statistics.entry(ping_result.target()).or_default().update(ping_result);
+        self.source_ip_statistics.entry(ping_result.configured_source().ip()).or_default().update(ping_result);
+        self.total_statistics.update(ping_result);
+
+        let ping_time = *ping_result.ping_time();
+        let ping_end_time = ping_time + chrono::Duration::from_std(ping_result.round_trip_time()).unwrap_or_else(|_| chrono::Duration::zero());
+        self.first_ping_time = Some(self.first_ping_time.map_or(ping_time, |first_ping_time| first_ping_time.min(ping_time)));
+        self.last_ping_end_time = Some(self.last_ping_end_time.map_or(ping_end_time, |last_ping_end_time| last_ping_end_time.max(ping_end_time)));
+    }
+
+    pub fn elapsed_time(&self) -> Duration {
+        return match (self.first_ping_time, self.last_ping_end_time) {
+            (Some(first_ping_time), Some(last_ping_end_time)) => (last_ping_end_time - first_ping_time).to_std().unwrap_or(Duration::ZERO),
+            _ => Duration::ZERO,
+        };
+    }
+
+    /// Pings per second that are actually sent during the elapsed time, which can be lower than the configured rate,
+    /// when the pings are slowed down by timeouts, port cooldown or the deadline.
+    pub fn achieved_ping_rate(&self) -> f64 {
+        let elapsed_time_in_secs = self.elapsed_time().as_secs_f64();
+        if elapsed_time_in_secs == 0.0 {
+            return 0.0;
+        }
+
+        return self.total_statistics.ping_count as f64 / elapsed_time_in_secs;
+    }
+
+    pub fn create_dto(&self) -> PingRunStatisticsDto {
+        return PingRunStatisticsDto {
+            protocol: self.protocol.clone().unwrap_or_default(),
+            targets: self.target_statistics.iter().map(|(target, statistics)| statistics.create_dto(Some(*target))).collect(),
+            source_ips: self
+                .source_ip_statistics
+                .iter()
+                .map(|(source_ip, statistics)| PingResultStatisticsDto { source_ip: Some(source_ip.to_string()), ..statistics.create_dto(None) })
+                .collect(),
+            total: self.total_statistics.create_dto(None),
+            elapsed_time_in_ms: self.elapsed_time().as_secs_f64() * 1000.0,
+            achieved_ping_rate: self.achieved_ping_rate(),
+        };
+    }
+}
+
+impl Default for PingRunStatistics {
+    fn default() -> Self {
+        PingRunStatistics::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnp_test_common;
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn ping_run_statistics_should_work() {
+        let mut statistics = PingRunStatistics::new();
+        rnp_test_common::generate_ping_result_test_samples().iter().for_each(|x| statistics.update(x));
+
+        assert_eq!(Some(String::from("TCP")), statistics.protocol);
+        assert_eq!(1, statistics.target_statistics.len());
+        assert_eq!(1, statistics.source_ip_statistics.len());
+
+        let total_statistics = &statistics.total_statistics;
+        assert_eq!(4, total_statistics.ping_count);
+        assert_eq!(2, total_statistics.success_count);
+        assert_eq!(2, total_statistics.failure_count);
+        assert_eq!(1, total_statistics.handshake_failed_count);
+        assert_eq!(1, total_statistics.disconnect_failed_count);
+        assert_eq!(
+            vec![(PingErrorCategory::ConnectionRefused, 1), (PingErrorCategory::ConnectionReset, 1), (PingErrorCategory::Timeout, 1)],
+            total_statistics.error_category_counts.iter().map(|(category, count)| (*category, *count)).collect::<Vec<(PingErrorCategory, u32)>>()
+        );
+
+        // The ping failed without any latency is not counted in latency statistics.
+        let latency_summary = total_statistics.latency_summary();
+        assert_eq!(3, latency_summary.sample_count);
+        assert_eq!(Duration::from_millis(20), latency_summary.min);
+        assert_eq!(Duration::from_millis(1000), latency_summary.max);
+        assert_eq!(Duration::from_millis(1000), latency_summary.p99);
+        assert!(statistics.reprobe_outcomes.is_empty());
+
+        // All samples are sent at the same time, so the elapsed time is the longest round trip time.
+        assert_eq!(Duration::from_millis(1000), statistics.elapsed_time());
+        assert_eq!(4.0, statistics.achieved_ping_rate());
+    }
+
+    #[test]
+    fn ping_run_statistics_should_track_reprobes_separately() {
+        let create_reprobe_result = |source: &str, is_succeeded: bool| {
+            PingResult::new(
+                &Utc::now(),
+                1,
+                "TCP",
+                "1.2.3.4:443".parse().unwrap(),
+                source.parse().unwrap(),
+                false,
+                true,
+                is_succeeded,
+                Duration::from_millis(if is_succeeded { 10 } else { 1000 }),
+                None,
+                None,
+                None,
+                None,
+                PingResultExtraDetails::new(),
+                !is_succeeded,
+                None,
+                None,
+            )
+        };
+
+        let mut statistics = PingRunStatistics::new();
+        statistics.update(&create_reprobe_result("5.6.7.8:1024", false));
+        statistics.update(&create_reprobe_result("5.6.7.8:1024", false));
+        statistics.update(&create_reprobe_result("5.6.7.8:1025", false));
+        statistics.update(&create_reprobe_result("5.6.7.8:1025", true));
+        statistics.update(&create_reprobe_result("5.6.7.9:1024", true));
+
+        assert_eq!(0, statistics.total_statistics.ping_count);
+        assert!(statistics.target_statistics.is_empty());
+        assert_eq!(Duration::ZERO, statistics.elapsed_time());
+
+        let outcomes = &statistics.reprobe_outcomes[&"1.2.3.4:443".parse().unwrap()];
+        assert_eq!(PingReprobeOutcome { reprobe_count: 2, success_count: 0 }, outcomes[&"5.6.7.8:1024".parse().unwrap()]);
+        assert!(outcomes[&"5.6.7.8:1024".parse().unwrap()].is_persistent_failure());
+        assert_eq!(PingReprobeOutcome { reprobe_count: 2, success_count: 1 }, outcomes[&"5.6.7.8:1025".parse().unwrap()]);
+        assert!(!outcomes[&"5.6.7.8:1025".parse().unwrap()].is_persistent_failure());
+
+        // The same source port on another source IP is tracked separately.
+        assert_eq!(PingReprobeOutcome { reprobe_count: 1, success_count: 1 }, outcomes[&"5.6.7.9:1024".parse().unwrap()]);
+    }
+
+    #[test]
+    fn ping_run_statistics_should_break_down_by_configured_source() {
+        let create_ping_result = |source_port: u16, is_succeeded: bool| {
+            let ping_result = PingResult::new(
+                &Utc::now(),
+                1,
+                "TCP",
+                "1.2.3.4:443".parse().unwrap(),
+                SocketAddr::new("0.0.0.0".parse().unwrap(), source_port),
+                false,
+                false,
+                is_succeeded,
+                Duration::from_millis(if is_succeeded { 10 } else { 1000 }),
+                None,
+                None,
+                None,
+                None,
+                PingResultExtraDetails::new(),
+                !is_succeeded,
+                None,
+                None,
+            );
+
+            // Only the succeeded pings know which local address the OS has picked.
+            if is_succeeded {
+                return ping_result.with_local_addr(Some(SocketAddr::new("5.6.7.8".parse().unwrap(), source_port)));
+            }
+            return ping_result;
+        };
+
+        let mut statistics = PingRunStatistics::new();
+        statistics.update(&create_ping_result(1024, true));
+        statistics.update(&create_ping_result(1024, false));
+        statistics.update(&create_ping_result(1025, false));
+
+        assert_eq!(vec!["0.0.0.0".parse::<IpAddr>().unwrap()], statistics.source_ip_statistics.keys().cloned().collect::<Vec<IpAddr>>());
+        let source_ip_statistics = &statistics.source_ip_statistics[&"0.0.0.0".parse::<IpAddr>().unwrap()];
+        assert_eq!(3, source_ip_statistics.ping_count);
+        assert_eq!(1, source_ip_statistics.success_count);
+        assert_eq!(2, source_ip_statistics.failure_count);
+    }
+}
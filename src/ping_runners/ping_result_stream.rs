@@ -0,0 +1,38 @@
+use crate::PingResult;
+use futures::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Stream of all ping results generated by a `PingRunnerCore`, including warmup pings.
+///
+/// The results are forwarded after all ping result processors have processed them. The channel behind the stream is
+/// bounded, so if the stream is not consumed in time, the result processing will be paused until it catches up. The
+/// stream ends after the ping runner core is stopped and all pending results are processed.
+pub struct PingResultStream {
+    receiver: mpsc::Receiver<PingResult>,
+}
+
+impl PingResultStream {
+    pub(crate) fn new(receiver: mpsc::Receiver<PingResult>) -> PingResultStream {
+        return PingResultStream { receiver };
+    }
+
+    /// Receive the next ping result, or None if all results are received.
+    pub async fn recv(&mut self) -> Option<PingResult> {
+        return self.receiver.recv().await;
+    }
+
+    /// Get the underlying mpsc receiver.
+    pub fn into_inner(self) -> mpsc::Receiver<PingResult> {
+        return self.receiver;
+    }
+}
+
+impl Stream for PingResultStream {
+    type Item = PingResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        return self.receiver.poll_recv(cx);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::*;
+use std::collections::BTreeMap;
+
+/// Outcome of the pings sent from a single source port.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PingSourcePortOutcome {
+    pub ping_count: u32,
+    pub success_count: u32,
+    pub failure_count: u32,
+    pub timed_out_count: u32,
+}
+
+impl PingSourcePortOutcome {
+    pub fn update(&mut self, ping_result: &PingResult) {
+        self.ping_count += 1;
+        if ping_result.is_succeeded() {
+            self.success_count += 1;
+        } else {
+            self.failure_count += 1;
+        }
+
+        if ping_result.is_timed_out() {
+            self.timed_out_count += 1;
+        }
+    }
+}
+
+/// Summary of a whole ping run, which is returned by `PingRunnerCore::join`.
+///
+/// Same as `PingRunStatistics`, warmup pings and preparation errors are not counted.
+#[derive(Debug, Clone)]
+pub struct PingRunSummary {
+    pub statistics: PingRunStatistics,
+    pub source_port_outcomes: BTreeMap<u16, PingSourcePortOutcome>,
+
+    /// The failed ping that stopped the run, when `exit_on_fail` is enabled.
+    pub exit_failure_reason: Option<PingResultDto>,
+}
+
+impl PingRunSummary {
+    pub fn new() -> PingRunSummary {
+        return PingRunSummary { statistics: PingRunStatistics::new(), source_port_outcomes: BTreeMap::new(), exit_failure_reason: None };
+    }
+
+    pub fn update(&mut self, ping_result: &PingResult) {
+        if ping_result.is_warmup() || ping_result.is_preparation_error() {
+            return;
+        }
+
+        self.statistics.update(ping_result);
+
+        // Re-probes are only sent on failed source ports, so they are not counted as the outcome of the source port.
+        if ping_result.is_reprobe() {
+            return;
+        }
+
+        self.source_port_outcomes.entry(ping_result.configured_source().port()).or_default().update(ping_result);
+    }
+
+    pub fn ping_count(&self) -> u32 {
+        self.statistics.total_statistics.ping_count
+    }
+    pub fn success_count(&self) -> u32 {
+        self.statistics.total_statistics.success_count
+    }
+    pub fn failure_count(&self) -> u32 {
+        self.statistics.total_statistics.failure_count
+    }
+
+    /// Ratio of failed pings in [0, 1]. When no ping is sent, the loss ratio is 0.
+    pub fn loss_ratio(&self) -> f64 {
+        if self.ping_count() == 0 {
+            return 0.0;
+        }
+
+        return self.failure_count() as f64 / self.ping_count() as f64;
+    }
+
+    pub fn latency_summary(&self) -> PingLatencySummary {
+        return self.statistics.total_statistics.latency_summary();
+    }
+}
+
+impl Default for PingRunSummary {
+    fn default() -> Self {
+        PingRunSummary::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnp_test_common;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn ping_run_summary_should_work() {
+        let mut summary = PingRunSummary::new();
+        rnp_test_common::generate_ping_result_test_samples().iter().for_each(|x| summary.update(x));
+
+        assert_eq!(4, summary.ping_count());
+        assert_eq!(2, summary.success_count());
+        assert_eq!(2, summary.failure_count());
+        assert_eq!(0.5, summary.loss_ratio());
+        assert_eq!(Duration::from_millis(1000), summary.latency_summary().max);
+        assert_eq!(None, summary.exit_failure_reason);
+
+        let mut expected_source_port_outcomes = BTreeMap::new();
+        expected_source_port_outcomes.insert(8080, PingSourcePortOutcome { ping_count: 4, success_count: 2, failure_count: 2, timed_out_count: 1 });
+        assert_eq!(expected_source_port_outcomes, summary.source_port_outcomes);
+    }
+
+    #[test]
+    fn empty_ping_run_summary_should_have_no_loss() {
+        let summary = PingRunSummary::new();
+        assert_eq!(0, summary.ping_count());
+        assert_eq!(0.0, summary.loss_ratio());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::*;
+use contracts::requires;
 use futures_intrusive::sync::ManualResetEvent;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::{sync::mpsc, task::JoinHandle};
 
 pub struct PingRunnerCore {
@@ -9,8 +11,11 @@ pub struct PingRunnerCore {
     stop_event: Arc<ManualResetEvent>,
     worker_join_handles: Vec<JoinHandle<()>>,
     ping_result_processor_stop_event: Arc<ManualResetEvent>,
-    ping_result_processor_join_handle: Option<JoinHandle<()>>,
+    ping_result_processor_join_handle: Option<JoinHandle<PingRunSummary>>,
     result_sender: mpsc::UnboundedSender<PingResult>,
+
+    // Shared by the warmup and normal pings, so the normal pings can skip the ports used in warmup with random strategies.
+    port_picking_seed: u64,
 }
 
 impl PingRunnerCore {
@@ -36,12 +41,16 @@ impl PingRunnerCore {
     /// let config = RnpPingRunnerConfig {
     ///     worker_config: PingWorkerConfig {
     ///         protocol: RnpSupportedProtocol::TCP,
-    ///         target: "10.0.0.1:443".parse().unwrap(),
-    ///         source_ip: "10.0.0.2".parse().unwrap(),
+    ///         targets: vec!["10.0.0.1:443".parse().unwrap()],
+    ///         source_ips: vec!["10.0.0.2".parse().unwrap()],
     ///         ping_interval: Duration::from_millis(1500),
     ///         ping_client_config: PingClientConfig {
     ///             wait_timeout: Duration::from_millis(1000),
     ///             time_to_live: Some(128),
+    ///             flow_label: None,
+    ///             type_of_service: None,
+    ///             interface: None,
+    ///             fwmark: None,
     ///             check_disconnect: false,
     ///             wait_before_disconnect: Duration::ZERO,
     ///             disconnect_timeout: Duration::from_millis(2000),
@@ -49,15 +58,29 @@ impl PingRunnerCore {
     ///             log_tls_key: false,
     ///             alpn_protocol: None,
     ///             use_timer_rtt: false,
+    ///             http_method: String::from("GET"),
+    ///             http_path: String::from("/"),
+    ///             http_host: None,
+    ///             http_headers: vec![],
+    ///             http_expected_status_codes: vec![],
     ///         },
     ///     },
     ///     worker_scheduler_config: PingWorkerSchedulerConfig {
     ///         source_ports: PortRangeList {
     ///             ranges: vec![(1024..=2048), (3096..=3096), (3097..=3097)]
     ///         },
+    ///         port_picking_strategy: PingPortPickingStrategy::Sequential,
+    ///         port_cooldown: Duration::ZERO,
+    ///         use_port_cross_product: false,
     ///         ping_count: Some(4),
+    ///         duration: None,
     ///         warmup_count: 1,
     ///         parallel_ping_count: 1,
+    ///         reprobe_count: 0,
+    ///         reprobe_interval: Duration::from_millis(5000),
+    ///         ping_rate: None,
+    ///         ping_rate_jitter: 0.0,
+    ///         use_poisson_arrival: false,
     ///     },
     ///     result_processor_config: PingResultProcessorConfig {
     ///         common_config: PingResultProcessorCommonConfig {
@@ -67,10 +90,13 @@ impl PingRunnerCore {
     ///         exit_failure_reason: None,
     ///         csv_log_path: None,
     ///         json_log_path: None,
+    ///         json_summary_log_path: None,
     ///         text_log_path: None,
     ///         show_result_scatter: false,
     ///         show_latency_scatter: false,
+    ///         show_bad_paths: false,
     ///         latency_buckets: None,
+    ///         metrics_address: None,
     ///     },
     ///     external_ping_client_factory: None,
     ///     extra_ping_result_processors: vec![],
@@ -84,7 +110,42 @@ impl PingRunnerCore {
     ///
     /// ```
     #[tracing::instrument(name = "Start running Rnp core", level = "debug", skip(stop_event))]
-    pub fn new(mut config: RnpPingRunnerConfig, stop_event: Arc<ManualResetEvent>) -> PingRunnerCore {
+    pub fn new(config: RnpPingRunnerConfig, stop_event: Arc<ManualResetEvent>) -> PingRunnerCore {
+        return PingRunnerCore::new_with_optional_result_stream(config, stop_event, None);
+    }
+
+    /// Same as `new`, but also returns a stream that yields every ping result, so the results can be consumed with
+    /// async code directly instead of implementing `PingResultProcessor`.
+    ///
+    /// The stream is backed by a bounded channel with `result_stream_buffer_size` slots. When it is full, the result
+    /// processing waits for the stream to be consumed, hence the stream must be polled while waiting on `join`,
+    /// e.g. by running `join` in another task.
+    ///
+    /// # Arguments
+    ///
+    /// * `config`: The configuration of Rnp.
+    /// * `stop_event`: The event to signal Rnp to stop.
+    /// * `result_stream_buffer_size`: How many ping results can be buffered before the stream is consumed.
+    ///
+    /// returns: (RnpCore, PingResultStream)
+    #[tracing::instrument(name = "Start running Rnp core with result stream", level = "debug", skip(stop_event))]
+    #[allow(unreachable_code)]
+    #[requires(result_stream_buffer_size > 0)]
+    pub fn new_with_result_stream(
+        config: RnpPingRunnerConfig,
+        stop_event: Arc<ManualResetEvent>,
+        result_stream_buffer_size: usize,
+    ) -> (PingRunnerCore, PingResultStream) {
+        let (result_stream_sender, result_stream_receiver) = mpsc::channel(result_stream_buffer_size);
+        let rnp_core = PingRunnerCore::new_with_optional_result_stream(config, stop_event, Some(result_stream_sender));
+        return (rnp_core, PingResultStream::new(result_stream_receiver));
+    }
+
+    fn new_with_optional_result_stream(
+        mut config: RnpPingRunnerConfig,
+        stop_event: Arc<ManualResetEvent>,
+        result_stream_sender: Option<mpsc::Sender<PingResult>>,
+    ) -> PingRunnerCore {
         // Move all extra ping result processors into another Vec for initializing result processing worker.
         // Otherwise RnpCoreConfig will be partially moved and results in compile error.
         let mut extra_ping_result_processors = Vec::new();
@@ -98,6 +159,7 @@ impl PingRunnerCore {
             config.worker_scheduler_config.parallel_ping_count,
             ping_result_processor_stop_event.clone(),
             stop_event.clone(),
+            result_stream_sender,
         );
 
         let rnp_core = PingRunnerCore {
@@ -107,6 +169,7 @@ impl PingRunnerCore {
             ping_result_processor_stop_event,
             ping_result_processor_join_handle: Some(ping_result_processor_join_handle),
             result_sender,
+            port_picking_seed: rand::random(),
         };
 
         rnp_core.log_header_to_console();
@@ -121,7 +184,8 @@ impl PingRunnerCore {
         parallel_ping_count: u32,
         stop_event: Arc<ManualResetEvent>,
         ping_stop_event: Arc<ManualResetEvent>,
-    ) -> (mpsc::UnboundedSender<PingResult>, JoinHandle<()>) {
+        result_stream_sender: Option<mpsc::Sender<PingResult>>,
+    ) -> (mpsc::UnboundedSender<PingResult>, JoinHandle<PingRunSummary>) {
         let (ping_result_sender, ping_result_receiver) = mpsc::unbounded_channel();
         let ping_result_processor_join_handle = PingResultProcessingWorker::run(
             Arc::new(result_processor_config),
@@ -129,6 +193,7 @@ impl PingRunnerCore {
             stop_event,
             ping_stop_event,
             ping_result_receiver,
+            result_stream_sender,
         );
 
         return (ping_result_sender, ping_result_processor_join_handle);
@@ -144,7 +209,107 @@ impl PingRunnerCore {
             None => "".to_string(),
         };
 
-        println!("Start testing {} {:?}{}:", self.config.worker_config.protocol, self.config.worker_config.target, ttl_message);
+        let source_ips = &self.config.worker_config.source_ips;
+        let source_ip_message = match source_ips.len() {
+            1 => "".to_string(),
+            source_ip_count => format!(" from {} source IPs {:?}", source_ip_count, source_ips),
+        };
+
+        let targets = &self.config.worker_config.targets;
+        if targets.len() == 1 {
+            println!("Start testing {} {:?}{}{}:", self.config.worker_config.protocol, targets[0], source_ip_message, ttl_message);
+        } else {
+            println!(
+                "Start testing {} {} targets {:?}{}{}:",
+                self.config.worker_config.protocol,
+                targets.len(),
+                targets,
+                source_ip_message,
+                ttl_message
+            );
+        }
+
+        self.log_port_cooldown_warning_to_console();
+        self.log_ping_rate_warning_to_console();
+    }
+
+    fn log_ping_rate_warning_to_console(&self) {
+        // Each worker runs 1 ping at a time, so to keep up with the rate when pings are timing out, we need at least
+        // (rate * timeout) workers.
+        let ping_rate = match self.config.worker_scheduler_config.ping_rate {
+            Some(ping_rate) => ping_rate,
+            None => return,
+        };
+
+        let wait_timeout = self.config.worker_config.ping_client_config.wait_timeout;
+        let required_worker_count = (ping_rate * wait_timeout.as_secs_f64()).ceil();
+        let parallel_ping_count = self.config.worker_scheduler_config.parallel_ping_count;
+        if (parallel_ping_count as f64) < required_worker_count {
+            println!(
+                "Warning: {} parallel pings might not keep up with {} pings/s when pings time out after {}ms, which needs {} parallel pings.",
+                parallel_ping_count,
+                ping_rate,
+                wait_timeout.as_millis(),
+                required_worker_count
+            );
+        }
+    }
+
+    fn log_port_cooldown_warning_to_console(&self) {
+        let scheduler_config = &self.config.worker_scheduler_config;
+        let required_port_count =
+            match PingRunnerCore::calculate_port_cooldown_required_port_count(scheduler_config, self.config.worker_config.ping_interval) {
+                Some(required_port_count) => required_port_count,
+                None => return,
+            };
+
+        let port_count = scheduler_config.source_ports.calculate_total_port_count() as u128;
+        if port_count < required_port_count {
+            let ping_frequency_message = match scheduler_config.ping_rate {
+                Some(ping_rate) => format!("{} pings/s", ping_rate),
+                None => {
+                    format!("{} parallel pings every {}ms", scheduler_config.parallel_ping_count, self.config.worker_config.ping_interval.as_millis())
+                }
+            };
+
+            println!(
+                "Warning: {} source ports are not enough for {} with {}ms port cooldown, which needs {} ports. Pings will be slowed down to wait for the ports to cool down.",
+                port_count,
+                ping_frequency_message,
+                scheduler_config.port_cooldown.as_millis(),
+                required_port_count
+            );
+        }
+    }
+
+    fn calculate_port_cooldown_required_port_count(scheduler_config: &PingWorkerSchedulerConfig, ping_interval: Duration) -> Option<u128> {
+        if scheduler_config.port_cooldown.is_zero() {
+            return None;
+        }
+
+        // With rate limit, the workers don't wait between pings, and (rate * cooldown) pings are sent within a cooldown.
+        if let Some(ping_rate) = scheduler_config.ping_rate {
+            return Some((ping_rate * scheduler_config.port_cooldown.as_secs_f64()).ceil() as u128);
+        }
+
+        // Otherwise, each worker starts a ping in every ping interval, so within a cooldown, each worker needs
+        // (cooldown / interval) source ports. If the range is smaller than that, the pings will be slowed down to wait for
+        // the ports.
+        let ping_interval_in_us = ping_interval.as_micros().max(1);
+        let total_cooldown_in_us = scheduler_config.port_cooldown.as_micros() * scheduler_config.parallel_ping_count as u128;
+        let required_port_count = total_cooldown_in_us / ping_interval_in_us;
+        return Some(if required_port_count * ping_interval_in_us < total_cooldown_in_us { required_port_count + 1 } else { required_port_count });
+    }
+
+    // Flow label is only set on the jobs when the ping client can send it, so the results never record a flow label that is
+    // not on the wire. QUIC cannot send it, because the endpoint drops the replies when the flow info of the peer address
+    // doesn't match, and sending flow label is only supported on Linux.
+    fn get_flow_label_to_send(&self) -> Option<PingFlowLabel> {
+        if !cfg!(target_os = "linux") || self.config.worker_config.protocol == RnpSupportedProtocol::QUIC {
+            return None;
+        }
+
+        return self.config.worker_config.ping_client_config.flow_label;
     }
 
     /// Run all warm up pings one by one and wait until they are all completed.
@@ -157,16 +322,24 @@ impl PingRunnerCore {
         }
 
         tracing::debug!("Creating warmup worker.");
-        let source_port_picker = Arc::new(Mutex::new(PingPortPicker::new(
+        let mut job_picker = PingJobPicker::new(
             Some(self.config.worker_scheduler_config.warmup_count),
+            self.config.worker_config.targets.clone(),
+            self.config.worker_config.source_ips.clone(),
             self.config.worker_scheduler_config.source_ports.clone(),
             0,
-        )));
+            self.config.worker_scheduler_config.port_picking_strategy,
+            self.port_picking_seed,
+        );
+        job_picker.enable_port_cooldown(self.config.worker_scheduler_config.port_cooldown);
+        if let Some(flow_label) = self.get_flow_label_to_send() {
+            job_picker.enable_flow_label(flow_label);
+        }
+        let job_picker = Arc::new(Mutex::new(job_picker));
 
         let mut worker_join_handles = self.create_ping_workers_with_options(
             1, // Warmup always use only 1 worker.
-            source_port_picker,
-            true,
+            job_picker, true,
         );
 
         tracing::debug!("Waiting for warmup worker to stop.");
@@ -194,28 +367,66 @@ impl PingRunnerCore {
             Some(ping_count) => Some(ping_count + warmup_count),
         };
 
-        let source_port_picker =
-            Arc::new(Mutex::new(PingPortPicker::new(adjusted_ping_count, self.config.worker_scheduler_config.source_ports.clone(), warmup_count)));
+        let mut job_picker = PingJobPicker::new(
+            adjusted_ping_count,
+            self.config.worker_config.targets.clone(),
+            self.config.worker_config.source_ips.clone(),
+            self.config.worker_scheduler_config.source_ports.clone(),
+            warmup_count,
+            self.config.worker_scheduler_config.port_picking_strategy,
+            self.port_picking_seed,
+        );
+        job_picker.enable_port_cooldown(self.config.worker_scheduler_config.port_cooldown);
+        if let Some(flow_label) = self.get_flow_label_to_send() {
+            job_picker.enable_flow_label(flow_label);
+        }
+        job_picker.enable_reprobes(self.config.worker_scheduler_config.reprobe_count, self.config.worker_scheduler_config.reprobe_interval);
+
+        if self.config.worker_scheduler_config.use_port_cross_product {
+            job_picker.enable_port_cross_product();
+        }
+
+        // The deadline starts after the warmup, so the warmup pings don't count against the duration. A duration too long to
+        // be represented never ends, which is the same as having no deadline.
+        if let Some(deadline) = self.config.worker_scheduler_config.duration.and_then(|duration| Instant::now().checked_add(duration)) {
+            job_picker.enable_deadline(deadline);
+        }
+
+        if let Some(ping_rate) = self.config.worker_scheduler_config.ping_rate {
+            job_picker.enable_rate_limit(PingRateLimiter::new(
+                ping_rate,
+                self.config.worker_scheduler_config.ping_rate_jitter,
+                self.config.worker_scheduler_config.use_poisson_arrival,
+            ));
+        }
+
+        let job_picker = Arc::new(Mutex::new(job_picker));
 
         let worker_count = self.config.worker_scheduler_config.parallel_ping_count;
-        self.worker_join_handles = self.create_ping_workers_with_options(worker_count, source_port_picker, false);
+        self.worker_join_handles = self.create_ping_workers_with_options(worker_count, job_picker, false);
     }
 
     fn create_ping_workers_with_options(
         &mut self,
         worker_count: u32,
-        source_port_picker: Arc<Mutex<PingPortPicker>>,
+        job_picker: Arc<Mutex<PingJobPicker>>,
         is_warmup_worker: bool,
     ) -> Vec<JoinHandle<()>> {
         let mut worker_join_handles = Vec::new();
 
-        let shared_worker_config = Arc::new(self.config.worker_config.clone());
+        // With rate limit, the pings are scheduled by the job picker for all workers, so workers don't wait between pings.
+        let mut worker_config = self.config.worker_config.clone();
+        if !is_warmup_worker && self.config.worker_scheduler_config.ping_rate.is_some() {
+            worker_config.ping_interval = Duration::ZERO;
+        }
+
+        let shared_worker_config = Arc::new(worker_config);
         for worker_id in 0..worker_count {
             let worker_join_handle = PingWorker::run(
                 worker_id,
                 shared_worker_config.clone(),
                 self.config.external_ping_client_factory.clone(),
-                source_port_picker.clone(),
+                job_picker.clone(),
                 self.stop_event.clone(),
                 self.result_sender.clone(),
                 is_warmup_worker,
@@ -226,9 +437,9 @@ impl PingRunnerCore {
         return worker_join_handles;
     }
 
-    /// Wait for all pings to complete.
+    /// Wait for all pings to complete and return the summary of the whole run.
     #[tracing::instrument(name = "Waiting for RNP core to be stopped.", level = "debug", skip(self))]
-    pub async fn join(&mut self) {
+    pub async fn join(&mut self) -> PingRunSummary {
         tracing::debug!("Waiting for all workers to be stopped.");
         for join_handle in &mut self.worker_join_handles {
             join_handle.await.unwrap();
@@ -247,7 +458,46 @@ impl PingRunnerCore {
         self.ping_result_processor_stop_event.set();
 
         tracing::debug!("Waiting for result processor to be stopped.");
-        self.ping_result_processor_join_handle.take().unwrap().await.unwrap();
+        let summary = self.ping_result_processor_join_handle.take().unwrap().await.unwrap();
         tracing::debug!("Result processor stopped.");
+
+        return summary;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn port_cooldown_required_port_count_should_follow_ping_interval_or_rate() {
+        let mut scheduler_config = PingWorkerSchedulerConfig {
+            source_ports: PortRangeList { ranges: vec![(1024..=2047)] },
+            port_picking_strategy: PingPortPickingStrategy::Sequential,
+            port_cooldown: Duration::ZERO,
+            use_port_cross_product: false,
+            ping_count: None,
+            duration: None,
+            warmup_count: 0,
+            parallel_ping_count: 10,
+            reprobe_count: 0,
+            reprobe_interval: Duration::ZERO,
+            ping_rate: None,
+            ping_rate_jitter: 0.0,
+            use_poisson_arrival: false,
+        };
+        let ping_interval = Duration::from_millis(1000);
+        assert_eq!(None, PingRunnerCore::calculate_port_cooldown_required_port_count(&scheduler_config, ping_interval));
+
+        scheduler_config.port_cooldown = Duration::from_millis(60000);
+        assert_eq!(Some(600), PingRunnerCore::calculate_port_cooldown_required_port_count(&scheduler_config, ping_interval));
+
+        // With rate limit, the ping interval is not used by the workers, so only the rate matters.
+        scheduler_config.ping_rate = Some(1000.0);
+        assert_eq!(Some(60000), PingRunnerCore::calculate_port_cooldown_required_port_count(&scheduler_config, ping_interval));
+
+        scheduler_config.ping_rate = Some(0.5);
+        assert_eq!(Some(30), PingRunnerCore::calculate_port_cooldown_required_port_count(&scheduler_config, ping_interval));
     }
 }
@@ -0,0 +1,366 @@
+use crate::ping_clients::ping_client_tcp::PingClientTcp;
+use crate::*;
+use socket2::Socket;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PingTraceHopStatus {
+    // The SYN expired on the way, and the hop responded with ICMP time exceeded.
+    TimeExceeded,
+
+    // The SYN reached the target, and the target responded with either SYN-ACK or RST.
+    Reached,
+
+    // The hop responded with ICMP destination unreachable, so no further hop can be reached.
+    Unreachable,
+
+    TimedOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct PingTraceHop {
+    pub ttl: u32,
+    pub source: SocketAddr,
+    pub responder: Option<IpAddr>,
+    pub round_trip_time: Duration,
+    pub status: PingTraceHopStatus,
+}
+
+impl PingTraceHop {
+    pub fn is_last_hop(&self) -> bool {
+        return self.status == PingTraceHopStatus::Reached || self.status == PingTraceHopStatus::Unreachable;
+    }
+}
+
+impl fmt::Display for PingTraceHop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let responder = match &self.responder {
+            Some(responder) => responder,
+            None => return write!(f, "{:>3}  *", self.ttl),
+        };
+
+        let rtt_in_ms = self.round_trip_time.as_micros() as f64 / 1000.0;
+        return match self.status {
+            PingTraceHopStatus::Reached => write!(f, "{:>3}  {}  {:.2}ms (target reached)", self.ttl, responder, rtt_in_ms),
+            PingTraceHopStatus::Unreachable => write!(f, "{:>3}  {}  {:.2}ms (unreachable)", self.ttl, responder, rtt_in_ms),
+            _ => write!(f, "{:>3}  {}  {:.2}ms", self.ttl, responder, rtt_in_ms),
+        };
+    }
+}
+
+/// Trace the path to the target with TCP SYNs, by increasing the TTL hop by hop. The ICMP errors sent back by the hops
+/// are read from the socket error queue (IP_RECVERR), so no raw socket or extra privilege is needed. Since every hop is
+/// probed with the same source port, the trace follows the same ECMP path as the pings from that port.
+pub struct PingTracer {
+    config: PingClientConfig,
+}
+
+impl PingTracer {
+    pub fn new(config: &PingClientConfig) -> PingTracer {
+        // TTL is controlled by tracer for each hop, and the connections should always be reset right away.
+        let mut config = config.clone();
+        config.time_to_live = None;
+        config.check_disconnect = false;
+        return PingTracer { config };
+    }
+
+    /// Trace the target hop by hop until the target is reached or unreachable. When source port is 0, the port picked by
+    /// OS in the first hop is used for the rest hops.
+    pub fn trace<F: FnMut(&PingTraceHop)>(
+        &self,
+        source: &SocketAddr,
+        target: &SocketAddr,
+        max_hop_count: u32,
+        mut on_hop: F,
+    ) -> io::Result<Vec<PingTraceHop>> {
+        let mut source = *source;
+        let mut hops = Vec::new();
+
+        for ttl in 1..=max_hop_count {
+            let hop = self.trace_hop(&source, target, ttl)?;
+            if source.port() == 0 {
+                source.set_port(hop.source.port());
+            }
+
+            on_hop(&hop);
+            let is_last_hop = hop.is_last_hop();
+            hops.push(hop);
+            if is_last_hop {
+                break;
+            }
+        }
+
+        return Ok(hops);
+    }
+
+    #[tracing::instrument(name = "Tracing hop", level = "debug", skip(self))]
+    pub fn trace_hop(&self, source: &SocketAddr, target: &SocketAddr, ttl: u32) -> io::Result<PingTraceHop> {
+        let socket = PingClientTcp::prepare_socket_for_ping(&self.config, source)?;
+        let source = socket.local_addr()?.as_socket().unwrap_or(*source);
+
+        if target.is_ipv4() {
+            socket.set_ttl(ttl)?;
+        } else {
+            socket.set_unicast_hops_v6(ttl)?;
+        }
+
+        let start_time = Instant::now();
+        let (responder, status) = probe_hop(&socket, target, self.config.wait_timeout)?;
+        let round_trip_time = Instant::now().duration_since(start_time);
+
+        return Ok(PingTraceHop { ttl, source, responder, round_trip_time, status });
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn probe_hop(socket: &Socket, target: &SocketAddr, timeout: Duration) -> io::Result<(Option<IpAddr>, PingTraceHopStatus)> {
+    return linux::probe_hop(socket, target, timeout);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe_hop(_socket: &Socket, _target: &SocketAddr, _timeout: Duration) -> io::Result<(Option<IpAddr>, PingTraceHopStatus)> {
+    return Err(io::Error::new(io::ErrorKind::Unsupported, "TCP trace is only supported on Linux."));
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::PingTraceHopStatus;
+    use socket2::{SockAddr, Socket};
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::os::unix::io::AsRawFd;
+    use std::time::Duration;
+
+    const ICMP_TIME_EXCEEDED: u8 = 11;
+    const ICMPV6_TIME_EXCEEDED: u8 = 3;
+
+    pub(super) fn probe_hop(socket: &Socket, target: &SocketAddr, timeout: Duration) -> io::Result<(Option<IpAddr>, PingTraceHopStatus)> {
+        enable_recv_error(socket, target.is_ipv4())?;
+        socket.set_nonblocking(true)?;
+
+        match socket.connect(&SockAddr::from(*target)) {
+            Ok(()) => return Ok((Some(target.ip()), PingTraceHopStatus::Reached)),
+            Err(e) if e.raw_os_error() == Some(libc::EINPROGRESS) => (),
+            Err(e) => return Err(e),
+        }
+
+        if !wait_for_connect_events(socket, timeout)? {
+            return Ok((None, PingTraceHopStatus::TimedOut));
+        }
+
+        // The ICMP error needs to be checked before the socket error, because it fails the connect as well.
+        if let Some((offender, icmp_type)) = read_icmp_error(socket)? {
+            let status = match icmp_type {
+                ICMP_TIME_EXCEEDED if target.is_ipv4() => PingTraceHopStatus::TimeExceeded,
+                ICMPV6_TIME_EXCEEDED if target.is_ipv6() => PingTraceHopStatus::TimeExceeded,
+                // Destination unreachable and whatever else stop the SYN from going any further.
+                _ => PingTraceHopStatus::Unreachable,
+            };
+            return Ok((offender, status));
+        }
+
+        // Both SYN-ACK and RST mean the SYN has reached the target.
+        return match socket.take_error()? {
+            None => Ok((Some(target.ip()), PingTraceHopStatus::Reached)),
+            Some(e) if e.kind() == io::ErrorKind::ConnectionRefused => Ok((Some(target.ip()), PingTraceHopStatus::Reached)),
+            Some(e) => Err(e),
+        };
+    }
+
+    fn enable_recv_error(socket: &Socket, is_ipv4: bool) -> io::Result<()> {
+        let (level, name) = if is_ipv4 { (libc::IPPROTO_IP, libc::IP_RECVERR) } else { (libc::IPPROTO_IPV6, libc::IPV6_RECVERR) };
+        let enabled: libc::c_int = 1;
+
+        // Safe, because the value is a plain int owned by us, and its size is passed in.
+        let result = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &enabled as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(());
+    }
+
+    // Returns false when timed out.
+    fn wait_for_connect_events(socket: &Socket, timeout: Duration) -> io::Result<bool> {
+        let mut poll_fd = libc::pollfd { fd: socket.as_raw_fd(), events: libc::POLLOUT, revents: 0 };
+        let timeout_in_ms = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+
+        // Safe, because we only pass in a single pollfd owned by us.
+        let result = unsafe { libc::poll(&mut poll_fd, 1, timeout_in_ms) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(result > 0);
+    }
+
+    // Read the ICMP error from socket error queue, and return the address of the hop that sent it, as well as the ICMP type.
+    fn read_icmp_error(socket: &Socket) -> io::Result<Option<(Option<IpAddr>, u8)>> {
+        let mut data_buffer = [0u8; 64];
+        let mut control_buffer = [0u8; 512];
+        let mut iov = libc::iovec { iov_base: data_buffer.as_mut_ptr() as *mut libc::c_void, iov_len: data_buffer.len() };
+
+        // Safe, because msghdr is a plain C struct, and all the buffers it points to are owned by us and outlive the call.
+        let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control_buffer.as_mut_ptr() as *mut libc::c_void;
+        message.msg_controllen = control_buffer.len() as _;
+
+        let result = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut message, libc::MSG_ERRQUEUE) };
+        if result < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::WouldBlock {
+                return Ok(None);
+            }
+            return Err(e);
+        }
+
+        // Safe, because the control messages are filled by kernel within the control buffer we passed in.
+        unsafe {
+            let mut control_message = libc::CMSG_FIRSTHDR(&message);
+            while !control_message.is_null() {
+                let header = &*control_message;
+                let is_recv_error = (header.cmsg_level == libc::SOL_IP && header.cmsg_type == libc::IP_RECVERR)
+                    || (header.cmsg_level == libc::SOL_IPV6 && header.cmsg_type == libc::IPV6_RECVERR);
+
+                if is_recv_error {
+                    let extended_error_ptr = libc::CMSG_DATA(control_message) as *const libc::sock_extended_err;
+                    let extended_error = std::ptr::read_unaligned(extended_error_ptr);
+                    if extended_error.ee_origin != libc::SO_EE_ORIGIN_ICMP && extended_error.ee_origin != libc::SO_EE_ORIGIN_ICMP6 {
+                        return Ok(None);
+                    }
+
+                    let offender = read_ip_from_sockaddr(libc::SO_EE_OFFENDER(extended_error_ptr));
+                    return Ok(Some((offender, extended_error.ee_type)));
+                }
+
+                control_message = libc::CMSG_NXTHDR(&message, control_message);
+            }
+        }
+
+        return Ok(None);
+    }
+
+    pub(super) unsafe fn read_ip_from_sockaddr(address: *const libc::sockaddr) -> Option<IpAddr> {
+        return match std::ptr::read_unaligned(address).sa_family as libc::c_int {
+            libc::AF_INET => {
+                let address_v4 = std::ptr::read_unaligned(address as *const libc::sockaddr_in);
+                Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(address_v4.sin_addr.s_addr))))
+            }
+            libc::AF_INET6 => {
+                let address_v6 = std::ptr::read_unaligned(address as *const libc::sockaddr_in6);
+                Some(IpAddr::V6(Ipv6Addr::from(address_v6.sin6_addr.s6_addr)))
+            }
+            _ => None,
+        };
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::net::TcpListener;
+
+    fn create_test_config() -> PingClientConfig {
+        return PingClientConfig {
+            wait_timeout: Duration::from_millis(1000),
+            time_to_live: None,
+            flow_label: None,
+            type_of_service: None,
+            interface: None,
+            fwmark: None,
+            check_disconnect: false,
+            wait_before_disconnect: Duration::ZERO,
+            disconnect_timeout: Duration::from_millis(1000),
+            server_name: None,
+            log_tls_key: false,
+            alpn_protocol: None,
+            use_timer_rtt: false,
+            http_method: String::from("GET"),
+            http_path: String::from("/"),
+            http_host: None,
+            http_headers: vec![],
+            http_expected_status_codes: vec![],
+        };
+    }
+
+    #[test]
+    fn tracing_local_listening_target_should_reach_target_in_first_hop() {
+        let listener = TcpListener::bind("127.0.0.1:11344").unwrap();
+        let target = listener.local_addr().unwrap();
+
+        let tracer = PingTracer::new(&create_test_config());
+        let mut reported_hop_count = 0;
+        let hops = tracer.trace(&"127.0.0.1:0".parse().unwrap(), &target, 30, |_| reported_hop_count += 1).unwrap();
+
+        assert_eq!(1, hops.len());
+        assert_eq!(1, reported_hop_count);
+        assert_eq!(PingTraceHopStatus::Reached, hops[0].status);
+        assert_eq!(Some(target.ip()), hops[0].responder);
+        assert_ne!(0, hops[0].source.port());
+    }
+
+    #[test]
+    fn tracing_local_closed_port_should_reach_target_with_reset() {
+        let tracer = PingTracer::new(&create_test_config());
+        let hop = tracer.trace_hop(&"127.0.0.1:0".parse().unwrap(), &"127.0.0.1:11345".parse().unwrap(), 1).unwrap();
+        assert_eq!(PingTraceHopStatus::Reached, hop.status);
+        assert_eq!(Some("127.0.0.1".parse::<IpAddr>().unwrap()), hop.responder);
+    }
+
+    #[test]
+    fn tracing_ipv6_local_listening_target_should_reach_target_in_first_hop() {
+        let listener = TcpListener::bind("[::1]:11346").unwrap();
+        let target = listener.local_addr().unwrap();
+
+        let tracer = PingTracer::new(&create_test_config());
+        let hops = tracer.trace(&"[::1]:0".parse().unwrap(), &target, 30, |_| ()).unwrap();
+        assert_eq!(1, hops.len());
+        assert_eq!(PingTraceHopStatus::Reached, hops[0].status);
+        assert_eq!(Some(target.ip()), hops[0].responder);
+    }
+
+    #[test]
+    fn reading_ip_from_icmp_offender_address_should_work() {
+        let address_v4: SocketAddr = "10.0.0.1:0".parse().unwrap();
+        let address_v6: SocketAddr = "[2001:db8::1]:0".parse().unwrap();
+
+        // Safe, because SockAddr holds a sockaddr_storage, which is large enough for both sockaddr_in and sockaddr_in6.
+        unsafe {
+            assert_eq!(Some(address_v4.ip()), linux::read_ip_from_sockaddr(socket2::SockAddr::from(address_v4).as_ptr()));
+            assert_eq!(Some(address_v6.ip()), linux::read_ip_from_sockaddr(socket2::SockAddr::from(address_v6).as_ptr()));
+        }
+    }
+
+    #[test]
+    fn formatting_trace_hops_should_work() {
+        let create_hop = |ttl: u32, responder: Option<&str>, status: PingTraceHopStatus| PingTraceHop {
+            ttl,
+            source: "10.0.0.2:1024".parse().unwrap(),
+            responder: responder.map(|responder| responder.parse().unwrap()),
+            round_trip_time: Duration::from_micros(1500),
+            status,
+        };
+
+        assert_eq!("  1  10.0.1.1  1.50ms", create_hop(1, Some("10.0.1.1"), PingTraceHopStatus::TimeExceeded).to_string());
+        assert_eq!("  2  *", create_hop(2, None, PingTraceHopStatus::TimedOut).to_string());
+        assert_eq!("  3  10.0.3.1  1.50ms (unreachable)", create_hop(3, Some("10.0.3.1"), PingTraceHopStatus::Unreachable).to_string());
+        assert_eq!("  4  10.0.0.1  1.50ms (target reached)", create_hop(4, Some("10.0.0.1"), PingTraceHopStatus::Reached).to_string());
+        assert!(!create_hop(1, Some("10.0.1.1"), PingTraceHopStatus::TimeExceeded).is_last_hop());
+        assert!(create_hop(3, Some("10.0.3.1"), PingTraceHopStatus::Unreachable).is_last_hop());
+    }
+}
@@ -1,16 +1,20 @@
-use crate::ping_clients::ping_client::{PingClientError, PingClientPingResultDetails};
-use crate::{ping_client_factory, PingClient, PingClientFactory, PingPortPicker, PingResult, PingWorkerConfig};
+use crate::ping_clients::ping_client::{
+    PingClientError, PingClientPingResultDetails, PingResultExtraDetails, PING_RESULT_EXTRA_DETAIL_FLOW_LABEL,
+    PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE,
+};
+use crate::{ping_client_factory, PingClient, PingClientFactory, PingJob, PingJobPicker, PingResult, PingWorkerConfig, RnpSupportedProtocol};
 use chrono::{offset::Utc, DateTime};
 use futures_intrusive::sync::ManualResetEvent;
-use std::time::Duration;
-use std::{net::SocketAddr, sync::Arc, sync::Mutex};
+use std::net::{SocketAddr, SocketAddrV6};
+use std::time::{Duration, Instant};
+use std::{sync::Arc, sync::Mutex};
 use tokio::{sync::mpsc, task, task::JoinHandle};
 
 pub struct PingWorker {
     id: u32,
     config: Arc<PingWorkerConfig>,
     stop_event: Arc<ManualResetEvent>,
-    port_picker: Arc<Mutex<PingPortPicker>>,
+    job_picker: Arc<Mutex<PingJobPicker>>,
     ping_client: Box<dyn PingClient + Send + Sync>,
     result_sender: mpsc::UnboundedSender<PingResult>,
     is_warmup_worker: bool,
@@ -20,13 +24,13 @@ impl PingWorker {
     #[tracing::instrument(
         name = "Starting worker",
         level = "debug",
-        skip(config, external_ping_client_factory, port_picker, stop_event, result_sender)
+        skip(config, external_ping_client_factory, job_picker, stop_event, result_sender)
     )]
     pub fn run(
         worker_id: u32,
         config: Arc<PingWorkerConfig>,
         external_ping_client_factory: Option<PingClientFactory>,
-        port_picker: Arc<Mutex<PingPortPicker>>,
+        job_picker: Arc<Mutex<PingJobPicker>>,
         stop_event: Arc<ManualResetEvent>,
         result_sender: mpsc::UnboundedSender<PingResult>,
         is_warmup_worker: bool,
@@ -34,7 +38,7 @@ impl PingWorker {
         let join_handle = task::spawn(async move {
             let ping_client = ping_client_factory::new_ping_client(&config.protocol, &config.ping_client_config, external_ping_client_factory);
 
-            let mut worker = PingWorker { id: worker_id, config, stop_event, port_picker, ping_client, result_sender, is_warmup_worker };
+            let mut worker = PingWorker { id: worker_id, config, stop_event, job_picker, ping_client, result_sender, is_warmup_worker };
             worker.run_worker_loop().await;
 
             tracing::debug!("Ping worker loop exited; worker_id={}", worker.id);
@@ -46,13 +50,29 @@ impl PingWorker {
     #[tracing::instrument(name = "Running worker loop", level = "debug", skip(self), fields(worker_id = %self.id))]
     async fn run_worker_loop(&mut self) {
         loop {
-            let source_port = self.port_picker.lock().expect("Failed getting port picker lock").next();
-            match source_port {
-                Some(source_port) => self.run_single_ping(source_port).await,
-                None => {
-                    tracing::debug!("Ping finished, stopping worker; worker_id={}", self.id);
-                    return;
+            let (job, next_job_available_time) = {
+                let mut job_picker = self.job_picker.lock().expect("Failed getting job picker lock");
+                (job_picker.next(), job_picker.next_job_available_time())
+            };
+
+            match job {
+                Some(job) => {
+                    let is_failed = self.run_single_ping(&job).await;
+                    self.job_picker.lock().expect("Failed getting job picker lock").on_job_completed(&job, is_failed);
                 }
+                None => match next_job_available_time {
+                    Some(next_job_available_time) => {
+                        tracing::debug!("Waiting for next job to be available; worker_id={}", self.id);
+                        if !self.wait_for_stop_event(next_job_available_time.saturating_duration_since(Instant::now())).await {
+                            break;
+                        }
+                        continue;
+                    }
+                    None => {
+                        tracing::debug!("Ping finished, stopping worker; worker_id={}", self.id);
+                        return;
+                    }
+                },
             }
 
             if !self.wait_for_next_schedule().await {
@@ -62,63 +82,93 @@ impl PingWorker {
     }
 
     #[tracing::instrument(name = "Running single ping", level = "debug", skip(self), fields(worker_id = %self.id))]
-    async fn run_single_ping(&mut self, source_port: u16) {
-        let source = SocketAddr::new(self.config.source_ip, source_port);
-        let target = self.config.target;
+    async fn run_single_ping(&mut self, job: &PingJob) -> bool {
+        let source = SocketAddr::new(job.source_ip, job.source_port);
+
+        // The flow label is passed to the ping client as the flow info of the target address.
+        let target = match (job.target, job.flow_label) {
+            (SocketAddr::V6(target), Some(flow_label)) => {
+                SocketAddr::V6(SocketAddrV6::new(*target.ip(), target.port(), flow_label, target.scope_id()))
+            }
+            (target, _) => target,
+        };
 
         let ping_time = Utc::now();
         match self.ping_client.prepare_ping(&source).await {
             Err(PingClientError::PreparationFailed(e)) => {
-                self.process_ping_client_error(&ping_time, source_port, PingClientError::PreparationFailed(e)).await
+                self.process_ping_client_error(&ping_time, job, PingClientError::PreparationFailed(e)).await
             }
             Err(_) => panic!("Unexpected failure from prepare_ping! The error type should always be PingClientError::PreparationFailed."),
             Ok(()) => (),
         }
 
-        match self.ping_client.ping(&source, &target).await {
-            Ok(result) => self.process_ping_client_result(&ping_time, source_port, result).await,
-            Err(error) => self.process_ping_client_error(&ping_time, source_port, error).await,
-        }
+        // Returns whether the ping is failed on the path, so the job picker can decide whether to re-probe it.
+        return match self.ping_client.ping(&source, &target).await {
+            Ok(result) => {
+                let is_timeout = result.is_timeout;
+                self.process_ping_client_result(&ping_time, job, result).await;
+                is_timeout
+            }
+            Err(error) => {
+                let is_ping_failed = matches!(error, PingClientError::PingFailed(_));
+                self.process_ping_client_error(&ping_time, job, error).await;
+                is_ping_failed
+            }
+        };
     }
 
     #[tracing::instrument(name = "Processing ping client single ping result", level = "debug", skip(self), fields(worker_id = %self.id))]
-    async fn process_ping_client_result(&self, ping_time: &DateTime<Utc>, src_port: u16, ping_result: PingClientPingResultDetails) {
-        let mut source: Option<SocketAddr> = ping_result.actual_local_addr;
+    async fn process_ping_client_result(&self, ping_time: &DateTime<Utc>, job: &PingJob, mut ping_result: PingClientPingResultDetails) {
+        self.add_packet_marking_to_extra_details(job, &mut ping_result.extra_details);
 
-        if source.is_none() {
-            source = Some(SocketAddr::new(self.config.source_ip, src_port));
-        }
+        let source = SocketAddr::new(job.source_ip, job.source_port);
 
         let result = PingResult::new(
             ping_time,
             self.id,
             self.ping_client.protocol(),
-            self.config.target,
-            source.unwrap(),
+            job.target,
+            source,
             self.is_warmup_worker,
+            job.reprobe_index > 0,
             !ping_result.is_timeout,
             ping_result.round_trip_time,
+            ping_result.handshake_time,
+            ping_result.status_code,
+            ping_result.time_to_first_byte,
+            ping_result.total_time,
+            ping_result.extra_details,
             ping_result.is_timeout,
             ping_result.warning,
             None,
-        );
+        )
+        .with_local_addr(ping_result.actual_local_addr);
 
         self.result_sender.send(result).unwrap();
     }
 
     #[tracing::instrument(name = "Processing ping client single ping error", level = "debug", skip(self), fields(worker_id = %self.id))]
-    async fn process_ping_client_error(&self, ping_time: &DateTime<Utc>, src_port: u16, error: PingClientError) {
-        let source = SocketAddr::new(self.config.source_ip, src_port);
+    async fn process_ping_client_error(&self, ping_time: &DateTime<Utc>, job: &PingJob, error: PingClientError) {
+        let source = SocketAddr::new(job.source_ip, job.source_port);
+
+        let mut extra_details = PingResultExtraDetails::new();
+        self.add_packet_marking_to_extra_details(job, &mut extra_details);
 
         let result = PingResult::new(
             ping_time,
             self.id,
             self.ping_client.protocol(),
-            self.config.target,
+            job.target,
             source,
             self.is_warmup_worker,
+            job.reprobe_index > 0,
             false,
             Duration::from_millis(0),
+            None,
+            None,
+            None,
+            None,
+            extra_details,
             false,
             None,
             Some(error),
@@ -127,10 +177,28 @@ impl PingWorker {
         self.result_sender.send(result).unwrap();
     }
 
+    fn add_packet_marking_to_extra_details(&self, job: &PingJob, extra_details: &mut PingResultExtraDetails) {
+        if let Some(flow_label) = job.flow_label {
+            extra_details.insert(PING_RESULT_EXTRA_DETAIL_FLOW_LABEL.to_string(), flow_label.to_string());
+        }
+
+        // quinn overrides the socket TOS with the ECN bits on every datagram it sends, so QUIC pings never carry it.
+        if self.config.protocol == RnpSupportedProtocol::QUIC {
+            return;
+        }
+
+        if let Some(type_of_service) = self.config.ping_client_config.type_of_service {
+            extra_details.insert(PING_RESULT_EXTRA_DETAIL_TYPE_OF_SERVICE.to_string(), type_of_service.to_string());
+        }
+    }
+
     #[tracing::instrument(name = "Waiting for next schedule", level = "debug", skip(self), fields(worker_id = %self.id))]
     async fn wait_for_next_schedule(&self) -> bool {
-        let ping_interval = self.config.ping_interval;
-        let result = tokio::time::timeout(ping_interval, self.stop_event.wait()).await;
+        return self.wait_for_stop_event(self.config.ping_interval).await;
+    }
+
+    async fn wait_for_stop_event(&self, wait_time: Duration) -> bool {
+        let result = tokio::time::timeout(wait_time, self.stop_event.wait()).await;
 
         // Wait succedded, which means we are signaled to exit.
         if let Ok(_) = result {
@@ -1,8 +1,12 @@
+use crate::rnp_utils::{parse_duration, parse_ping_targets, parse_source_ips};
 use num::One;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::iter::Sum;
+use std::net::{IpAddr, SocketAddr};
 use std::ops::{Add, RangeInclusive, Sub};
 use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RangeListInclusive<Idx> {
@@ -13,6 +17,19 @@ impl<Idx: Copy + PartialOrd<Idx> + Add<Output = Idx> + Sub<Output = Idx> + One +
     pub fn calculate_total_port_count(&self) -> Idx {
         return self.ranges.iter().map(|r| *r.end() - *r.start() + One::one()).sum();
     }
+
+    /// Create the range list from sorted values, with the consecutive values merged into ranges.
+    pub fn from_values(values: &[Idx]) -> RangeListInclusive<Idx> {
+        let mut ranges: Vec<RangeInclusive<Idx>> = Vec::new();
+        for value in values {
+            match ranges.last_mut() {
+                Some(last_range) if *last_range.end() + One::one() == *value => *last_range = *last_range.start()..=*value,
+                _ => ranges.push(*value..=*value),
+            }
+        }
+
+        return RangeListInclusive { ranges };
+    }
 }
 
 impl<Idx: Copy + FromStr> FromStr for RangeListInclusive<Idx> {
@@ -69,8 +86,130 @@ impl<Idx: fmt::Display + PartialEq> fmt::Display for RangeListInclusive<Idx> {
     }
 }
 
+// Range lists are saved in the same format as the command line, e.g. "1024,10000-11000".
+impl<Idx: fmt::Display + PartialEq> Serialize for RangeListInclusive<Idx> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, Idx: Copy + FromStr> Deserialize<'de> for RangeListInclusive<Idx> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
 pub type PortRangeList = RangeListInclusive<u16>;
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingTargetList {
+    pub targets: Vec<SocketAddr>,
+}
+
+impl FromStr for PingTargetList {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PingTargetList, Self::Err> {
+        return Ok(PingTargetList { targets: parse_ping_targets(input)? });
+    }
+}
+
+impl fmt::Display for PingTargetList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let targets: Vec<String> = self.targets.iter().map(|target| target.to_string()).collect();
+        write!(f, "{}", targets.join(","))
+    }
+}
+
+impl Serialize for PingTargetList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PingTargetList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingSourceIpList {
+    pub ips: Vec<IpAddr>,
+}
+
+impl FromStr for PingSourceIpList {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PingSourceIpList, Self::Err> {
+        return Ok(PingSourceIpList { ips: parse_source_ips(input)? });
+    }
+}
+
+impl fmt::Display for PingSourceIpList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ips: Vec<String> = self.ips.iter().map(|ip| ip.to_string()).collect();
+        write!(f, "{}", ips.join(","))
+    }
+}
+
+impl Serialize for PingSourceIpList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PingSourceIpList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PingRunDuration {
+    pub duration: Duration,
+}
+
+impl FromStr for PingRunDuration {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PingRunDuration, Self::Err> {
+        return Ok(PingRunDuration { duration: parse_duration(input)? });
+    }
+}
+
+impl fmt::Display for PingRunDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Write the duration with the same units as parsing, e.g. 5400s as "1h30m", so it can be parsed back.
+        let units: [(&str, u128); 5] = [("d", 24 * 60 * 60 * 1000), ("h", 60 * 60 * 1000), ("m", 60 * 1000), ("s", 1000), ("ms", 1)];
+        let mut remaining_in_ms = self.duration.as_millis();
+        if remaining_in_ms == 0 {
+            return write!(f, "0s");
+        }
+
+        for (unit, unit_in_ms) in units {
+            if remaining_in_ms >= unit_in_ms {
+                write!(f, "{}{}", remaining_in_ms / unit_in_ms, unit)?;
+                remaining_in_ms %= unit_in_ms;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for PingRunDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PingRunDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +247,35 @@ mod tests {
         assert_eq!("1,2,5-6", RangeListInclusive { ranges: vec![(1..=1), (2..=2), (5..=6)] }.to_string());
         assert_eq!("1,2,5-6,100-200", RangeListInclusive { ranges: vec![(1..=1), (2..=2), (5..=6), (100..=200)] }.to_string());
     }
+
+    #[test]
+    fn range_list_from_values_should_work() {
+        assert_eq!(RangeListInclusive { ranges: Vec::<RangeInclusive<u16>>::new() }, RangeListInclusive::<u16>::from_values(&[]));
+        assert_eq!(RangeListInclusive { ranges: vec![(1..=3), (5..=5), (7..=8)] }, RangeListInclusive::<u16>::from_values(&[1, 2, 3, 5, 7, 8]));
+    }
+
+    #[test]
+    fn ping_target_list_to_string_should_work() {
+        let targets = PingTargetList { targets: vec!["10.0.0.1:443".parse().unwrap(), "[2001:db8::1]:80".parse().unwrap()] };
+        assert_eq!("10.0.0.1:443,[2001:db8::1]:80", targets.to_string());
+        assert_eq!(targets, targets.to_string().parse::<PingTargetList>().unwrap());
+    }
+
+    #[test]
+    fn ping_source_ip_list_to_string_should_work() {
+        let source_ips = PingSourceIpList { ips: vec!["10.0.0.2".parse().unwrap(), "2001:db8::2".parse().unwrap()] };
+        assert_eq!("10.0.0.2,2001:db8::2", source_ips.to_string());
+        assert_eq!(source_ips, source_ips.to_string().parse::<PingSourceIpList>().unwrap());
+    }
+
+    #[test]
+    fn ping_run_duration_to_string_should_work() {
+        assert_eq!("500ms", PingRunDuration { duration: Duration::from_millis(500) }.to_string());
+        assert_eq!("10m", PingRunDuration { duration: Duration::from_secs(600) }.to_string());
+        assert_eq!("1d1h30m1s500ms", PingRunDuration { duration: Duration::from_millis(91_801_500) }.to_string());
+
+        let duration = PingRunDuration { duration: Duration::from_secs(5400) };
+        assert_eq!("1h30m", duration.to_string());
+        assert_eq!(duration, duration.to_string().parse::<PingRunDuration>().unwrap());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::{PingClientFactory, PingResultDto, PingResultProcessor, PortRangeList};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::fmt::Debug;
 use std::net::{IpAddr, SocketAddr};
@@ -14,7 +15,10 @@ pub const RNP_ABOUT: &str = "A simple layer 4 ping tool for cloud.";
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum RnpSupportedProtocol {
     TCP,
+    UDP,
     QUIC,
+    TLS,
+    HTTP,
     External(String),
 }
 
@@ -24,7 +28,10 @@ impl FromStr for RnpSupportedProtocol {
     fn from_str(input: &str) -> Result<RnpSupportedProtocol, Self::Err> {
         match input.to_uppercase().as_str() {
             "TCP" => Ok(RnpSupportedProtocol::TCP),
+            "UDP" => Ok(RnpSupportedProtocol::UDP),
             "QUIC" => Ok(RnpSupportedProtocol::QUIC),
+            "TLS" => Ok(RnpSupportedProtocol::TLS),
+            "HTTP" => Ok(RnpSupportedProtocol::HTTP),
             _ => Err(String::from("Invalid protocol")),
         }
     }
@@ -34,7 +41,10 @@ impl fmt::Display for RnpSupportedProtocol {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let protocol = match self {
             RnpSupportedProtocol::TCP => "TCP",
+            RnpSupportedProtocol::UDP => "UDP",
             RnpSupportedProtocol::QUIC => "QUIC",
+            RnpSupportedProtocol::TLS => "TLS",
+            RnpSupportedProtocol::HTTP => "HTTP",
             RnpSupportedProtocol::External(p) => &p,
         };
 
@@ -42,11 +52,149 @@ impl fmt::Display for RnpSupportedProtocol {
     }
 }
 
+impl Serialize for RnpSupportedProtocol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RnpSupportedProtocol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// How the source ports are picked from the port ranges. Sequential pings put neighbouring ports through the middle
+/// boxes back to back and correlate the port number with time, so the other strategies help spreading them out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingPortPickingStrategy {
+    /// Walk through the ports in order.
+    Sequential,
+
+    /// Pick a random port for each ping, so the same port can be picked again before all ports are used.
+    Random,
+
+    /// Walk through all ports in a random order, which is changed in every cycle.
+    Shuffle,
+
+    /// Walk through the ports with the specified step. When the step shares a factor with the port count, the walk is
+    /// shifted by 1 port after each loop, so all ports are still used once per cycle.
+    Stride(u32),
+}
+
+impl FromStr for PingPortPickingStrategy {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PingPortPickingStrategy, Self::Err> {
+        let input = input.to_lowercase();
+        match input.as_str() {
+            "sequential" => return Ok(PingPortPickingStrategy::Sequential),
+            "random" => return Ok(PingPortPickingStrategy::Random),
+            "shuffle" => return Ok(PingPortPickingStrategy::Shuffle),
+            _ => (),
+        }
+
+        let step = input.strip_prefix("stride:").ok_or_else(|| String::from("Invalid port picking strategy"))?;
+        return match step.parse::<u32>() {
+            Ok(step) if step > 0 => Ok(PingPortPickingStrategy::Stride(step)),
+            _ => Err(format!("Invalid stride step: {}", step)),
+        };
+    }
+}
+
+impl fmt::Display for PingPortPickingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PingPortPickingStrategy::Sequential => write!(f, "sequential"),
+            PingPortPickingStrategy::Random => write!(f, "random"),
+            PingPortPickingStrategy::Shuffle => write!(f, "shuffle"),
+            PingPortPickingStrategy::Stride(step) => write!(f, "stride:{}", step),
+        }
+    }
+}
+
+impl Serialize for PingPortPickingStrategy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PingPortPickingStrategy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+// Flow label is 20 bits, but Linux reserves the upper half for the stateless flow labels, which cannot be requested from
+// the flow label manager by default (net.ipv6.flowlabel_state_ranges), so only the lower half can be used.
+pub const PING_FLOW_LABEL_MAX: u32 = 0x7FFFF;
+
+/// IPv6 flow label of the pings. ECMP can hash on the flow label for IPv6, so rotating it scans the paths the same way
+/// as rotating the source ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingFlowLabel {
+    /// Use the same flow label in all pings.
+    Fixed(u32),
+
+    /// Use a different flow label in each ping. Re-probes use the same flow label as the failed ping.
+    Rotate,
+}
+
+impl FromStr for PingFlowLabel {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PingFlowLabel, Self::Err> {
+        let input = input.to_lowercase();
+        if input == "rotate" {
+            return Ok(PingFlowLabel::Rotate);
+        }
+
+        let flow_label = match input.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => input.parse::<u32>(),
+        };
+
+        // 0 means no flow label.
+        return match flow_label {
+            Ok(flow_label) if flow_label > 0 && flow_label <= PING_FLOW_LABEL_MAX => Ok(PingFlowLabel::Fixed(flow_label)),
+            _ => Err(format!(
+                "Invalid flow label: {}. It should be \"rotate\" or a number between 1 and {:#x}, since the labels above it are reserved for stateless flow labels on Linux.",
+                input, PING_FLOW_LABEL_MAX
+            )),
+        };
+    }
+}
+
+impl fmt::Display for PingFlowLabel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PingFlowLabel::Fixed(flow_label) => write!(f, "{}", flow_label),
+            PingFlowLabel::Rotate => write!(f, "rotate"),
+        }
+    }
+}
+
+impl Serialize for PingFlowLabel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PingFlowLabel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+// Ping client factory and result processors are code instead of settings, so they are left out when saving the config.
+#[derive(Serialize, Deserialize)]
 pub struct RnpPingRunnerConfig {
     pub worker_config: PingWorkerConfig,
     pub worker_scheduler_config: PingWorkerSchedulerConfig,
     pub result_processor_config: PingResultProcessorConfig,
+    #[serde(skip)]
     pub external_ping_client_factory: Option<PingClientFactory>,
+    #[serde(skip)]
     pub extra_ping_result_processors: Vec<Box<dyn PingResultProcessor + Send + Sync>>,
 }
 
@@ -86,19 +234,23 @@ impl PartialEq for RnpPingRunnerConfig {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PingWorkerConfig {
     pub protocol: RnpSupportedProtocol,
-    pub target: SocketAddr,
-    pub source_ip: IpAddr,
+    pub targets: Vec<SocketAddr>,
+    pub source_ips: Vec<IpAddr>,
     pub ping_interval: Duration,
     pub ping_client_config: PingClientConfig,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PingClientConfig {
     pub wait_timeout: Duration,
     pub time_to_live: Option<u32>,
+    pub flow_label: Option<PingFlowLabel>,
+    pub type_of_service: Option<u8>,
+    pub interface: Option<String>,
+    pub fwmark: Option<u32>,
     pub check_disconnect: bool,
     pub wait_before_disconnect: Duration,
     pub disconnect_timeout: Duration,
@@ -106,17 +258,31 @@ pub struct PingClientConfig {
     pub log_tls_key: bool,
     pub alpn_protocol: Option<String>,
     pub use_timer_rtt: bool,
+    pub http_method: String,
+    pub http_path: String,
+    pub http_host: Option<String>,
+    pub http_headers: Vec<String>,
+    pub http_expected_status_codes: Vec<u16>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PingWorkerSchedulerConfig {
     pub source_ports: PortRangeList,
+    pub port_picking_strategy: PingPortPickingStrategy,
+    pub port_cooldown: Duration,
+    pub use_port_cross_product: bool,
     pub ping_count: Option<u32>,
+    pub duration: Option<Duration>,
     pub warmup_count: u32,
     pub parallel_ping_count: u32,
+    pub reprobe_count: u32,
+    pub reprobe_interval: Duration,
+    pub ping_rate: Option<f64>,
+    pub ping_rate_jitter: f64,
+    pub use_poisson_arrival: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PingResultProcessorCommonConfig {
     pub quiet_level: i32,
 }
@@ -126,17 +292,21 @@ pub const RNP_QUIET_LEVEL_NO_PING_RESULT: i32 = 1;
 pub const RNP_QUIET_LEVEL_NO_PING_SUMMARY: i32 = 2;
 pub const RNP_QUIET_LEVEL_NO_OUTPUT: i32 = 3;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PingResultProcessorConfig {
     pub common_config: PingResultProcessorCommonConfig,
     pub exit_on_fail: bool,
+    #[serde(skip)]
     pub exit_failure_reason: Option<Arc<Mutex<Option<PingResultDto>>>>,
     pub csv_log_path: Option<PathBuf>,
     pub json_log_path: Option<PathBuf>,
+    pub json_summary_log_path: Option<PathBuf>,
     pub text_log_path: Option<PathBuf>,
     pub show_result_scatter: bool,
     pub show_latency_scatter: bool,
+    pub show_bad_paths: bool,
     pub latency_buckets: Option<Vec<f64>>,
+    pub metrics_address: Option<SocketAddr>,
 }
 
 impl PartialEq for PingResultProcessorConfig {
@@ -156,6 +326,9 @@ impl PartialEq for PingResultProcessorConfig {
         if self.json_log_path != other.json_log_path {
             return false;
         }
+        if self.json_summary_log_path != other.json_summary_log_path {
+            return false;
+        }
         if self.text_log_path != other.text_log_path {
             return false;
         }
@@ -165,14 +338,20 @@ impl PartialEq for PingResultProcessorConfig {
         if self.show_latency_scatter != other.show_latency_scatter {
             return false;
         }
+        if self.show_bad_paths != other.show_bad_paths {
+            return false;
+        }
         if self.latency_buckets != other.latency_buckets {
             return false;
         }
+        if self.metrics_address != other.metrics_address {
+            return false;
+        }
         return true;
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RnpStubServerConfig {
     pub protocol: RnpSupportedProtocol,
     pub server_address: SocketAddr,
@@ -182,4 +361,47 @@ pub struct RnpStubServerConfig {
     pub write_count_limit: u32,
     pub sleep_before_write: Duration,
     pub wait_before_disconnect: Duration,
+    pub server_certificate_path: Option<PathBuf>,
+    pub server_private_key_path: Option<PathBuf>,
+    pub alpn_protocols: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parsing_flow_label_should_work() {
+        assert_eq!(PingFlowLabel::Fixed(1), "1".parse::<PingFlowLabel>().unwrap());
+        assert_eq!(PingFlowLabel::Fixed(12345), "12345".parse::<PingFlowLabel>().unwrap());
+        assert_eq!(PingFlowLabel::Fixed(12345), "0x3039".parse::<PingFlowLabel>().unwrap());
+        assert_eq!(PingFlowLabel::Fixed(0x7FFFF), "0X7FFFF".parse::<PingFlowLabel>().unwrap());
+        assert_eq!(PingFlowLabel::Rotate, "rotate".parse::<PingFlowLabel>().unwrap());
+        assert_eq!(PingFlowLabel::Rotate, "Rotate".parse::<PingFlowLabel>().unwrap());
+    }
+
+    #[test]
+    fn parsing_invalid_flow_label_should_fail() {
+        assert!("".parse::<PingFlowLabel>().is_err());
+        assert!("0".parse::<PingFlowLabel>().is_err());
+        assert!("0x0".parse::<PingFlowLabel>().is_err());
+        assert!("-1".parse::<PingFlowLabel>().is_err());
+        assert!("0x80000".parse::<PingFlowLabel>().is_err());
+        assert!("0xFFFFF".parse::<PingFlowLabel>().is_err());
+        assert!("1048576".parse::<PingFlowLabel>().is_err());
+        assert!("0xZZ".parse::<PingFlowLabel>().is_err());
+        assert!("random".parse::<PingFlowLabel>().is_err());
+    }
+
+    #[test]
+    fn flow_label_to_string_should_work() {
+        assert_eq!("12345", PingFlowLabel::Fixed(12345).to_string());
+        assert_eq!("rotate", PingFlowLabel::Rotate.to_string());
+
+        // Flow labels are saved in the same format as the command line, so they can be parsed back.
+        for flow_label in [PingFlowLabel::Fixed(1), PingFlowLabel::Fixed(PING_FLOW_LABEL_MAX), PingFlowLabel::Rotate] {
+            assert_eq!(flow_label, flow_label.to_string().parse::<PingFlowLabel>().unwrap());
+        }
+    }
 }
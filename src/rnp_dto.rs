@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::net::IpAddr;
 
-#[derive(Debug, Serialize, Deserialize, PartialOrd, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialOrd, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 pub struct PingResultDto {
     pub utc_time: DateTime<Utc>,
@@ -20,11 +23,30 @@ pub struct PingResultDto {
     pub ping_error: String,
     pub handshake_error: String,
     pub disconnect_error: String,
+    pub handshake_time_in_ms: Option<f64>,
+    pub status_code: Option<u16>,
+    pub ttfb_in_ms: Option<f64>,
+    pub total_time_in_ms: Option<f64>,
+
+    #[serde(default, deserialize_with = "deserialize_extra_details")]
+    pub extra_details: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub error_category: String,
+
+    #[serde(default)]
+    pub is_reprobe: bool,
 }
 
 impl PingResultDto {
     pub fn to_console_log(&self) -> String {
-        let warmup_sign = if self.is_warmup { " (warmup)" } else { "" };
+        let warmup_sign = if self.is_warmup {
+            " (warmup)"
+        } else if self.is_reprobe {
+            " (reprobe)"
+        } else {
+            ""
+        };
 
         if self.is_timed_out {
             return format!(
@@ -47,29 +69,56 @@ impl PingResultDto {
             );
         }
 
+        let mut app_details = self.handshake_time_in_ms.map_or(String::from(""), |t| format!(", Handshake={:.2}ms", t));
+        if let Some(status_code) = self.status_code {
+            app_details += &format!(", Status={}", status_code);
+        }
+        if let Some(ttfb) = self.ttfb_in_ms {
+            app_details += &format!(", TTFB={:.2}ms", ttfb);
+        }
+        if let Some(total_time) = self.total_time_in_ms {
+            app_details += &format!(", Total={:.2}ms", total_time);
+        }
+
         if !self.handshake_error.is_empty() {
             return format!(
-                "Reaching {} {}:{} from {}:{}{} succeeded, but app handshake failed: RTT={:.2}ms, Error = {}",
-                self.protocol, self.target_ip, self.target_port, self.source_ip, self.source_port, warmup_sign, self.rtt_in_ms, self.handshake_error,
+                "Reaching {} {}:{} from {}:{}{} succeeded, but app handshake failed: RTT={:.2}ms{}, Error = {}",
+                self.protocol,
+                self.target_ip,
+                self.target_port,
+                self.source_ip,
+                self.source_port,
+                warmup_sign,
+                self.rtt_in_ms,
+                app_details,
+                self.handshake_error,
             );
         }
 
         if !self.disconnect_error.is_empty() {
             return format!(
-                "Reaching {} {}:{} from {}:{}{} succeeded, but disconnect failed: RTT={:.2}ms, Error = {}",
-                self.protocol, self.target_ip, self.target_port, self.source_ip, self.source_port, warmup_sign, self.rtt_in_ms, self.disconnect_error,
+                "Reaching {} {}:{} from {}:{}{} succeeded, but disconnect failed: RTT={:.2}ms{}, Error = {}",
+                self.protocol,
+                self.target_ip,
+                self.target_port,
+                self.source_ip,
+                self.source_port,
+                warmup_sign,
+                self.rtt_in_ms,
+                app_details,
+                self.disconnect_error,
             );
         }
 
         return format!(
-            "Reaching {} {}:{} from {}:{}{} succeeded: RTT={:.2}ms",
-            self.protocol, self.target_ip, self.target_port, self.source_ip, self.source_port, warmup_sign, self.rtt_in_ms,
+            "Reaching {} {}:{} from {}:{}{} succeeded: RTT={:.2}ms{}",
+            self.protocol, self.target_ip, self.target_port, self.source_ip, self.source_port, warmup_sign, self.rtt_in_ms, app_details,
         );
     }
 
     pub fn to_json_lite(&self) -> String {
         format!(
-            "{{\"UtcTime\":\"{:?}\",\"WorkerId\":{},\"Protocol\":\"{}\",\"TargetIp\":\"{}\",\"TargetPort\":{},\"SourceIp\":\"{}\",\"SourcePort\":{},\"IsWarmup\":{},\"IsSucceeded\":{},\"RttInMs\":{:.2},\"IsTimedOut\":{},\"PreparationError\":\"{}\",\"PingError\":\"{}\",\"HandshakeError\":\"{}\",\"DisconnectError\":\"{}\"}}",
+            "{{\"UtcTime\":\"{:?}\",\"WorkerId\":{},\"Protocol\":\"{}\",\"TargetIp\":\"{}\",\"TargetPort\":{},\"SourceIp\":\"{}\",\"SourcePort\":{},\"IsWarmup\":{},\"IsSucceeded\":{},\"RttInMs\":{:.2},\"IsTimedOut\":{},\"PreparationError\":\"{}\",\"PingError\":\"{}\",\"HandshakeError\":\"{}\",\"DisconnectError\":\"{}\",\"HandshakeTimeInMs\":{},\"StatusCode\":{},\"TtfbInMs\":{},\"TotalTimeInMs\":{},\"ExtraDetails\":{{{}}},\"ErrorCategory\":\"{}\",\"IsReprobe\":{}}}",
             self.utc_time,
             self.worker_id,
             self.protocol,
@@ -85,12 +134,19 @@ impl PingResultDto {
             self.ping_error,
             self.handshake_error,
             self.disconnect_error,
+            self.handshake_time_in_ms.map_or(String::from("null"), |t| format!("{:.2}", t)),
+            self.status_code.map_or(String::from("null"), |c| c.to_string()),
+            self.ttfb_in_ms.map_or(String::from("null"), |t| format!("{:.2}", t)),
+            self.total_time_in_ms.map_or(String::from("null"), |t| format!("{:.2}", t)),
+            self.extra_details.iter().map(|(key, value)| format!("\"{}\":\"{}\"", key, value)).collect::<Vec<String>>().join(","),
+            self.error_category,
+            self.is_reprobe,
         )
     }
 
     pub fn to_csv_lite(&self) -> String {
         format!(
-            "{:?},{},{},{},{},{},{},{},{},{:.2},{},\"{}\",\"{}\",\"{}\",\"{}\"",
+            "{:?},{},{},{},{},{},{},{},{},{:.2},{},\"{}\",\"{}\",\"{}\",\"{}\",{},{},{},{},\"{}\",{},{}",
             self.utc_time,
             self.worker_id,
             self.protocol,
@@ -106,6 +162,138 @@ impl PingResultDto {
             self.ping_error,
             self.handshake_error,
             self.disconnect_error,
+            self.handshake_time_in_ms.map_or(String::from(""), |t| format!("{:.2}", t)),
+            self.status_code.map_or(String::from(""), |c| c.to_string()),
+            self.ttfb_in_ms.map_or(String::from(""), |t| format!("{:.2}", t)),
+            self.total_time_in_ms.map_or(String::from(""), |t| format!("{:.2}", t)),
+            self.extra_details.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<String>>().join(";"),
+            self.error_category,
+            self.is_reprobe,
+        )
+    }
+}
+
+// Extra details are saved as an object in json, but as a single "Key=Value;Key=Value" column in csv, so we need to
+// handle both when loading them back.
+fn deserialize_extra_details<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error> {
+    struct ExtraDetailsVisitor;
+
+    impl<'de> Visitor<'de> for ExtraDetailsVisitor {
+        type Value = BTreeMap<String, String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map or a string of key=value pairs separated by ;")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            let mut extra_details = BTreeMap::new();
+            for detail in value.split(';').filter(|detail| !detail.is_empty()) {
+                match detail.split_once('=') {
+                    Some((key, value)) => extra_details.insert(key.to_string(), value.to_string()),
+                    None => return Err(E::custom(format!("Invalid extra detail \"{}\", expecting key=value.", detail))),
+                };
+            }
+
+            return Ok(extra_details);
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut extra_details = BTreeMap::new();
+            while let Some((key, value)) = map.next_entry()? {
+                extra_details.insert(key, value);
+            }
+
+            return Ok(extra_details);
+        }
+    }
+
+    deserializer.deserialize_any(ExtraDetailsVisitor)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialOrd, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PingResultStatisticsDto {
+    pub target: Option<String>,
+
+    #[serde(default)]
+    pub source_ip: Option<String>,
+
+    pub ping_count: u32,
+    pub success_count: u32,
+    pub failure_count: u32,
+    pub handshake_failed_count: u32,
+    pub disconnect_failed_count: u32,
+    pub syn_retransmitted_count: u32,
+    pub latency_sample_count: u64,
+    pub min_latency_in_ms: f64,
+    pub max_latency_in_ms: f64,
+    pub average_latency_in_ms: f64,
+    pub std_dev_latency_in_ms: f64,
+    pub jitter_in_ms: f64,
+    pub p50_latency_in_ms: f64,
+    pub p90_latency_in_ms: f64,
+    pub p95_latency_in_ms: f64,
+    pub p99_latency_in_ms: f64,
+    pub p999_latency_in_ms: f64,
+}
+
+impl PingResultStatisticsDto {
+    pub fn to_json_lite(&self) -> String {
+        format!(
+            "{{\"Target\":{},\"SourceIp\":{},\"PingCount\":{},\"SuccessCount\":{},\"FailureCount\":{},\"HandshakeFailedCount\":{},\"DisconnectFailedCount\":{},\"SynRetransmittedCount\":{},\"LatencySampleCount\":{},\"MinLatencyInMs\":{:.3},\"MaxLatencyInMs\":{:.3},\"AverageLatencyInMs\":{:.3},\"StdDevLatencyInMs\":{:.3},\"JitterInMs\":{:.3},\"P50LatencyInMs\":{:.3},\"P90LatencyInMs\":{:.3},\"P95LatencyInMs\":{:.3},\"P99LatencyInMs\":{:.3},\"P999LatencyInMs\":{:.3}}}",
+            self.target.as_ref().map_or(String::from("null"), |t| format!("\"{}\"", t)),
+            self.source_ip.as_ref().map_or(String::from("null"), |ip| format!("\"{}\"", ip)),
+            self.ping_count,
+            self.success_count,
+            self.failure_count,
+            self.handshake_failed_count,
+            self.disconnect_failed_count,
+            self.syn_retransmitted_count,
+            self.latency_sample_count,
+            self.min_latency_in_ms,
+            self.max_latency_in_ms,
+            self.average_latency_in_ms,
+            self.std_dev_latency_in_ms,
+            self.jitter_in_ms,
+            self.p50_latency_in_ms,
+            self.p90_latency_in_ms,
+            self.p95_latency_in_ms,
+            self.p99_latency_in_ms,
+            self.p999_latency_in_ms,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialOrd, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PingRunStatisticsDto {
+    pub protocol: String,
+    pub targets: Vec<PingResultStatisticsDto>,
+
+    #[serde(default)]
+    pub source_ips: Vec<PingResultStatisticsDto>,
+
+    pub total: PingResultStatisticsDto,
+
+    #[serde(default)]
+    pub elapsed_time_in_ms: f64,
+
+    #[serde(default)]
+    pub achieved_ping_rate: f64,
+}
+
+impl PingRunStatisticsDto {
+    pub fn to_json_lite(&self) -> String {
+        let targets = self.targets.iter().map(|t| format!("\n    {}", t.to_json_lite())).collect::<Vec<String>>().join(",");
+        let source_ips = self.source_ips.iter().map(|s| format!("\n    {}", s.to_json_lite())).collect::<Vec<String>>().join(",");
+        format!(
+            "{{\n  \"Protocol\":\"{}\",\n  \"ElapsedTimeInMs\":{:.3},\n  \"AchievedPingRate\":{:.3},\n  \"Targets\":[{}\n  ],\n  \"SourceIps\":[{}\n  ],\n  \"Total\":{}\n}}\n",
+            self.protocol,
+            self.elapsed_time_in_ms,
+            self.achieved_ping_rate,
+            targets,
+            source_ips,
+            self.total.to_json_lite()
         )
     }
 }
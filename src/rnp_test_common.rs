@@ -22,8 +22,14 @@ pub fn generate_ping_result_test_samples() -> Vec<PingResult> {
             "1.2.3.4:443".parse().unwrap(),
             "5.6.7.8:8080".parse().unwrap(),
             true,
+            false,
             true,
             Duration::from_millis(10),
+            None,
+            Some(200),
+            Some(Duration::from_millis(15)),
+            Some(Duration::from_millis(18)),
+            create_tcp_info_extra_details_test_sample(),
             false,
             None,
             None,
@@ -37,7 +43,13 @@ pub fn generate_ping_result_test_samples() -> Vec<PingResult> {
             "5.6.7.8:8080".parse().unwrap(),
             false,
             false,
+            false,
             Duration::from_millis(1000),
+            None,
+            None,
+            None,
+            None,
+            PingResultExtraDetails::new(),
             true,
             None,
             None,
@@ -50,8 +62,14 @@ pub fn generate_ping_result_test_samples() -> Vec<PingResult> {
             "1.2.3.4:443".parse().unwrap(),
             "5.6.7.8:8080".parse().unwrap(),
             false,
+            false,
             true,
             Duration::from_millis(20),
+            Some(Duration::from_millis(5)),
+            None,
+            None,
+            None,
+            PingResultExtraDetails::new(),
             false,
             Some(PingClientWarning::AppHandshakeFailed(Box::new(io::Error::new(io::ErrorKind::ConnectionAborted, "connect aborted")))),
             None,
@@ -64,8 +82,14 @@ pub fn generate_ping_result_test_samples() -> Vec<PingResult> {
             "1.2.3.4:443".parse().unwrap(),
             "5.6.7.8:8080".parse().unwrap(),
             false,
+            false,
             true,
             Duration::from_millis(20),
+            None,
+            None,
+            None,
+            None,
+            PingResultExtraDetails::new(),
             false,
             Some(PingClientWarning::DisconnectFailed(Box::new(io::Error::new(io::ErrorKind::TimedOut, "disconnect timeout")))),
             None,
@@ -79,7 +103,13 @@ pub fn generate_ping_result_test_samples() -> Vec<PingResult> {
             "5.6.7.8:8080".parse().unwrap(),
             false,
             false,
+            false,
             Duration::from_millis(0),
+            None,
+            None,
+            None,
+            None,
+            PingResultExtraDetails::new(),
             false,
             None,
             Some(PingClientError::PingFailed(Box::new(io::Error::new(io::ErrorKind::ConnectionRefused, "connect failed")))),
@@ -93,10 +123,46 @@ pub fn generate_ping_result_test_samples() -> Vec<PingResult> {
             "5.6.7.8:8080".parse().unwrap(),
             false,
             false,
+            false,
             Duration::from_millis(0),
+            None,
+            None,
+            None,
+            None,
+            PingResultExtraDetails::new(),
             false,
             None,
             Some(PingClientError::PreparationFailed(Box::new(io::Error::new(io::ErrorKind::AddrInUse, "address in use")))),
         ),
     ]
 }
+
+// Re-probe of the timed out ping in the samples above, which goes to the same source port again.
+pub fn generate_reprobe_ping_result_test_sample() -> PingResult {
+    return PingResult::new(
+        &Utc.ymd(2021, 7, 6).and_hms_milli(9, 10, 12, 12),
+        1,
+        "TCP",
+        "1.2.3.4:443".parse().unwrap(),
+        "5.6.7.8:8080".parse().unwrap(),
+        false,
+        true,
+        false,
+        Duration::from_millis(1000),
+        None,
+        None,
+        None,
+        None,
+        PingResultExtraDetails::new(),
+        true,
+        None,
+        None,
+    );
+}
+
+fn create_tcp_info_extra_details_test_sample() -> PingResultExtraDetails {
+    let mut extra_details = PingResultExtraDetails::new();
+    extra_details.insert(PING_RESULT_EXTRA_DETAIL_TCP_MSS.to_string(), "1460".to_string());
+    extra_details.insert(PING_RESULT_EXTRA_DETAIL_TCP_SYN_RETRANSMITS.to_string(), "0".to_string());
+    return extra_details;
+}
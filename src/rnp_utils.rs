@@ -1,7 +1,17 @@
+use crate::PortRangeList;
 use std::fs::{self, File};
-use std::net::{IpAddr, SocketAddr};
-use std::path::PathBuf;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+
+// Expanding a large CIDR or port range by mistake can easily generate millions of targets, so we put a limit here.
+pub const RNP_MAX_PING_TARGET_COUNT: u128 = 65536;
+pub const RNP_MAX_SOURCE_IP_COUNT: u128 = 1024;
+
+// The time between pings is 1 / rate, which cannot be represented as a duration when the rate is too small, so we reject
+// the rates slower than 1 ping every 1000 seconds.
+pub const RNP_MIN_PING_RATE: f64 = 0.001;
 
 pub fn create_log_file(log_path_buf: &PathBuf) -> File {
     let log_path = log_path_buf.as_path();
@@ -93,6 +103,298 @@ pub fn parse_ping_target(input: &str) -> Result<SocketAddr, String> {
     return Ok(SocketAddr::new(ip, port));
 }
 
+/// Parse a list of ping targets separated by comma. Besides the format supported by `parse_ping_target`, each target can
+/// also be a CIDR or use a port range, such as `10.0.0.0/28:443`, `[2001:db8::/120]:443` or `10.0.0.1:8080-8090`.
+pub fn parse_ping_targets(input: &str) -> Result<Vec<SocketAddr>, String> {
+    let mut targets = Vec::new();
+
+    for target_str in input.split(',') {
+        let target_str = target_str.trim();
+        if target_str.len() == 0 {
+            return Err(format!("Empty ping target found in \"{}\"", input));
+        }
+
+        let (ip_str, port_str) = split_ping_target_ip_and_port(target_str)?;
+        let (ips, ip_count) = parse_ping_target_ips(ip_str, target_str)?;
+        let ports = match port_str {
+            Some(port_str) => PortRangeList::from_str(port_str)
+                .map_err(|_| format!("Invalid port \"{}\" found in ping target \"{}\"", port_str, target_str))?
+                .ranges
+                .remove(0),
+            None => 80..=80,
+        };
+
+        if ports.start() > ports.end() {
+            return Err(format!("Invalid port range \"{}\" found in ping target \"{}\"", port_str.unwrap(), target_str));
+        }
+
+        let target_count = ip_count * (*ports.end() as u128 - *ports.start() as u128 + 1) + targets.len() as u128;
+        if target_count > RNP_MAX_PING_TARGET_COUNT {
+            return Err(format!(
+                "Too many ping targets specified in \"{}\". At most {} targets can be pinged at the same time.",
+                input, RNP_MAX_PING_TARGET_COUNT
+            ));
+        }
+
+        for ip in ips {
+            for port in ports.clone() {
+                targets.push(SocketAddr::new(ip, port));
+            }
+        }
+    }
+
+    return Ok(targets);
+}
+
+/// Load ping targets from file. Each line can contain any targets supported by `parse_ping_targets`. Empty lines and
+/// anything after `#` are ignored.
+pub fn load_ping_targets_from_file(path: &Path) -> Result<Vec<SocketAddr>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read targets file \"{}\": {}", path.display(), e))?;
+
+    let mut targets = Vec::new();
+    for line in content.lines() {
+        let line = match line.find('#') {
+            Some(comment_index) => &line[..comment_index],
+            None => line,
+        };
+
+        if line.trim().len() == 0 {
+            continue;
+        }
+
+        targets.extend(parse_ping_targets(line)?);
+        if targets.len() as u128 > RNP_MAX_PING_TARGET_COUNT {
+            return Err(format!(
+                "Too many ping targets specified in \"{}\". At most {} targets can be pinged at the same time.",
+                path.display(),
+                RNP_MAX_PING_TARGET_COUNT
+            ));
+        }
+    }
+
+    return Ok(targets);
+}
+
+// Split the ping target into IP (or CIDR) and port (or port range) part, e.g. "[::1/120]:80-81" => ("[::1/120]", "80-81").
+fn split_ping_target_ip_and_port(input: &str) -> Result<(&str, Option<&str>), String> {
+    let port_separator_index = match input.rfind(']') {
+        Some(last_bracket_index) => match &input[last_bracket_index + 1..] {
+            "" => None,
+            port_part if port_part.starts_with(':') => Some(last_bracket_index + 1),
+            _ => return Err(format!("Invalid ping target \"{}\"", input)),
+        },
+        None => input.rfind(':'),
+    };
+
+    return match port_separator_index {
+        // Port colon without port defaults to 80, which is the same as parse_ping_target.
+        Some(port_separator_index) if port_separator_index + 1 == input.len() => Ok((&input[..port_separator_index], None)),
+        Some(port_separator_index) => Ok((&input[..port_separator_index], Some(&input[port_separator_index + 1..]))),
+        None => Ok((input, None)),
+    };
+}
+
+fn parse_ping_target_ips(ip_str: &str, input: &str) -> Result<(Box<dyn Iterator<Item = IpAddr>>, u128), String> {
+    let (ip_str, prefix_len_str) = match ip_str.split_once('/') {
+        // For IPv6, the prefix length is inside the bracket, e.g. [2001:db8::/120].
+        Some((ip_part, prefix_part)) if ip_part.starts_with('[') => (format!("{}]", ip_part), Some(prefix_part.trim_end_matches(']'))),
+        Some((ip_part, prefix_part)) => (ip_part.to_string(), Some(prefix_part)),
+        None => (ip_str.to_string(), None),
+    };
+
+    // Reuse the single target parser, so the IP validation as well as the domain name check are the same.
+    let ip = parse_ping_target(&ip_str)?.ip();
+
+    let prefix_len_str = match prefix_len_str {
+        Some(prefix_len_str) => prefix_len_str,
+        None => return Ok((Box::new(std::iter::once(ip)), 1)),
+    };
+
+    let max_prefix_len = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix_len = match u32::from_str(prefix_len_str) {
+        Ok(prefix_len) if prefix_len <= max_prefix_len => prefix_len,
+        _ => return Err(format!("Invalid prefix length \"{}\" found in ping target \"{}\"", prefix_len_str, input)),
+    };
+
+    return Ok(expand_cidr(ip, prefix_len));
+}
+
+// Expand the CIDR into the host addresses in it, along with the address count.
+fn expand_cidr(ip: IpAddr, prefix_len: u32) -> (Box<dyn Iterator<Item = IpAddr>>, u128) {
+    return match ip {
+        IpAddr::V4(ip) => {
+            let host_mask = u32::MAX.checked_shr(prefix_len).unwrap_or(0);
+            let mut first = u32::from(ip) & !host_mask;
+            let mut last = first | host_mask;
+
+            // Network and broadcast addresses are not pingable hosts, except for /31 and /32.
+            if prefix_len <= 30 {
+                first += 1;
+                last -= 1;
+            }
+
+            (Box::new((first..=last).map(|x| IpAddr::V4(Ipv4Addr::from(x)))), (last - first) as u128 + 1)
+        }
+        IpAddr::V6(ip) => {
+            let host_mask = u128::MAX.checked_shr(prefix_len).unwrap_or(0);
+            let first = u128::from(ip) & !host_mask;
+            let last = first | host_mask;
+
+            // The address count can be larger than u128::MAX when prefix length is 0, so we saturate it, which fails
+            // the target count check anyway.
+            (Box::new((first..=last).map(|x| IpAddr::V6(Ipv6Addr::from(x)))), host_mask.saturating_add(1))
+        }
+    };
+}
+
+/// Parse a list of source IPs separated by comma. Each one can be an IP or a CIDR, such as `10.0.0.2`, `10.0.1.0/29`,
+/// `2001:db8::2` or `2001:db8::/126`.
+pub fn parse_source_ips(input: &str) -> Result<Vec<IpAddr>, String> {
+    let mut source_ips = Vec::new();
+
+    for source_ip_str in input.split(',') {
+        let source_ip_str = source_ip_str.trim();
+        let (ip_str, prefix_len_str) = match source_ip_str.split_once('/') {
+            Some((ip_str, prefix_len_str)) => (ip_str, Some(prefix_len_str)),
+            None => (source_ip_str, None),
+        };
+
+        let ip = IpAddr::from_str(ip_str).map_err(|_| format!("Invalid source IP \"{}\" found in \"{}\"", source_ip_str, input))?;
+        let (ips, ip_count) = match prefix_len_str {
+            Some(prefix_len_str) => {
+                let max_prefix_len = if ip.is_ipv4() { 32 } else { 128 };
+                match u32::from_str(prefix_len_str) {
+                    Ok(prefix_len) if prefix_len <= max_prefix_len => expand_cidr(ip, prefix_len),
+                    _ => return Err(format!("Invalid prefix length \"{}\" found in source IP \"{}\"", prefix_len_str, source_ip_str)),
+                }
+            }
+            None => (Box::new(std::iter::once(ip)) as Box<dyn Iterator<Item = IpAddr>>, 1),
+        };
+
+        if ip_count + source_ips.len() as u128 > RNP_MAX_SOURCE_IP_COUNT {
+            return Err(format!(
+                "Too many source IPs specified in \"{}\". At most {} source IPs can be used at the same time.",
+                input, RNP_MAX_SOURCE_IP_COUNT
+            ));
+        }
+
+        source_ips.extend(ips);
+    }
+
+    return Ok(source_ips);
+}
+
+/// Parse the HTTP header in "Name: Value" format. The name must be a token and the value cannot contain control characters
+/// other than tab, so the header cannot inject CR/LF to add more headers or even another request.
+pub fn parse_http_header(input: &str) -> Result<String, String> {
+    let invalid_http_header_error = || {
+        format!(
+            "Invalid HTTP header \"{}\". Expected format: \"Name: Value\", where the name is a token and the value contains no control characters",
+            input.escape_debug()
+        )
+    };
+
+    let (name, value) = input.split_once(':').ok_or_else(invalid_http_header_error)?;
+    let (name, value) = (name.trim(), value.trim());
+
+    let is_name_valid = !name.is_empty() && name.chars().all(is_http_token_char);
+    let is_value_valid = value.chars().all(|c| c == '\t' || !c.is_control());
+    if !is_name_valid || !is_value_valid {
+        return Err(invalid_http_header_error());
+    }
+
+    return Ok(format!("{}: {}", name, value));
+}
+
+// Token characters defined in RFC 7230, section 3.2.6.
+fn is_http_token_char(c: char) -> bool {
+    return c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+}
+
+/// Parse the ping rate in pings per second, e.g. "100" or "100/s".
+pub fn parse_ping_rate(input: &str) -> Result<f64, String> {
+    let rate_str = input.trim();
+    let rate_str = rate_str.strip_suffix("/s").unwrap_or(rate_str);
+    return match rate_str.trim().parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate >= RNP_MIN_PING_RATE => Ok(rate),
+        _ => Err(format!(
+            "Invalid ping rate \"{}\". Expected a number of pings per second no less than {}, e.g. \"100\" or \"100/s\"",
+            input, RNP_MIN_PING_RATE
+        )),
+    };
+}
+
+/// Parse the DSCP value, either as a number from 0 to 63, or as a class name, e.g. "ef", "af41", "cs1" or "be".
+pub fn parse_dscp(input: &str) -> Result<u8, String> {
+    let invalid_dscp_error =
+        || format!("Invalid DSCP \"{}\". Expected a number from 0 to 63 or a class name, e.g. \"ef\", \"af41\" or \"cs1\"", input);
+
+    let dscp_str = input.trim().to_lowercase();
+    if let Ok(dscp) = u8::from_str(&dscp_str) {
+        return if dscp <= 63 { Ok(dscp) } else { Err(invalid_dscp_error()) };
+    }
+
+    // Class selectors (CSx) take the 3 high bits, while assured forwarding (AFxy) adds the drop precedence after them.
+    let (class_name, class_digits) = dscp_str.split_at(dscp_str.find(|c: char| c.is_ascii_digit()).unwrap_or(dscp_str.len()));
+    let digits: Vec<u8> =
+        class_digits.chars().map(|c| c.to_digit(10).map(|d| d as u8)).collect::<Option<Vec<u8>>>().ok_or_else(invalid_dscp_error)?;
+    return match (class_name, digits.as_slice()) {
+        ("be", []) => Ok(0),
+        ("ef", []) => Ok(46),
+        ("cs", [class]) if *class <= 7 => Ok(class << 3),
+        ("af", [class, drop_precedence]) if (1..=4).contains(class) && (1..=3).contains(drop_precedence) => Ok((class << 3) | (drop_precedence << 1)),
+        _ => Err(invalid_dscp_error()),
+    };
+}
+
+/// Parse the firewall mark, either in decimal or in hex with "0x" prefix, e.g. "100" or "0x64".
+pub fn parse_fwmark(input: &str) -> Result<u32, String> {
+    let fwmark_str = input.trim().to_lowercase();
+    let fwmark = match fwmark_str.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => u32::from_str(&fwmark_str),
+    };
+
+    return fwmark.map_err(|_| format!("Invalid firewall mark \"{}\". Expected a 32-bit number, e.g. \"100\" or \"0x64\"", input));
+}
+
+/// Parse a duration made of one or more numbers with units, e.g. "500ms", "30s", "10m", "1h30m" or "1d". A number without
+/// unit is treated as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let invalid_duration_error = || format!("Invalid duration \"{}\". Expected a positive duration, e.g. \"30s\", \"10m\" or \"1h30m\"", input);
+
+    let duration_str = input.trim();
+    if let Ok(seconds) = u64::from_str(duration_str) {
+        return if seconds > 0 { Ok(Duration::from_secs(seconds)) } else { Err(invalid_duration_error()) };
+    }
+
+    let mut duration = Duration::ZERO;
+    let mut remaining = duration_str;
+    while !remaining.is_empty() {
+        let number_len = remaining.find(|c: char| !c.is_ascii_digit()).unwrap_or(remaining.len());
+        let unit_len = remaining[number_len..].find(|c: char| c.is_ascii_digit()).unwrap_or(remaining.len() - number_len);
+        let number = u64::from_str(&remaining[..number_len]).map_err(|_| invalid_duration_error())?;
+        let unit_in_ms = match &remaining[number_len..number_len + unit_len] {
+            "ms" => 1,
+            "s" => 1000,
+            "m" => 60 * 1000,
+            "h" => 60 * 60 * 1000,
+            "d" => 24 * 60 * 60 * 1000,
+            _ => return Err(invalid_duration_error()),
+        };
+
+        let part_in_ms = number.checked_mul(unit_in_ms).ok_or_else(invalid_duration_error)?;
+        duration = duration.checked_add(Duration::from_millis(part_in_ms)).ok_or_else(invalid_duration_error)?;
+        remaining = &remaining[number_len + unit_len..];
+    }
+
+    if duration.is_zero() {
+        return Err(invalid_duration_error());
+    }
+
+    return Ok(duration);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,4 +422,168 @@ mod tests {
         assert!(parse_ping_target("www.google.com").is_err());
         assert!(parse_ping_target("www.google.com:443").is_err());
     }
+
+    #[test]
+    fn parsing_ping_targets_should_work() {
+        assert_eq!(Ok(vec!["10.0.0.1:80".parse().unwrap()]), parse_ping_targets("10.0.0.1"));
+        assert_eq!(Ok(vec!["10.0.0.1:443".parse().unwrap(), "[::1]:80".parse().unwrap()]), parse_ping_targets("10.0.0.1:443, [::1]"));
+        assert_eq!(
+            Ok(vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.1:444".parse().unwrap(), "10.0.0.1:445".parse().unwrap()]),
+            parse_ping_targets("10.0.0.1:443-445")
+        );
+        assert_eq!(Ok(vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.2:443".parse().unwrap()]), parse_ping_targets("10.0.0.0/30:443"));
+        assert_eq!(Ok(vec!["10.0.0.2:80".parse().unwrap(), "10.0.0.3:80".parse().unwrap()]), parse_ping_targets("10.0.0.3/31"));
+        assert_eq!(Ok(vec!["10.0.0.3:80".parse().unwrap()]), parse_ping_targets("10.0.0.3/32:"));
+        assert_eq!(14, parse_ping_targets("10.0.0.0/28:443").unwrap().len());
+        assert_eq!(
+            Ok(vec![
+                "[2001:db8::]:443".parse().unwrap(),
+                "[2001:db8::]:444".parse().unwrap(),
+                "[2001:db8::1]:443".parse().unwrap(),
+                "[2001:db8::1]:444".parse().unwrap()
+            ]),
+            parse_ping_targets("[2001:db8::1/127]:443-444")
+        );
+        assert_eq!(Ok(vec!["[2001:db8::1]:80".parse().unwrap()]), parse_ping_targets("[2001:db8::1/128]"));
+
+        assert!(parse_ping_targets("").is_err());
+        assert!(parse_ping_targets("10.0.0.1,").is_err());
+        assert!(parse_ping_targets("10.0.0.1:443-").is_err());
+        assert!(parse_ping_targets("10.0.0.1:445-443").is_err());
+        assert!(parse_ping_targets("10.0.0.0/33").is_err());
+        assert!(parse_ping_targets("10.0.0.0/").is_err());
+        assert!(parse_ping_targets("[2001:db8::/129]").is_err());
+        assert!(parse_ping_targets("[2001:db8::1]443").is_err());
+        assert!(parse_ping_targets("10.0.0.0/8").is_err());
+        assert!(parse_ping_targets("[2001:db8::/0]").is_err());
+        assert!(parse_ping_targets("www.google.com/24").is_err());
+        assert!(parse_ping_targets("www.google.com:443,10.0.0.1").is_err());
+    }
+
+    #[test]
+    fn loading_ping_targets_from_file_should_work() {
+        let targets_file_path = std::env::temp_dir().join("rnp_utils_test_targets.txt");
+        fs::write(&targets_file_path, "# Backends\n10.0.0.1:443\n\n10.0.0.2:443-444, [::1]:443 # Local\n").unwrap();
+
+        assert_eq!(
+            Ok(vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.2:443".parse().unwrap(), "10.0.0.2:444".parse().unwrap(), "[::1]:443".parse().unwrap()]),
+            load_ping_targets_from_file(&targets_file_path)
+        );
+
+        assert!(load_ping_targets_from_file(&std::env::temp_dir().join("rnp_utils_test_targets_not_exist.txt")).is_err());
+    }
+
+    #[test]
+    fn parsing_http_header_should_work() {
+        assert_eq!(Ok(String::from("X-Probe: rnp")), parse_http_header("X-Probe: rnp"));
+        assert_eq!(Ok(String::from("X-Probe: rnp")), parse_http_header(" X-Probe :rnp "));
+        assert_eq!(Ok(String::from("X-Probe: ")), parse_http_header("X-Probe:"));
+        assert_eq!(Ok(String::from("X-Time: 10:00")), parse_http_header("X-Time: 10:00"));
+
+        assert!(parse_http_header("").is_err());
+        assert!(parse_http_header("X-Probe").is_err());
+        assert!(parse_http_header(": rnp").is_err());
+    }
+
+    #[test]
+    fn parsing_http_header_with_invalid_name_should_fail() {
+        assert!(parse_http_header("X Probe: rnp").is_err());
+        assert!(parse_http_header("X-Probe\r\nHost: rnp").is_err());
+        assert!(parse_http_header("GET / HTTP/1.1\r\nX-Probe: rnp").is_err());
+        assert!(parse_http_header("X-Pröbe: rnp").is_err());
+        assert!(parse_http_header("X-(Probe): rnp").is_err());
+    }
+
+    #[test]
+    fn parsing_http_header_with_control_characters_in_value_should_fail() {
+        assert_eq!(Ok(String::from("X-Probe: a\tb")), parse_http_header("X-Probe: a\tb"));
+        assert!(parse_http_header("X-Probe: rnp\r\nHost: example.com").is_err());
+        assert!(parse_http_header("X-Probe: rnp\r\n\r\nGET / HTTP/1.1").is_err());
+        assert!(parse_http_header("X-Probe: rnp\nHost: example.com").is_err());
+        assert!(parse_http_header("X-Probe: rnp\0").is_err());
+    }
+
+    #[test]
+    fn parsing_source_ips_should_work() {
+        assert_eq!(Ok(vec!["10.0.0.2".parse().unwrap()]), parse_source_ips("10.0.0.2"));
+        assert_eq!(Ok(vec!["10.0.0.2".parse().unwrap(), "2001:db8::2".parse().unwrap()]), parse_source_ips("10.0.0.2, 2001:db8::2"));
+        assert_eq!(Ok(vec!["10.0.1.1".parse().unwrap(), "10.0.1.2".parse().unwrap()]), parse_source_ips("10.0.1.0/30"));
+        assert_eq!(
+            Ok(vec!["2001:db8::".parse().unwrap(), "2001:db8::1".parse().unwrap(), "2001:db8::2".parse().unwrap(), "2001:db8::3".parse().unwrap()]),
+            parse_source_ips("2001:db8::/126")
+        );
+
+        assert!(parse_source_ips("").is_err());
+        assert!(parse_source_ips("10.0.0.2,").is_err());
+        assert!(parse_source_ips("10.0.0.2:80").is_err());
+        assert!(parse_source_ips("[2001:db8::2]").is_err());
+        assert!(parse_source_ips("10.0.0.0/33").is_err());
+        assert!(parse_source_ips("10.0.0.0/16").is_err());
+    }
+
+    #[test]
+    fn parsing_ping_rate_should_work() {
+        assert_eq!(Ok(100.0), parse_ping_rate("100"));
+        assert_eq!(Ok(100.0), parse_ping_rate("100/s"));
+        assert_eq!(Ok(0.5), parse_ping_rate(" 0.5/s "));
+
+        assert!(parse_ping_rate("").is_err());
+        assert!(parse_ping_rate("/s").is_err());
+        assert!(parse_ping_rate("0").is_err());
+        assert!(parse_ping_rate("-1/s").is_err());
+        assert!(parse_ping_rate("1e-300").is_err());
+        assert!(parse_ping_rate("0.0009/s").is_err());
+        assert_eq!(Ok(0.001), parse_ping_rate("0.001"));
+        assert!(parse_ping_rate("100/m").is_err());
+    }
+
+    #[test]
+    fn parsing_dscp_should_work() {
+        assert_eq!(Ok(0), parse_dscp("0"));
+        assert_eq!(Ok(63), parse_dscp("63"));
+        assert_eq!(Ok(0), parse_dscp("be"));
+        assert_eq!(Ok(46), parse_dscp("EF"));
+        assert_eq!(Ok(8), parse_dscp("cs1"));
+        assert_eq!(Ok(34), parse_dscp("af41"));
+        assert_eq!(Ok(14), parse_dscp("af13"));
+
+        assert!(parse_dscp("").is_err());
+        assert!(parse_dscp("64").is_err());
+        assert!(parse_dscp("cs8").is_err());
+        assert!(parse_dscp("af51").is_err());
+        assert!(parse_dscp("af14").is_err());
+        assert!(parse_dscp("ef1").is_err());
+        assert!(parse_dscp("x").is_err());
+    }
+
+    #[test]
+    fn parsing_fwmark_should_work() {
+        assert_eq!(Ok(100), parse_fwmark("100"));
+        assert_eq!(Ok(100), parse_fwmark("0x64"));
+        assert_eq!(Ok(u32::MAX), parse_fwmark("0xFFFFFFFF"));
+
+        assert!(parse_fwmark("").is_err());
+        assert!(parse_fwmark("0x").is_err());
+        assert!(parse_fwmark("-1").is_err());
+        assert!(parse_fwmark("0x100000000").is_err());
+    }
+
+    #[test]
+    fn parsing_duration_should_work() {
+        assert_eq!(Ok(Duration::from_secs(30)), parse_duration("30"));
+        assert_eq!(Ok(Duration::from_millis(500)), parse_duration("500ms"));
+        assert_eq!(Ok(Duration::from_secs(30)), parse_duration(" 30s "));
+        assert_eq!(Ok(Duration::from_secs(600)), parse_duration("10m"));
+        assert_eq!(Ok(Duration::from_secs(5400)), parse_duration("1h30m"));
+        assert_eq!(Ok(Duration::from_millis(86_401_500)), parse_duration("1d1s500ms"));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("-10s").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("10 m").is_err());
+    }
 }
@@ -1,2 +1,5 @@
 pub mod stub_server_factory;
+#[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+mod stub_server_quic;
 mod stub_server_tcp;
+mod stub_server_udp;
@@ -1,4 +1,7 @@
+#[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+use crate::stub_servers::stub_server_quic::StubServerQuic;
 use crate::stub_servers::stub_server_tcp::StubServerTcp;
+use crate::stub_servers::stub_server_udp::StubServerUdp;
 use crate::*;
 use contracts::requires;
 use futures_intrusive::sync::ManualResetEvent;
@@ -17,6 +20,9 @@ pub fn run(
 
     match config.protocol {
         RnpSupportedProtocol::TCP => return StubServerTcp::run_new(config.clone(), stop_event, server_started_event),
+        RnpSupportedProtocol::UDP => return StubServerUdp::run_new(config.clone(), stop_event, server_started_event),
+        #[cfg(any(not(target_os = "windows"), not(target_arch = "aarch64")))]
+        RnpSupportedProtocol::QUIC => return StubServerQuic::run_new(config.clone(), stop_event, server_started_event),
         _ => panic!("Protocol {} is not supported!", config.protocol),
     }
 }
@@ -0,0 +1,380 @@
+use crate::RnpStubServerConfig;
+use futures::StreamExt;
+use futures_intrusive::sync::ManualResetEvent;
+use quinn::{CertificateChain, Connecting, Endpoint, NewConnection, PrivateKey, RecvStream, SendStream, ServerConfig, ServerConfigBuilder};
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+const QUIC_STUB_SERVER_SELF_SIGNED_CERT_SUBJECT_NAME: &str = "localhost";
+
+pub struct StubServerQuic {
+    config: Arc<RnpStubServerConfig>,
+    stop_event: Arc<ManualResetEvent>,
+    server_started_event: Arc<ManualResetEvent>,
+
+    next_conn_id: u32,
+    conn_stats_map: HashMap<u32, Arc<Mutex<StubServerQuicConnectionStats>>>,
+}
+
+impl StubServerQuic {
+    #[tracing::instrument(name = "Start running new QUIC stub server", level = "debug", skip(stop_event))]
+    pub fn run_new(
+        config: RnpStubServerConfig,
+        stop_event: Arc<ManualResetEvent>,
+        server_started_event: Arc<ManualResetEvent>,
+    ) -> JoinHandle<Result<(), Box<dyn Error + Send + Sync>>> {
+        return tokio::spawn(async move {
+            let mut server = StubServerQuic::new(config, stop_event, server_started_event.clone());
+
+            // In case server started failed, we always signal server started event here to keep it safe.
+            let result = server.run().await;
+            server_started_event.set();
+
+            return result;
+        });
+    }
+
+    #[tracing::instrument(name = "Creating QUIC stub server", level = "debug", skip(stop_event))]
+    fn new(config: RnpStubServerConfig, stop_event: Arc<ManualResetEvent>, server_started_event: Arc<ManualResetEvent>) -> StubServerQuic {
+        return StubServerQuic { config: Arc::new(config), stop_event, server_started_event, next_conn_id: 0, conn_stats_map: HashMap::new() };
+    }
+
+    #[tracing::instrument(name = "Running QUIC stub server loop", level = "debug", skip(self))]
+    async fn run(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let server_config = self.create_server_config()?;
+
+        let mut endpoint_builder = Endpoint::builder();
+        endpoint_builder.listen(server_config);
+        let (endpoint, mut incoming) = endpoint_builder.bind(&self.config.server_address)?;
+        self.server_started_event.set();
+
+        println!("Rnp {} server started successfully at {}.", self.config.protocol, self.config.server_address);
+
+        let mut next_report_time = Instant::now();
+        loop {
+            tokio::select! {
+                // New connection arrived.
+                connecting = incoming.next() => {
+                    match connecting {
+                        Some(connecting) => self.handle_new_connection(connecting).await,
+                        None => {
+                            println!("QUIC endpoint is closed. Exit.");
+                            break;
+                        }
+                    }
+                }
+
+                // Report interval reached
+                _ = tokio::time::sleep_until(next_report_time) => {
+                    self.report_and_reset_conn_stats();
+                    next_report_time += self.config.report_interval;
+                }
+
+                // Stopped
+                _ = self.stop_event.wait() => {
+                    break;
+                }
+            }
+        }
+
+        endpoint.close(0u32.into(), b"");
+        return Ok(());
+    }
+
+    fn create_server_config(&self) -> Result<ServerConfig, Box<dyn Error + Send + Sync>> {
+        let (cert_chain, private_key) = self.load_server_certificate()?;
+
+        let mut server_config_builder = ServerConfigBuilder::default();
+        server_config_builder.certificate(cert_chain, private_key)?;
+
+        // When ALPN protocols are specified, the clients that don't support any of them will fail in handshake.
+        if self.config.alpn_protocols.len() > 0 {
+            let protocols: Vec<&[u8]> = self.config.alpn_protocols.iter().map(|p| p.as_bytes()).collect();
+            server_config_builder.protocols(&protocols);
+        }
+
+        return Ok(server_config_builder.build());
+    }
+
+    #[tracing::instrument(name = "Loading QUIC stub server certificate", level = "debug", skip(self))]
+    fn load_server_certificate(&self) -> Result<(CertificateChain, PrivateKey), Box<dyn Error + Send + Sync>> {
+        let (cert_path, key_path) = match (&self.config.server_certificate_path, &self.config.server_private_key_path) {
+            (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+            (None, None) => return StubServerQuic::generate_self_signed_certificate(),
+            _ => return Err("Server certificate and private key must be specified together.".into()),
+        };
+
+        println!("Loading server certificate: Certificate = {}, PrivateKey = {}", cert_path.display(), key_path.display());
+        let cert = std::fs::read(cert_path)?;
+        let key = std::fs::read(key_path)?;
+
+        // Files with .der extension are treated as DER encoded, and all others are treated as PEM encoded.
+        let is_der = |path: &std::path::PathBuf| path.extension().map_or(false, |ext| ext == "der");
+        let cert_chain =
+            if is_der(cert_path) { CertificateChain::from_certs(quinn::Certificate::from_der(&cert)) } else { CertificateChain::from_pem(&cert)? };
+        let private_key = if is_der(key_path) { PrivateKey::from_der(&key)? } else { PrivateKey::from_pem(&key)? };
+
+        return Ok((cert_chain, private_key));
+    }
+
+    fn generate_self_signed_certificate() -> Result<(CertificateChain, PrivateKey), Box<dyn Error + Send + Sync>> {
+        println!("No server certificate specified, generating self-signed certificate for {}.", QUIC_STUB_SERVER_SELF_SIGNED_CERT_SUBJECT_NAME);
+
+        let cert = rcgen::generate_simple_self_signed(vec![QUIC_STUB_SERVER_SELF_SIGNED_CERT_SUBJECT_NAME.to_string()])?;
+        let cert_chain = CertificateChain::from_certs(quinn::Certificate::from_der(&cert.serialize_der()?));
+        let private_key = PrivateKey::from_der(&cert.serialize_private_key_der())?;
+        return Ok((cert_chain, private_key));
+    }
+
+    #[tracing::instrument(name = "New connection received", level = "debug", skip(self, connecting))]
+    async fn handle_new_connection(&mut self, connecting: Connecting) {
+        let peer_addr = connecting.remote_address();
+        println!("New connection received: Remote = {}", peer_addr);
+
+        let conn_config = self.config.clone();
+        let conn_stop_event = self.stop_event.clone();
+
+        let conn_id = self.next_conn_id;
+        self.next_conn_id += 1;
+
+        let conn_stats = Arc::new(Mutex::new(StubServerQuicConnectionStats::new(&peer_addr)));
+        self.conn_stats_map.insert(conn_id, conn_stats.clone());
+
+        // Handshake is done in the connection worker, so slow or broken clients won't block the accept loop.
+        tokio::spawn(async move {
+            let mut worker = StubServerQuicConnection::new(conn_id, conn_config, peer_addr, conn_stats);
+            tokio::select! {
+                _ = worker.run(connecting) => { return; }
+                _ = conn_stop_event.wait() => { return; }
+            }
+        });
+    }
+
+    #[tracing::instrument(name = "Report and reset connection stats", level = "debug", skip(self))]
+    fn report_and_reset_conn_stats(&mut self) {
+        if self.conn_stats_map.len() == 0 {
+            return;
+        }
+
+        println!("========== Connection Stats ==========");
+        for (id, conn_stats) in &self.conn_stats_map {
+            let conn_stats = conn_stats.lock().unwrap().clone_and_clear_stats();
+            let read_bps = conn_stats.bytes_read * 8 * 1000 / (self.config.report_interval.as_millis() as usize);
+            let write_bps = conn_stats.bytes_write * 8 * 1000 / (self.config.report_interval.as_millis() as usize);
+            println!(
+                "[{}] {} => ALPN = {}, Streams = {}, Read = {} bytes ({} bps), Write = {} bytes ({} bps)",
+                id,
+                conn_stats.remote_address,
+                conn_stats.alpn_protocol.as_deref().unwrap_or("-"),
+                conn_stats.streams_accepted,
+                conn_stats.bytes_read,
+                read_bps,
+                conn_stats.bytes_write,
+                write_bps
+            );
+        }
+        println!();
+
+        // We clean up the dead connections after reporting, otherwise we will miss the stats in the last round of report.
+        self.conn_stats_map.retain(|_, v| v.lock().unwrap().is_alive);
+    }
+}
+
+struct StubServerQuicConnection {
+    id: u32,
+    config: Arc<RnpStubServerConfig>,
+    remote_address: SocketAddr,
+    conn_stats: Arc<Mutex<StubServerQuicConnectionStats>>,
+}
+
+impl StubServerQuicConnection {
+    #[tracing::instrument(name = "Creating new QUIC connection worker", level = "debug", skip(conn_stats))]
+    fn new(
+        id: u32,
+        config: Arc<RnpStubServerConfig>,
+        remote_address: SocketAddr,
+        conn_stats: Arc<Mutex<StubServerQuicConnectionStats>>,
+    ) -> StubServerQuicConnection {
+        return StubServerQuicConnection { id, config, remote_address, conn_stats };
+    }
+
+    #[tracing::instrument(name = "Running new QUIC connection worker", level = "debug", skip(self, connecting), fields(id = %self.id, remote_address = %self.remote_address))]
+    async fn run(&mut self, connecting: Connecting) {
+        self.run_loop(connecting).await;
+        self.conn_stats.lock().unwrap().is_alive = false;
+    }
+
+    async fn run_loop(&mut self, connecting: Connecting) {
+        let NewConnection { connection, mut bi_streams, mut uni_streams, .. } = match connecting.await {
+            Ok(new_connection) => new_connection,
+            Err(e) => {
+                println!("Failed to complete handshake with {}, connection closed: Error = {}", self.remote_address, e);
+                return;
+            }
+        };
+
+        let alpn_protocol = connection.handshake_data().and_then(|data| data.protocol).map(|p| String::from_utf8_lossy(&p).into_owned());
+        println!("Connection established: Remote = {}, ALPN = {}", self.remote_address, alpn_protocol.as_deref().unwrap_or("-"));
+        self.conn_stats.lock().unwrap().alpn_protocol = alpn_protocol;
+
+        if self.config.close_on_accept {
+            connection.close(0u32.into(), b"");
+            println!("Connection closed on accept: Remote = {}", self.remote_address);
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                bi_stream = bi_streams.next() => {
+                    match bi_stream {
+                        Some(Ok((send_stream, recv_stream))) => self.start_stream_worker(Some(send_stream), recv_stream),
+                        Some(Err(e)) => {
+                            println!("Connection to {} is closed: Reason = {}", self.remote_address, e);
+                            return;
+                        }
+                        None => return,
+                    }
+                }
+
+                uni_stream = uni_streams.next() => {
+                    match uni_stream {
+                        Some(Ok(recv_stream)) => self.start_stream_worker(None, recv_stream),
+                        Some(Err(e)) => {
+                            println!("Connection to {} is closed: Reason = {}", self.remote_address, e);
+                            return;
+                        }
+                        None => return,
+                    }
+                }
+            }
+        }
+    }
+
+    fn start_stream_worker(&mut self, send_stream: Option<SendStream>, recv_stream: RecvStream) {
+        self.conn_stats.lock().unwrap().streams_accepted += 1;
+
+        let config = self.config.clone();
+        let remote_address = self.remote_address;
+        let conn_stats = self.conn_stats.clone();
+        tokio::spawn(async move {
+            let mut worker = StubServerQuicStream { config, remote_address, conn_stats };
+            worker.run(send_stream, recv_stream).await;
+        });
+    }
+}
+
+struct StubServerQuicStream {
+    config: Arc<RnpStubServerConfig>,
+    remote_address: SocketAddr,
+    conn_stats: Arc<Mutex<StubServerQuicConnectionStats>>,
+}
+
+impl StubServerQuicStream {
+    async fn run(&mut self, send_stream: Option<SendStream>, recv_stream: RecvStream) {
+        let mut send_stream = match send_stream {
+            Some(send_stream) => send_stream,
+            None => {
+                let _ = self.on_stream_read(recv_stream).await;
+                return;
+            }
+        };
+
+        let (read_result, write_result) = tokio::join!(self.on_stream_read(recv_stream), self.on_stream_write(&mut send_stream));
+        if read_result.is_err() || write_result.is_err() {
+            return;
+        }
+
+        if !self.config.wait_before_disconnect.is_zero() {
+            println!(
+                "Stream is finished by remote side. Wait for {:?} before finishing the stream: Remote = {}",
+                self.config.wait_before_disconnect, self.remote_address
+            );
+            tokio::time::sleep(self.config.wait_before_disconnect).await;
+        }
+
+        let _ = send_stream.finish().await;
+    }
+
+    async fn on_stream_read(&self, mut recv_stream: RecvStream) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut read_buf = vec![0 as u8; 4096];
+        loop {
+            match recv_stream.read(&mut read_buf).await {
+                Ok(Some(n)) => self.conn_stats.lock().unwrap().bytes_read += n,
+                Ok(None) => return Ok(()),
+                Err(e) => {
+                    println!("Error found in stream from {}, stream closed: Error = {}", self.remote_address, e);
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+
+    async fn on_stream_write(&self, send_stream: &mut SendStream) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.config.write_chunk_size == 0 {
+            return Ok(());
+        }
+
+        let write_buf = vec![0 as u8; self.config.write_chunk_size];
+        loop {
+            if !self.config.sleep_before_write.is_zero() {
+                tokio::time::sleep(self.config.sleep_before_write).await;
+            }
+
+            // Update write count
+            {
+                let mut conn_stats = self.conn_stats.lock().unwrap();
+                if self.config.write_count_limit != 0 && conn_stats.total_write_count >= self.config.write_count_limit {
+                    return Ok(());
+                };
+                conn_stats.total_write_count += 1;
+            }
+
+            if let Err(e) = send_stream.write_all(&write_buf).await {
+                println!("Error found in stream to {}, stream closed: Error = {}", self.remote_address, e);
+                return Err(e.into());
+            }
+            self.conn_stats.lock().unwrap().bytes_write += write_buf.len();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct StubServerQuicConnectionStats {
+    pub remote_address: SocketAddr,
+    pub alpn_protocol: Option<String>,
+    pub is_alive: bool,
+    pub streams_accepted: usize,
+    pub bytes_read: usize,
+    pub bytes_write: usize,
+    pub total_write_count: u32,
+}
+
+impl StubServerQuicConnectionStats {
+    pub fn new(remote_address: &SocketAddr) -> StubServerQuicConnectionStats {
+        return StubServerQuicConnectionStats {
+            remote_address: remote_address.clone(),
+            alpn_protocol: None,
+            is_alive: true,
+            streams_accepted: 0,
+            bytes_read: 0,
+            bytes_write: 0,
+            total_write_count: 0,
+        };
+    }
+
+    pub fn clone_and_clear_stats(&mut self) -> StubServerQuicConnectionStats {
+        let stats = self.clone();
+        self.clear_stats();
+        return stats;
+    }
+
+    pub fn clear_stats(&mut self) {
+        self.streams_accepted = 0;
+        self.bytes_write = 0;
+        self.bytes_read = 0;
+    }
+}
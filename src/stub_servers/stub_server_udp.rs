@@ -0,0 +1,165 @@
+use crate::RnpStubServerConfig;
+use futures_intrusive::sync::ManualResetEvent;
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+pub struct StubServerUdp {
+    config: Arc<RnpStubServerConfig>,
+    stop_event: Arc<ManualResetEvent>,
+    server_started_event: Arc<ManualResetEvent>,
+
+    peer_stats_map: Arc<Mutex<HashMap<SocketAddr, StubServerUdpPeerStats>>>,
+}
+
+impl StubServerUdp {
+    #[tracing::instrument(name = "Start running new UDP stub server", level = "debug", skip(stop_event))]
+    pub fn run_new(
+        config: RnpStubServerConfig,
+        stop_event: Arc<ManualResetEvent>,
+        server_started_event: Arc<ManualResetEvent>,
+    ) -> JoinHandle<Result<(), Box<dyn Error + Send + Sync>>> {
+        return tokio::spawn(async move {
+            let mut server = StubServerUdp::new(config, stop_event, server_started_event.clone());
+
+            // In case server started failed, we always signal server started event here to keep it safe.
+            let result = server.run().await;
+            server_started_event.set();
+
+            return result;
+        });
+    }
+
+    #[tracing::instrument(name = "Creating UDP stub server", level = "debug", skip(stop_event))]
+    fn new(config: RnpStubServerConfig, stop_event: Arc<ManualResetEvent>, server_started_event: Arc<ManualResetEvent>) -> StubServerUdp {
+        return StubServerUdp { config: Arc::new(config), stop_event, server_started_event, peer_stats_map: Arc::new(Mutex::new(HashMap::new())) };
+    }
+
+    #[tracing::instrument(name = "Running UDP stub server loop", level = "debug", skip(self))]
+    async fn run(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let socket = Arc::new(UdpSocket::bind(self.config.server_address).await?);
+        self.server_started_event.set();
+
+        println!("Rnp {} server started successfully at {}.", self.config.protocol, self.config.server_address);
+
+        let mut read_buf = vec![0 as u8; 65536];
+        let mut next_report_time = Instant::now();
+        loop {
+            tokio::select! {
+                // New packet arrived.
+                recv_result = socket.recv_from(&mut read_buf) => {
+                    match recv_result {
+                        Ok((n, peer_addr)) => {
+                            self.handle_new_packet(socket.clone(), read_buf[..n].to_vec(), peer_addr).await
+                        },
+                        Err(e) => {
+                            // When the remote side is gone, the ICMP port unreachable can be reported on our socket
+                            // on some platforms, so we don't exit here.
+                            println!("Failed to receive packet: Error = {}", e);
+                        }
+                    }
+                }
+
+                // Report interval reached
+                _ = tokio::time::sleep_until(next_report_time) => {
+                    self.report_and_reset_peer_stats();
+                    next_report_time += self.config.report_interval;
+                }
+
+                // Stopped
+                _ = self.stop_event.wait() => {
+                    break;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    #[tracing::instrument(name = "New packet received", level = "debug", skip(self, socket, packet))]
+    async fn handle_new_packet(&mut self, socket: Arc<UdpSocket>, packet: Vec<u8>, peer_addr: SocketAddr) {
+        {
+            let mut peer_stats_map = self.peer_stats_map.lock().unwrap();
+            let peer_stats = peer_stats_map.entry(peer_addr).or_insert_with(|| StubServerUdpPeerStats::new(&peer_addr));
+            peer_stats.packets_read += 1;
+            peer_stats.bytes_read += packet.len();
+        }
+
+        // Echo the packet back as it is, so the ping client can match the reply with its own request.
+        if self.config.sleep_before_write.is_zero() {
+            StubServerUdp::echo_packet(&socket, &packet, &peer_addr, &self.peer_stats_map).await;
+            return;
+        }
+
+        let sleep_before_write = self.config.sleep_before_write;
+        let peer_stats_map = self.peer_stats_map.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(sleep_before_write).await;
+            StubServerUdp::echo_packet(&socket, &packet, &peer_addr, &peer_stats_map).await;
+        });
+    }
+
+    async fn echo_packet(
+        socket: &UdpSocket,
+        packet: &[u8],
+        peer_addr: &SocketAddr,
+        peer_stats_map: &Arc<Mutex<HashMap<SocketAddr, StubServerUdpPeerStats>>>,
+    ) {
+        match socket.send_to(packet, peer_addr).await {
+            Ok(n) => {
+                let mut peer_stats_map = peer_stats_map.lock().unwrap();
+                let peer_stats = peer_stats_map.entry(*peer_addr).or_insert_with(|| StubServerUdpPeerStats::new(peer_addr));
+                peer_stats.packets_write += 1;
+                peer_stats.bytes_write += n;
+            }
+            Err(e) => println!("Failed to echo packet back to {}: Error = {}", peer_addr, e),
+        }
+    }
+
+    #[tracing::instrument(name = "Report and reset peer stats", level = "debug", skip(self))]
+    fn report_and_reset_peer_stats(&mut self) {
+        let mut peer_stats_map = self.peer_stats_map.lock().unwrap();
+        if peer_stats_map.len() == 0 {
+            return;
+        }
+
+        println!("========== Peer Stats ==========");
+        for peer_stats in peer_stats_map.values() {
+            let read_bps = peer_stats.bytes_read * 8 * 1000 / (self.config.report_interval.as_millis() as usize);
+            let write_bps = peer_stats.bytes_write * 8 * 1000 / (self.config.report_interval.as_millis() as usize);
+            println!(
+                "{} => Read = {} packets, {} bytes ({} bps), Write = {} packets, {} bytes ({} bps)",
+                peer_stats.remote_address,
+                peer_stats.packets_read,
+                peer_stats.bytes_read,
+                read_bps,
+                peer_stats.packets_write,
+                peer_stats.bytes_write,
+                write_bps
+            );
+        }
+        println!();
+
+        // UDP is connectionless, so we only keep tracking the peers within each report interval.
+        peer_stats_map.clear();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct StubServerUdpPeerStats {
+    pub remote_address: SocketAddr,
+    pub packets_read: usize,
+    pub bytes_read: usize,
+    pub packets_write: usize,
+    pub bytes_write: usize,
+}
+
+impl StubServerUdpPeerStats {
+    pub fn new(remote_address: &SocketAddr) -> StubServerUdpPeerStats {
+        return StubServerUdpPeerStats { remote_address: remote_address.clone(), packets_read: 0, bytes_read: 0, packets_write: 0, bytes_write: 0 };
+    }
+}
@@ -1,6 +1,7 @@
 mod test_common;
 mod test_mocks;
 
+use futures::StreamExt;
 use futures_intrusive::sync::ManualResetEvent;
 use pretty_assertions::assert_eq;
 use rnp::*;
@@ -16,12 +17,12 @@ fn ping_with_rnp_core_should_work() {
     let actual_ping_results = Arc::new(Mutex::new(Vec::<MockPingClientResult>::new()));
     let config = create_mock_rnp_config(actual_ping_results.clone(), 6, 3, 1);
     let rt = Runtime::new().unwrap();
-    rt.block_on(async {
+    let summary = rt.block_on(async {
         let stop_event = Arc::new(ManualResetEvent::new(false));
         let mut rp = PingRunnerCore::new(config, stop_event);
         rp.run_warmup_pings().await;
         rp.start_running_normal_pings();
-        rp.join().await;
+        rp.join().await
     });
 
     let results = actual_ping_results.lock().unwrap();
@@ -39,6 +40,66 @@ fn ping_with_rnp_core_should_work() {
         ],
         *results
     );
+
+    // Warmup pings and preparation errors are not counted in the summary.
+    assert_eq!(5, summary.ping_count());
+    assert_eq!(3, summary.success_count());
+    assert_eq!(2, summary.failure_count());
+    assert_eq!(0.4, summary.loss_ratio());
+    assert_eq!(5, summary.source_port_outcomes.values().map(|x| x.ping_count).sum::<u32>());
+    assert_eq!(1, summary.source_port_outcomes.values().map(|x| x.timed_out_count).sum::<u32>());
+    assert_eq!(Duration::from_micros(12345), summary.latency_summary().min);
+    assert!(summary.exit_failure_reason.is_none());
+}
+
+#[test]
+fn ping_with_rnp_core_multiple_targets_should_work() {
+    test_common::initialize();
+
+    let actual_ping_results = Arc::new(Mutex::new(Vec::<MockPingClientResult>::new()));
+    let mut config = create_mock_rnp_config(actual_ping_results.clone(), 4, 1, 2);
+    config.worker_config.targets = vec!["10.0.0.1:443".parse().unwrap(), "10.0.0.3:443".parse().unwrap(), "10.0.0.4:443".parse().unwrap()];
+
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let stop_event = Arc::new(ManualResetEvent::new(false));
+        let mut rp = PingRunnerCore::new(config, stop_event);
+        rp.run_warmup_pings().await;
+        rp.start_running_normal_pings();
+        rp.join().await;
+    });
+
+    // Both warmup and normal ping counts are per target.
+    let results = actual_ping_results.lock().unwrap();
+    assert_eq!(15, results.len());
+}
+
+#[test]
+fn ping_with_rnp_core_result_stream_should_work() {
+    test_common::initialize();
+
+    let actual_ping_results = Arc::new(Mutex::new(Vec::<MockPingClientResult>::new()));
+    let config = create_mock_rnp_config(actual_ping_results.clone(), 6, 3, 2);
+    let rt = Runtime::new().unwrap();
+    let streamed_ping_results = rt.block_on(async {
+        let stop_event = Arc::new(ManualResetEvent::new(false));
+
+        // Use a tiny buffer, so the result processing has to wait for the stream to be consumed.
+        let (mut rp, result_stream) = PingRunnerCore::new_with_result_stream(config, stop_event, 1);
+        let join_handle = tokio::spawn(async move {
+            rp.run_warmup_pings().await;
+            rp.start_running_normal_pings();
+            rp.join().await;
+        });
+
+        let streamed_ping_results: Vec<PingResult> = result_stream.collect().await;
+        join_handle.await.unwrap();
+        streamed_ping_results
+    });
+
+    assert_eq!(9, streamed_ping_results.len());
+    assert_eq!(3, streamed_ping_results.iter().filter(|x| x.is_warmup()).count());
+    assert_eq!(actual_ping_results.lock().unwrap().len(), streamed_ping_results.len());
 }
 
 #[test]
@@ -93,21 +154,61 @@ fn ping_with_rnp_core_exit_on_fail_should_work() {
     config.result_processor_config.exit_failure_reason = Some(exit_reason.clone());
 
     let rt = Runtime::new().unwrap();
-    rt.block_on(async {
+    let summary = rt.block_on(async {
         let stop_event = Arc::new(ManualResetEvent::new(false));
         let mut rp = PingRunnerCore::new(config, stop_event);
         rp.run_warmup_pings().await;
         rp.start_running_normal_pings();
-        rp.join().await;
+        rp.join().await
     });
 
     assert!(exit_reason.lock().unwrap().is_some());
+    assert_eq!(*exit_reason.lock().unwrap(), summary.exit_failure_reason);
 
     let failed_ping_result = exit_reason.lock().unwrap();
     assert!(!failed_ping_result.as_ref().unwrap().is_succeeded);
     assert!(failed_ping_result.as_ref().unwrap().is_timed_out || !failed_ping_result.as_ref().unwrap().ping_error.is_empty());
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn ping_with_rnp_core_flow_label_should_work() {
+    test_common::initialize();
+
+    // Connecting to a listening socket succeeds without accepting it, so we don't need to run a server here.
+    let listener = std::net::TcpListener::bind("[::1]:0").unwrap();
+
+    let actual_ping_results = Arc::new(Mutex::new(Vec::<MockPingClientResult>::new()));
+    let mut config = create_mock_rnp_config(actual_ping_results, 2, 0, 1);
+    config.worker_config.targets = vec![listener.local_addr().unwrap()];
+    config.worker_config.source_ips = vec!["::1".parse().unwrap()];
+    config.worker_config.ping_client_config.flow_label = Some(PingFlowLabel::Fixed(0x12345));
+    config.worker_scheduler_config.source_ports = PortRangeList { ranges: vec![(41024..=41088)] };
+    config.external_ping_client_factory = None;
+    config.extra_ping_result_processors = vec![];
+
+    let rt = Runtime::new().unwrap();
+    let ping_results = rt.block_on(async {
+        let stop_event = Arc::new(ManualResetEvent::new(false));
+        let (mut rp, result_stream) = PingRunnerCore::new_with_result_stream(config, stop_event, 16);
+        let join_handle = tokio::spawn(async move {
+            rp.run_warmup_pings().await;
+            rp.start_running_normal_pings();
+            rp.join().await;
+        });
+
+        let ping_results: Vec<PingResult> = result_stream.collect().await;
+        join_handle.await.unwrap();
+        ping_results
+    });
+
+    assert_eq!(2, ping_results.len());
+    for ping_result in &ping_results {
+        assert!(ping_result.is_succeeded(), "Ping failed: {:?}", ping_result.error());
+        assert_eq!(Some(0x12345), ping_result.flow_label());
+    }
+}
+
 fn create_mock_rnp_config(
     actual_ping_results: Arc<Mutex<Vec<MockPingClientResult>>>,
     ping_count: u32,
@@ -117,12 +218,16 @@ fn create_mock_rnp_config(
     RnpPingRunnerConfig {
         worker_config: PingWorkerConfig {
             protocol: RnpSupportedProtocol::TCP,
-            target: "10.0.0.1:443".parse().unwrap(),
-            source_ip: "10.0.0.2".parse().unwrap(),
+            targets: vec!["10.0.0.1:443".parse().unwrap()],
+            source_ips: vec!["10.0.0.2".parse().unwrap()],
             ping_interval: Duration::from_millis(0),
             ping_client_config: PingClientConfig {
                 wait_timeout: Duration::from_millis(1000),
                 time_to_live: Some(128),
+                flow_label: None,
+                type_of_service: None,
+                interface: None,
+                fwmark: None,
                 check_disconnect: false,
                 wait_before_disconnect: Duration::ZERO,
                 disconnect_timeout: Duration::ZERO,
@@ -130,13 +235,27 @@ fn create_mock_rnp_config(
                 log_tls_key: false,
                 alpn_protocol: None,
                 use_timer_rtt: false,
+                http_method: String::from("GET"),
+                http_path: String::from("/"),
+                http_host: None,
+                http_headers: vec![],
+                http_expected_status_codes: vec![],
             },
         },
         worker_scheduler_config: PingWorkerSchedulerConfig {
             source_ports: PortRangeList { ranges: vec![(1024..=2048)] },
+            port_picking_strategy: PingPortPickingStrategy::Sequential,
+            port_cooldown: Duration::ZERO,
+            use_port_cross_product: false,
             ping_count: Some(ping_count),
+            duration: None,
             warmup_count,
             parallel_ping_count,
+            reprobe_count: 0,
+            reprobe_interval: Duration::ZERO,
+            ping_rate: None,
+            ping_rate_jitter: 0.0,
+            use_poisson_arrival: false,
         },
         result_processor_config: PingResultProcessorConfig {
             common_config: PingResultProcessorCommonConfig { quiet_level: RNP_QUIET_LEVEL_NONE },
@@ -144,10 +263,13 @@ fn create_mock_rnp_config(
             exit_failure_reason: None,
             csv_log_path: None,
             json_log_path: None,
+            json_summary_log_path: None,
             text_log_path: None,
             show_result_scatter: false,
             show_latency_scatter: false,
+            show_bad_paths: false,
             latency_buckets: None,
+            metrics_address: None,
         },
         external_ping_client_factory: Some(|_, config| {
             Some(Box::new(MockPingClient::new(